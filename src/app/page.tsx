@@ -12,6 +12,8 @@ const FONT = "Monaco, Menlo, 'Ubuntu Mono', Consolas, source-code-pro, monospace
 interface TerminalLine {
   type: 'input' | 'output' | 'error';
   content: string;
+  // the prompt an input line was typed at
+  prompt?: string;
 }
 
 // a line in the editor because we need to track line numbers
@@ -33,6 +35,12 @@ interface EditEditor {
 // wasm terminal type - using any because typescript is annoying
 type Terminal = any;
 
+// the default PS1, until the terminal renders the real one
+const DEFAULT_PROMPT = "[virt::core] ➤ ";
+
+// colour escapes from \e in PS1 - the input line can't show them, so drop them
+const stripEscapes = (text: string) => text.replace(/\x1b\[[0-9;]*[A-Za-z]/g, '');
+
 const Home = () => {
   // all the state we need to track
   const [lines, setLines] = useState<TerminalLine[]>([]);
@@ -44,6 +52,7 @@ const Home = () => {
   const [editEditor, setEditEditor] = useState<EditEditor | null>(null);
   const [history, setHistory] = useState<string[]>([]);
   const [historyIndex, setHistoryIndex] = useState<number | null>(null);
+  const [prompt, setPrompt] = useState(DEFAULT_PROMPT);
   
   // refs for when we need to mess with the dom directly
   const inputRef = useRef<HTMLInputElement>(null);
@@ -53,6 +62,22 @@ const Home = () => {
   // vfs context because we need to read files somehow
   const vfs = useVfs();
 
+  // ask the terminal for the next prompt - PS1, or PS2 while a command is
+  // unfinished. the first call runs the startup files, and whatever they
+  // print comes back above the prompt's own line
+  const refreshPrompt = (term: Terminal) => {
+    if (typeof term.render_prompt !== 'function') return;
+    const shown: string[] = term.render_prompt().split('\n');
+    const printed = shown.slice(0, -1);
+    if (printed.length > 0) {
+      setLines(prev => [
+        ...prev,
+        ...printed.map((line: string) => ({ type: 'output' as const, content: stripEscapes(line) }))
+      ]);
+    }
+    setPrompt(stripEscapes(shown[shown.length - 1]));
+  };
+
   // load the wasm module when the component mounts
   useEffect(() => {
     const init = async () => {
@@ -130,8 +155,14 @@ const Home = () => {
         if (set_async_result_callback) {
           set_async_result_callback(handleAsyncResult);
         }
+
+        // at, watch and cron only run when asked, so ask every second
+        if (typeof (term as any).tick === 'function') {
+          setInterval(() => (term as any).tick(), 1000);
+        }
         
         setTerminal(term);
+        refreshPrompt(term);
         setIsLoading(false);
       } catch (error) {
         console.error('failed to initialize wasm module:', error);
@@ -192,7 +223,7 @@ const Home = () => {
     }
 
     // regular command execution
-    setLines(prev => [...prev, { type: 'input', content: command }]);
+    setLines(prev => [...prev, { type: 'input', content: command, prompt }]);
 
     // check if this is an async command that takes forever
     const isAsyncCommand = command.trim().startsWith('ping ') || command.trim().startsWith('curl ');
@@ -200,7 +231,9 @@ const Home = () => {
     try {
       // call wasm terminal
       const response = terminal.execute_command(command);
-      
+      // PROMPT_COMMAND and $PS1 run again for the next line
+      refreshPrompt(terminal);
+
       if (response.success) {
         if (response.output) {
           // check if switching to edit mode
@@ -515,8 +548,8 @@ const Home = () => {
                       <Image alt="trifledmatter-logo" width={32} height={32} src={Logo} className="w-4 h-4" />
                       &nbsp;
                     </span>
-                    <span className="text-cyan-400 flex-shrink-0 mr-2 font-bold">
-                      [virt::core] ➤
+                    <span className="text-cyan-400 flex-shrink-0 mr-2 font-bold whitespace-pre">
+                      {line.prompt ?? DEFAULT_PROMPT}
                     </span>
                     <span className="flex-1 break-all">{line.content}</span>
                   </>
@@ -535,8 +568,8 @@ const Home = () => {
               <Image alt="trifledmatter-logo" width={32} height={32} src={Logo} className="w-4 h-4" />
               &nbsp;
             </span>
-            <span className="text-cyan-400 flex-shrink-0 mr-2 font-bold">
-              {isEditMode ? '[edit]' : '[virt::core]'} ➤
+            <span className="text-cyan-400 flex-shrink-0 mr-2 font-bold whitespace-pre">
+              {isEditMode ? '[edit] ➤ ' : prompt}
             </span>
             <input
               ref={inputRef}
@@ -1,10 +1,39 @@
 use crate::context::TerminalContext;
+use crate::shell::exec::Output;
 use std::collections::HashMap;
 
 pub type CommandResult = Result<String, String>;
 
 pub trait Command {
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult;
+
+    /// same as `execute` but with piped input (`a | b` gives b a's output)
+    /// commands that can read stdin override this, the rest just ignore it
+    fn execute_with_stdin(&self, args: &[String], stdin: Option<&str>, ctx: &mut TerminalContext) -> CommandResult {
+        let _ = stdin;
+        self.execute(args, ctx)
+    }
+
+    /// builtins that run shell code in the current shell (source, eval)
+    /// write straight into the caller's output, so what that code sends to
+    /// stdout and stderr stays apart, and leave their status in
+    /// ctx.exit_code. false means execute_with_stdin does the work instead
+    fn execute_into(&self, args: &[String], stdin: Option<&str>, ctx: &mut TerminalContext, out: &mut Output) -> bool {
+        let _ = (args, stdin, ctx, out);
+        false
+    }
+
+    /// tab completion for the argument after `args` (the ones already
+    /// typed, not counting the command name). None means just offer paths
+    fn complete(&self, args: &[String], ctx: &TerminalContext) -> Option<Vec<String>> {
+        let _ = (args, ctx);
+        None
+    }
+
+    /// flags offered when the word being completed starts with a -
+    fn options(&self) -> &'static [&'static str] {
+        &[]
+    }
 }
 
 pub struct CommandRegistry {
@@ -47,6 +76,18 @@ impl CommandRegistry {
         reg.register_command("ps", Box::new(crate::commands::ps::PsCommand));
         reg.register_command("kill", Box::new(crate::commands::kill::KillCommand));
         reg.register_command("killall", Box::new(crate::commands::killall::KillallCommand));
+        reg.register_command("jobs", Box::new(crate::commands::jobs::JobsCommand));
+        reg.register_command("fg", Box::new(crate::commands::jobs::FgCommand));
+        reg.register_command("bg", Box::new(crate::commands::jobs::BgCommand));
+        reg.register_command("wait", Box::new(crate::commands::jobs::WaitCommand));
+        reg.register_command("disown", Box::new(crate::commands::jobs::DisownCommand));
+        reg.register_command("trap", Box::new(crate::commands::trap::TrapCommand));
+        reg.register_command("sleep", Box::new(crate::commands::sleep::SleepCommand));
+        reg.register_command("watch", Box::new(crate::commands::watch::WatchCommand));
+        reg.register_command("at", Box::new(crate::commands::at::AtCommand));
+        reg.register_command("atq", Box::new(crate::commands::at::AtqCommand));
+        reg.register_command("atrm", Box::new(crate::commands::at::AtrmCommand));
+        reg.register_command("crontab", Box::new(crate::commands::crontab::CrontabCommand));
         reg.register_command("export", Box::new(crate::commands::export::ExportCommand));
         reg.register_command("env", Box::new(crate::commands::env::EnvCommand));
         reg.register_command("alias", Box::new(crate::commands::alias::AliasCommand));
@@ -67,6 +108,22 @@ impl CommandRegistry {
         reg.register_command("storage", Box::new(crate::commands::storage::StorageCommand));
         reg.register_command("zip", Box::new(crate::commands::zip::ZipCommand));
         reg.register_command("unzip", Box::new(crate::commands::unzip::UnzipCommand));
+        reg.register_command("true", Box::new(crate::commands::truefalse::TrueCommand));
+        reg.register_command(":", Box::new(crate::commands::truefalse::TrueCommand));
+        reg.register_command("false", Box::new(crate::commands::truefalse::FalseCommand));
+        reg.register_command("break", Box::new(crate::commands::control::BreakCommand));
+        reg.register_command("continue", Box::new(crate::commands::control::ContinueCommand));
+        reg.register_command("return", Box::new(crate::commands::control::ReturnCommand));
+        reg.register_command("exit", Box::new(crate::commands::control::ExitCommand));
+        reg.register_command("local", Box::new(crate::commands::local::LocalCommand));
+        reg.register_command("let", Box::new(crate::commands::arith::LetCommand));
+        reg.register_command("test", Box::new(crate::commands::test::TestCommand));
+        reg.register_command("[", Box::new(crate::commands::test::BracketCommand));
+        reg.register_command("read", Box::new(crate::commands::read::ReadCommand));
+        reg.register_command("printf", Box::new(crate::commands::printf::PrintfCommand));
+        reg.register_command("shift", Box::new(crate::commands::shift::ShiftCommand));
+        reg.register_command("eval", Box::new(crate::commands::eval::EvalCommand));
+        reg.register_command("exec", Box::new(crate::commands::eval::ExecCommand));
         reg
     }
 }
@@ -83,18 +140,6 @@ pub fn run_command(input: &str, ctx: &mut TerminalContext, registry: &CommandReg
         }
     }
     
-    // standard command handling for everything else
-    let mut parts = input.split_whitespace();
-    let cmd = match parts.next() {
-        Some(c) => c,
-        None => return Ok(String::new()), // empty input = no-op
-    };
-    let args: Vec<String> = parts.map(|s| s.to_string()).collect();
-    
-    // find & run cmd or bail with err
-    if let Some(command) = registry.get(cmd) {
-        command.execute(&args, ctx)
-    } else {
-        Err(format!("Command not found: {}", cmd))
-    }
+    // everything else goes through the shell - quotes, pipes etc
+    crate::shell::exec::run(input, ctx, registry)
 }
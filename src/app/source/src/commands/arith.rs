@@ -0,0 +1,24 @@
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+
+/// let EXPR...
+/// Evaluate each arithmetic expression; fails if the last one is 0.
+pub struct LetCommand;
+
+impl Command for LetCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        if args.is_empty() {
+            return Err("let: expression expected".to_string());
+        }
+        let mut last = 0;
+        for expr in args {
+            last = crate::shell::arith::eval(expr, ctx).map_err(|e| format!("let: {}", e))?;
+        }
+        // like (( )), zero means false
+        if last == 0 {
+            Err(String::new())
+        } else {
+            Ok(String::new())
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use chrono::{DateTime, Duration, Local, TimeZone};
+
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+
+/// at [-f FILE] TIME
+/// Run the commands on stdin (or in FILE) once, at TIME.
+pub struct AtCommand;
+
+/// atq
+/// List the jobs `at` is holding.
+pub struct AtqCommand;
+
+/// atrm JOB...
+/// Remove jobs before they run.
+pub struct AtrmCommand;
+
+const DATE_FORMAT: &str = "%a %b %e %H:%M:%S %Y";
+
+// `16:30`, `1630`, `4pm`, `4:30 pm` - the hour and minute. takes the
+// following word too when it's am or pm
+fn clock(word: &str, next: Option<&&str>) -> Option<(u32, u32, bool)> {
+    let (digits, meridiem, took_next) = match (word.strip_suffix("am").or_else(|| word.strip_suffix("pm")), next) {
+        (Some(digits), _) => (digits, Some(&word[digits.len()..]), false),
+        (None, Some(&next)) if next == "am" || next == "pm" => (word, Some(next), true),
+        _ => (word, None, false),
+    };
+    let (hour, minute) = match digits.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None if digits.len() > 2 => (digits[..digits.len() - 2].parse().ok()?, digits[digits.len() - 2..].parse().ok()?),
+        None if meridiem.is_some() => (digits.parse().ok()?, 0),
+        None => return None,
+    };
+    let hour = match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some("pm") => hour % 12 + 12,
+        Some(_) => hour % 12,
+        None => hour,
+    };
+    (hour < 24 && minute < 60).then_some((hour, minute, took_next))
+}
+
+/// `now + 5 minutes`, `noon tomorrow`, `16:30`, `4pm + 2 days`... a time
+/// of day that's already gone today means tomorrow
+fn parse_when(spec: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let spec = spec.to_ascii_lowercase().replace('+', " + ");
+    let words: Vec<&str> = spec.split_whitespace().collect();
+    let mut i = 1;
+    let mut when = match *words.first()? {
+        "now" => now,
+        word => {
+            let (hour, minute) = match word {
+                "noon" => (12, 0),
+                "midnight" => (0, 0),
+                "teatime" => (16, 0),
+                _ => {
+                    let (hour, minute, took_next) = clock(word, words.get(1))?;
+                    i += took_next as usize;
+                    (hour, minute)
+                }
+            };
+            let at = Local.from_local_datetime(&now.date_naive().and_hms_opt(hour, minute, 0)?).earliest()?;
+            match words.get(i) {
+                Some(&"today") => {
+                    i += 1;
+                    at
+                }
+                Some(&"tomorrow") => {
+                    i += 1;
+                    at + Duration::days(1)
+                }
+                _ if at <= now => at + Duration::days(1),
+                _ => at,
+            }
+        }
+    };
+    if words.get(i) == Some(&"+") {
+        let count: i64 = words.get(i + 1)?.parse().ok()?;
+        let unit = words.get(i + 2)?.trim_end_matches('s');
+        when += match unit {
+            "min" | "minute" => Duration::minutes(count),
+            "hour" => Duration::hours(count),
+            "day" => Duration::days(count),
+            "week" => Duration::weeks(count),
+            _ => return None,
+        };
+        i += 3;
+    }
+    (i == words.len()).then_some(when)
+}
+
+fn queue(ctx: &TerminalContext) -> String {
+    let user = ctx.lookup_var("USER").cloned().unwrap_or_default();
+    let jobs = ctx.schedule.at_jobs().iter();
+    jobs.map(|job| format!("{}\t{} a {}", job.id, job.when.format(DATE_FORMAT), user)).collect::<Vec<_>>().join("\n")
+}
+
+fn remove(name: &str, ids: &[String], ctx: &mut TerminalContext) -> CommandResult {
+    if ids.is_empty() {
+        return Err(format!("{}: missing job number", name));
+    }
+    let mut errors = Vec::new();
+    for id in ids {
+        if !id.parse().is_ok_and(|id| ctx.schedule.remove_at(id)) {
+            errors.push(format!("Cannot find jobid {}", id));
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(String::new()),
+        false => Err(errors.join("\n")),
+    }
+}
+
+impl Command for AtCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-f", "-l", "-r", "-d", "-c"]
+    }
+
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        self.execute_with_stdin(args, None, ctx)
+    }
+
+    fn execute_with_stdin(&self, args: &[String], stdin: Option<&str>, ctx: &mut TerminalContext) -> CommandResult {
+        match args.first().map(String::as_str) {
+            Some("-l") => return Ok(queue(ctx)),
+            Some("-r" | "-d") => return remove("at", &args[1..], ctx),
+            // what a job will run
+            Some("-c") => {
+                let mut shown = Vec::new();
+                for id in &args[1..] {
+                    let job = ctx.schedule.at_jobs().iter().find(|job| job.id.to_string() == *id);
+                    shown.push(job.map(|job| job.cmd.clone()).ok_or_else(|| format!("Cannot find jobid {}", id))?);
+                }
+                return Ok(shown.join("\n"));
+            }
+            _ => {}
+        }
+        let (file, spec) = match args.first().map(String::as_str) {
+            Some("-f") => (Some(args.get(1).ok_or("at: option requires an argument -- 'f'")?), &args[2.min(args.len())..]),
+            _ => (None, args),
+        };
+        if spec.is_empty() {
+            return Err("Usage: at [-f FILE] TIME\n       at -l | -r JOB... | -c JOB...".to_string());
+        }
+        let when = parse_when(&spec.join(" "), Local::now()).ok_or("at: garbled time")?;
+        let cmds = match file {
+            Some(file) => {
+                let bytes = ctx.vfs.read_file(&ctx.resolve_path(file)).map_err(|_| format!("at: cannot open input file {}: No such file or directory", file))?;
+                String::from_utf8_lossy(bytes).into_owned()
+            }
+            None => stdin.map(str::to_string).unwrap_or_default(),
+        };
+        if cmds.trim().is_empty() {
+            return Err("at: no commands to run - pipe them in, e.g. echo 'date' | at now + 1 minute".to_string());
+        }
+        let cwd = ctx.cwd.clone();
+        let id = ctx.schedule.at(when, &cmds, &cwd);
+        Ok(format!("job {} at {}", id, when.format(DATE_FORMAT)))
+    }
+}
+
+impl Command for AtqCommand {
+    fn execute(&self, _args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        Ok(queue(ctx))
+    }
+}
+
+impl Command for AtrmCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        remove("atrm", args, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::CommandRegistry;
+    use crate::shell::exec;
+    use std::sync::Arc;
+
+    fn run(line: &str, ctx: &mut TerminalContext) -> Result<String, String> {
+        exec::run(line, ctx, &CommandRegistry::default_commands())
+    }
+
+    #[test]
+    fn test_at_times() {
+        let now = Local.with_ymd_and_hms(2026, 10, 16, 14, 5, 30).unwrap();
+        let at = |spec: &str| parse_when(spec, now).map(|t| t.format("%d %H:%M:%S").to_string());
+        assert_eq!(at("now + 5 minutes").as_deref(), Some("16 14:10:30"));
+        assert_eq!(at("now+1 hour").as_deref(), Some("16 15:05:30"));
+        assert_eq!(at("16:30").as_deref(), Some("16 16:30:00"));
+        assert_eq!(at("9am").as_deref(), Some("17 09:00:00"));
+        assert_eq!(at("4:30 PM tomorrow").as_deref(), Some("17 16:30:00"));
+        assert_eq!(at("noon + 2 days").as_deref(), Some("19 12:00:00"));
+        assert_eq!(at("1200 today").as_deref(), Some("16 12:00:00"));
+        for bad in ["", "later", "25:00", "13pm", "now + 5 fortnights", "noon 5"] {
+            assert_eq!(at(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_at_queue() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_command_registry(Arc::new(CommandRegistry::default_commands()));
+        let queued = run("echo 'echo done > /tmp/out' | at now + 1 minute", &mut ctx).unwrap();
+        assert!(queued.starts_with("job 1 at "), "{}", queued);
+        run("echo x | at midnight", &mut ctx).unwrap();
+        assert_eq!(run("atq", &mut ctx).unwrap().lines().count(), 2);
+        assert_eq!(run("at -c 1", &mut ctx).unwrap(), "echo done > /tmp/out\n");
+
+        assert_eq!(run("atrm 2 7", &mut ctx).unwrap_err(), "Cannot find jobid 7");
+        assert_eq!(ctx.schedule.at_jobs().len(), 1);
+        assert_eq!(run("at now", &mut ctx).unwrap_err(), "at: no commands to run - pipe them in, e.g. echo 'date' | at now + 1 minute");
+        assert_eq!(run("echo x | at whenever", &mut ctx).unwrap_err(), "at: garbled time");
+    }
+}
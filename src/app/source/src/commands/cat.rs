@@ -4,8 +4,16 @@ use crate::context::TerminalContext;
 pub struct CatCommand;
 
 impl Command for CatCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-b", "-n", "-s", "--number", "--number-nonblank", "--squeeze-blank", "--help"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
-        if args.is_empty() {
+        self.execute_with_stdin(args, None, ctx)
+    }
+
+    fn execute_with_stdin(&self, args: &[String], stdin: Option<&str>, ctx: &mut TerminalContext) -> CommandResult {
+        if args.is_empty() && stdin.is_none() {
             return Ok(String::from(
                 "cat - Display file contents\n\
                  Usage: cat [options] <file1> [file2] ...\n\
@@ -56,8 +64,8 @@ impl Command for CatCommand {
                     ));
                 }
                 _ => {
-                    // actual file or bad flag
-                    if args[i].starts_with('-') {
+                    // actual file or bad flag ("-" alone means stdin)
+                    if args[i].starts_with('-') && args[i] != "-" {
                         return Err(format!("cat: invalid option '{}'", args[i]));
                     }
                     files.push(&args[i]);
@@ -66,9 +74,13 @@ impl Command for CatCommand {
             i += 1;
         }
 
-        // bail early if no files
+        // no files but something piped in? read that instead
+        let dash = "-".to_string();
         if files.is_empty() {
-            return Err("cat: missing file operand".to_string());
+            if stdin.is_none() {
+                return Err("cat: missing file operand".to_string());
+            }
+            files.push(&dash);
         }
 
         // output setup
@@ -85,23 +97,27 @@ impl Command for CatCommand {
                 format!("{}/{}", ctx.cwd, filename)
             };
 
-            // try to read the file
-            let content = match ctx.vfs.read_file(&path) {
-                Ok(bytes) => {
-                    // check if file is text or binary
-                    match String::from_utf8(bytes.to_vec()) {
-                        Ok(text) => text,
-                        Err(_) => {
-                            // binary file - just report and skip
-                            output.push_str(&format!("cat: {}: Binary file (not displayed)\n", filename));
-                            continue;
+            // try to read the file ("-" is whatever was piped in)
+            let content = if filename.as_str() == "-" {
+                stdin.unwrap_or_default().to_string()
+            } else {
+                match ctx.vfs.read_file(&path) {
+                    Ok(bytes) => {
+                        // check if file is text or binary
+                        match String::from_utf8(bytes.to_vec()) {
+                            Ok(text) => text,
+                            Err(_) => {
+                                // binary file - just report and skip
+                                output.push_str(&format!("cat: {}: Binary file (not displayed)\n", filename));
+                                continue;
+                            }
                         }
                     }
-                }
-                Err(_) => {
-                    // file not found - report and continue
-                    output.push_str(&format!("cat: {}: No such file or directory\n", filename));
-                    continue;
+                    Err(_) => {
+                        // file not found - report and continue
+                        output.push_str(&format!("cat: {}: No such file or directory\n", filename));
+                        continue;
+                    }
                 }
             };
 
@@ -116,23 +132,33 @@ impl Command for CatCommand {
                 }
             }
 
-            // process content line by line
-            let lines: Vec<&str> = content.lines().collect();
-            for (i, line) in lines.iter().enumerate() {
+            // an empty file adds nothing, not even a numbered line
+            if content.is_empty() {
+                continue;
+            }
+
+            // process content line by line. the newline that ends the file
+            // ends its last line, it doesn't start another one
+            let body = content.strip_suffix('\n').unwrap_or(&content);
+            let mut first_line = true;
+            for line in body.split('\n') {
                 let is_empty = line.trim().is_empty();
                 
                 // skip empty lines if squeeze is on
                 if squeeze_blank && is_empty && last_line_was_empty {
                     continue;
                 }
+                if !first_line {
+                    output.push('\n');
+                }
+                first_line = false;
 
                 // handle line numbering based on flags
                 if number_nonblank {
+                    // blank lines stay blank, no number and no tab
                     if !is_empty {
                         output.push_str(&format!("{:6}\t", line_number));
                         line_number += 1;
-                    } else {
-                        output.push_str("      \t");
                     }
                 } else if number_lines {
                     output.push_str(&format!("{:6}\t", line_number));
@@ -140,32 +166,13 @@ impl Command for CatCommand {
                 }
 
                 output.push_str(line);
-                
-                // add newline unless it's the last line and doesn't have one
-                if i < lines.len() - 1 || file_index < files.len() - 1 || content.ends_with('\n') {
-                    output.push('\n');
-                }
-
                 last_line_was_empty = is_empty;
             }
-
-            // special case for empty files
-            if content.is_empty() {
-                if number_lines {
-                    output.push_str(&format!("{:6}\t\n", line_number));
-                    line_number += 1;
-                } else {
-                    output.push('\n');
-                }
-                last_line_was_empty = true;
+            if content.ends_with('\n') {
+                output.push('\n');
             }
         }
 
-        // clean up trailing newline to match real cat
-        if output.ends_with('\n') {
-            output.pop();
-        }
-
         Ok(output)
     }
 }
@@ -174,12 +181,12 @@ impl Command for CatCommand {
 mod tests {
     use super::*;
     use crate::context::TerminalContext;
-    use crate::vfs::VFS;
+    use crate::vfs::VirtualFileSystem;
 
     #[test]
     fn test_cat_single_file() {
-        let mut vfs = VFS::new();
-        vfs.create_file("/test.txt", b"Hello\nWorld").unwrap();
+        let mut vfs = VirtualFileSystem::new();
+        vfs.create_file("/test.txt", b"Hello\nWorld".to_vec()).unwrap();
         
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         let cmd = CatCommand;
@@ -191,20 +198,32 @@ mod tests {
 
     #[test]
     fn test_cat_with_line_numbers() {
-        let mut vfs = VFS::new();
-        vfs.create_file("/test.txt", b"Line 1\nLine 2\n").unwrap();
+        let mut vfs = VirtualFileSystem::new();
+        vfs.create_file("/test.txt", b"Line 1\nLine 2\n".to_vec()).unwrap();
         
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         let cmd = CatCommand;
         
         let result = cmd.execute(&["-n".to_string(), "test.txt".to_string()], &mut ctx);
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "     1\tLine 1\n     2\tLine 2\n     3\t");
+        assert_eq!(result.unwrap(), "     1\tLine 1\n     2\tLine 2\n");
+    }
+
+    #[test]
+    fn test_cat_number_nonblank() {
+        let mut vfs = VirtualFileSystem::new();
+        vfs.create_file("/test.txt", b"Line 1\n\nLine 2\n".to_vec()).unwrap();
+        
+        let mut ctx = TerminalContext::new_with_vfs(vfs);
+        let cmd = CatCommand;
+        
+        let result = cmd.execute(&["-b".to_string(), "test.txt".to_string()], &mut ctx);
+        assert_eq!(result.unwrap(), "     1\tLine 1\n\n     2\tLine 2\n");
     }
 
     #[test]
     fn test_cat_nonexistent_file() {
-        let vfs = VFS::new();
+        let vfs = VirtualFileSystem::new();
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         let cmd = CatCommand;
         
@@ -215,9 +234,9 @@ mod tests {
 
     #[test]
     fn test_cat_multiple_files() {
-        let mut vfs = VFS::new();
-        vfs.create_file("/file1.txt", b"Content 1").unwrap();
-        vfs.create_file("/file2.txt", b"Content 2").unwrap();
+        let mut vfs = VirtualFileSystem::new();
+        vfs.create_file("/file1.txt", b"Content 1".to_vec()).unwrap();
+        vfs.create_file("/file2.txt", b"Content 2".to_vec()).unwrap();
         
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         let cmd = CatCommand;
@@ -227,9 +246,19 @@ mod tests {
         assert_eq!(result.unwrap(), "Content 1\n\nContent 2");
     }
 
+    #[test]
+    fn test_cat_reads_stdin() {
+        let vfs = VirtualFileSystem::new();
+        let mut ctx = TerminalContext::new_with_vfs(vfs);
+        let cmd = CatCommand;
+
+        let result = cmd.execute_with_stdin(&["-n".to_string()], Some("a\nb"), &mut ctx);
+        assert_eq!(result.unwrap(), "     1\ta\n     2\tb");
+    }
+
     #[test]
     fn test_cat_help() {
-        let vfs = VFS::new();
+        let vfs = VirtualFileSystem::new();
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         let cmd = CatCommand;
         
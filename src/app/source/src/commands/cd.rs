@@ -130,7 +130,7 @@ fn resolve_relative_path(current_path: &str, relative_path: &str) -> String {
 }
 
 // normalize path by resolving . and .. components
-fn normalize_path(path: &str) -> String {
+pub fn normalize_path(path: &str) -> String {
     let mut components = Vec::new();
     
     // split and process each path component
@@ -178,7 +178,7 @@ fn normalize_path(path: &str) -> String {
 mod tests {
     use super::*;
     use crate::context::TerminalContext;
-    use crate::vfs::VFS;
+    use crate::vfs::VirtualFileSystem;
 
     #[test]
     fn test_get_parent_directory() {
@@ -210,9 +210,9 @@ mod tests {
 
     #[test]
     fn test_cd_absolute_path() {
-        let mut vfs = VFS::new();
-        vfs.create_directory("/home").unwrap();
-        vfs.create_directory("/home/user").unwrap();
+        let mut vfs = VirtualFileSystem::new();
+        vfs.create_dir("/home").unwrap();
+        vfs.create_dir("/home/user").unwrap();
         
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         ctx.cwd = "/".to_string();
@@ -226,9 +226,9 @@ mod tests {
 
     #[test]
     fn test_cd_relative_path() {
-        let mut vfs = VFS::new();
-        vfs.create_directory("/home").unwrap();
-        vfs.create_directory("/home/user").unwrap();
+        let mut vfs = VirtualFileSystem::new();
+        vfs.create_dir("/home").unwrap();
+        vfs.create_dir("/home/user").unwrap();
         
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         ctx.cwd = "/home".to_string();
@@ -242,9 +242,9 @@ mod tests {
 
     #[test]
     fn test_cd_parent_directory() {
-        let mut vfs = VFS::new();
-        vfs.create_directory("/home").unwrap();
-        vfs.create_directory("/home/user").unwrap();
+        let mut vfs = VirtualFileSystem::new();
+        vfs.create_dir("/home").unwrap();
+        vfs.create_dir("/home/user").unwrap();
         
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         ctx.cwd = "/home/user".to_string();
@@ -258,7 +258,7 @@ mod tests {
 
     #[test]
     fn test_cd_nonexistent_directory() {
-        let vfs = VFS::new();
+        let vfs = VirtualFileSystem::new();
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         
         let cmd = CdCommand;
@@ -270,8 +270,8 @@ mod tests {
 
     #[test]
     fn test_cd_to_file() {
-        let mut vfs = VFS::new();
-        vfs.create_file("/test.txt", b"content").unwrap();
+        let mut vfs = VirtualFileSystem::new();
+        vfs.create_file("/test.txt", b"content".to_vec()).unwrap();
         
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         
@@ -284,8 +284,8 @@ mod tests {
 
     #[test]
     fn test_cd_home() {
-        let mut vfs = VFS::new();
-        vfs.create_directory("/home").unwrap();
+        let mut vfs = VirtualFileSystem::new();
+        vfs.create_dir("/home").unwrap();
         
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         ctx.cwd = "/some/path".to_string();
@@ -299,8 +299,8 @@ mod tests {
 
     #[test]
     fn test_cd_tilde() {
-        let mut vfs = VFS::new();
-        vfs.create_directory("/home").unwrap();
+        let mut vfs = VirtualFileSystem::new();
+        vfs.create_dir("/home").unwrap();
         
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         ctx.cwd = "/some/path".to_string();
@@ -314,9 +314,9 @@ mod tests {
 
     #[test]
     fn test_cd_previous_directory() {
-        let mut vfs = VFS::new();
-        vfs.create_directory("/home").unwrap();
-        vfs.create_directory("/tmp").unwrap();
+        let mut vfs = VirtualFileSystem::new();
+        vfs.create_dir("/home").unwrap();
+        vfs.create_dir("/tmp").unwrap();
         
         let mut ctx = TerminalContext::new_with_vfs(vfs);
         ctx.cwd = "/home".to_string();
@@ -7,6 +7,7 @@ pub struct ChmodCommand;
 const CHMOD_VERSION: &str = "chmod 1.0.0";
 const CHMOD_HELP: &str = r#"Usage: chmod [OPTION]... MODE[,MODE]... FILE...
 Change the mode of each FILE to MODE.
+MODE is octal (755) or symbolic: [ugoa]*[-+=][rwx]*, e.g. +x, go-w, u=rw.
 
   -R, --recursive      change files and directories recursively
   -v, --verbose       output a diagnostic for every file processed
@@ -28,9 +29,50 @@ fn parse_octal_mode(mode: &str) -> Option<Permissions> {
     }
 }
 
-fn apply_permissions(node: &mut VfsNode, perms: Permissions, recursive: bool, verbose: bool, path: &str, output: &mut Vec<String>) {
+// u+x, go-w, a=r or just +x (everyone) - comma separated, each one
+// changing the bits it names and leaving the rest as they were
+fn apply_symbolic_mode(mode: &str, current: Permissions) -> Option<Permissions> {
+    let mut perms = current;
+    for clause in mode.split(',') {
+        let split = clause.find(['+', '-', '='])?;
+        let (who, mut ops) = clause.split_at(split);
+        if !who.chars().all(|c| "ugoa".contains(c)) {
+            return None;
+        }
+        let all = who.is_empty() || who.contains('a');
+        while let Some(op) = ops.chars().next() {
+            let end = ops[1..].find(['+', '-', '=']).map_or(ops.len(), |i| i + 1);
+            let mut bits = 0;
+            for c in ops[1..end].chars() {
+                bits |= match c {
+                    'r' => 0b100,
+                    'w' => 0b010,
+                    'x' => 0b001,
+                    _ => return None,
+                };
+            }
+            let apply = |field: &mut u8| match op {
+                '+' => *field |= bits,
+                '-' => *field &= !bits,
+                _ => *field = bits,
+            };
+            if all || who.contains('u') { apply(&mut perms.user); }
+            if all || who.contains('g') { apply(&mut perms.group); }
+            if all || who.contains('o') { apply(&mut perms.other); }
+            ops = &ops[end..];
+        }
+    }
+    Some(perms)
+}
+
+fn new_permissions(mode: &str, current: Permissions) -> Option<Permissions> {
+    parse_octal_mode(mode).or_else(|| apply_symbolic_mode(mode, current))
+}
+
+fn apply_permissions(node: &mut VfsNode, mode: &str, recursive: bool, verbose: bool, path: &str, output: &mut Vec<String>) {
     match node {
         VfsNode::File { permissions, .. } | VfsNode::Directory { permissions, .. } => {
+            let perms = new_permissions(mode, *permissions).unwrap_or(*permissions);
             let changed = *permissions != perms;
             *permissions = perms;
             if verbose || changed {
@@ -43,13 +85,17 @@ fn apply_permissions(node: &mut VfsNode, perms: Permissions, recursive: bool, ve
         if let VfsNode::Directory { children, .. } = node {
             for (name, child) in children.iter_mut() {
                 let child_path = if path == "/" { format!("/{}", name) } else { format!("{}/{}", path, name) };
-                apply_permissions(child, perms, true, verbose, &child_path, output);
+                apply_permissions(child, mode, true, verbose, &child_path, output);
             }
         }
     }
 }
 
 impl Command for ChmodCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-c", "-f", "-v", "-R", "--changes", "--silent", "--verbose", "--recursive", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         if args.iter().any(|a| a == "--help") {
             return Ok(CHMOD_HELP.to_string());
@@ -68,6 +114,8 @@ impl Command for ChmodCommand {
                 "-v" | "--verbose" => verbose = true,
                 "-c" | "--changes" => verbose = true,
                 "-f" | "--silent" => silent = true,
+                // -w, -x and so on are modes, not options
+                s if s.starts_with('-') && mode.is_none() && apply_symbolic_mode(s, Permissions::default_file()).is_some() => mode = Some(s.to_string()),
                 s if s.starts_with('-') => {},
                 s if mode.is_none() => mode = Some(s.to_string()),
                 s => files.push(s.to_string()),
@@ -77,18 +125,18 @@ impl Command for ChmodCommand {
             Some(m) => m,
             None => return Err("chmod: missing operand".to_string()),
         };
-        let perms = match parse_octal_mode(&mode) {
-            Some(p) => p,
-            None => return Err("chmod: only octal modes supported in this version".to_string()),
-        };
+        if new_permissions(&mode, Permissions::default_file()).is_none() {
+            return Err(format!("chmod: invalid mode: '{}'", mode));
+        }
         if files.is_empty() {
             return Err("chmod: missing file operand".to_string());
         }
         let mut output = Vec::new();
         for file in files {
-            match ctx.vfs.resolve_path_mut(&file) {
+            let path = ctx.resolve_path(&file);
+            match ctx.vfs.resolve_path_mut(&path) {
                 Some(node) => {
-                    apply_permissions(node, perms, recursive, verbose, &file, &mut output);
+                    apply_permissions(node, &mode, recursive, verbose, &file, &mut output);
                 }
                 None => {
                     if !silent {
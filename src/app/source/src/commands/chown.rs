@@ -56,6 +56,10 @@ fn apply_ownership(node: &mut VfsNode, owner: &Option<String>, group: &Option<St
 }
 
 impl Command for ChownCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-c", "-f", "-v", "-R", "--changes", "--silent", "--verbose", "--recursive", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         // handle boring flags first
         if args.iter().any(|a| a == "--help") {
@@ -0,0 +1,91 @@
+use crate::command::{Command, CommandResult};
+use crate::context::{Flow, TerminalContext};
+
+/// break [N]
+/// Leave the innermost N enclosing for/while/until loops.
+pub struct BreakCommand;
+
+/// continue [N]
+/// Jump to the next round of the Nth enclosing loop.
+pub struct ContinueCommand;
+
+/// exit [N]
+/// Stop the running script (or line) with status N (default $?).
+pub struct ExitCommand;
+
+/// return [N]
+/// Leave the current function or sourced script with status N (default $?).
+pub struct ReturnCommand;
+
+// shared bit of break/continue - work out N and check we're in a loop
+fn loop_count(name: &str, args: &[String], ctx: &TerminalContext) -> Result<Option<u32>, String> {
+    if ctx.loop_depth == 0 {
+        // bash just warns about this and carries on
+        return Ok(None);
+    }
+    let n = match args.first() {
+        None => 1,
+        Some(arg) => match arg.parse::<u32>() {
+            Ok(0) => return Err(format!("{}: {}: loop count out of range", name, arg)),
+            Ok(n) => n,
+            Err(_) => return Err(format!("{}: {}: numeric argument required", name, arg)),
+        },
+    };
+    // break 5 inside two loops just leaves both
+    Ok(Some(n.min(ctx.loop_depth)))
+}
+
+impl Command for BreakCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        match loop_count("break", args, ctx)? {
+            Some(n) => ctx.flow = Some(Flow::Break(n)),
+            None => return Ok("break: only meaningful in a `for', `while', or `until' loop".to_string()),
+        }
+        Ok(String::new())
+    }
+}
+
+impl Command for ContinueCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        match loop_count("continue", args, ctx)? {
+            Some(n) => ctx.flow = Some(Flow::Continue(n)),
+            None => return Ok("continue: only meaningful in a `for', `while', or `until' loop".to_string()),
+        }
+        Ok(String::new())
+    }
+}
+
+// N for exit/return - statuses wrap like a real shell's, exit 256 is 0
+fn status_arg(name: &str, args: &[String], ctx: &TerminalContext) -> Result<i32, String> {
+    match args.first() {
+        None => Ok(ctx.last_status),
+        Some(arg) => match arg.parse::<i64>() {
+            Ok(n) => Ok(n.rem_euclid(256) as i32),
+            Err(_) => Err(format!("{}: {}: numeric argument required", name, arg)),
+        },
+    }
+}
+
+impl Command for ExitCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        // a bad N still exits, with 2
+        let (code, result) = match status_arg("exit", args, ctx) {
+            Ok(code) => (code, Ok(String::new())),
+            Err(e) => (2, Err(e)),
+        };
+        ctx.flow = Some(Flow::Exit(code));
+        ctx.exit_code = Some(code);
+        result
+    }
+}
+
+impl Command for ReturnCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        if ctx.locals.is_empty() && ctx.source_depth == 0 {
+            return Err("return: can only `return' from a function or sourced script".to_string());
+        }
+        let code = status_arg("return", args, ctx)?;
+        ctx.flow = Some(Flow::Return(code));
+        Ok(String::new())
+    }
+}
@@ -9,6 +9,10 @@ const CP_VERSION: &str = "cp 1.0.0";
 const CP_HELP: &str = "Usage: cp [OPTION]... [-T] SOURCE DEST\n       cp [OPTION]... SOURCE... DIRECTORY\n       cp [OPTION]... -t DIRECTORY SOURCE...\nCopy SOURCE to DEST, or multiple SOURCE(s) to DIRECTORY.\n\n  -R, -r, --recursive   copy directories recursively\n  -f, --force           if an existing destination file cannot be opened, remove it and try again\n  -i, --interactive     prompt before overwrite\n  -n, --no-clobber      do not overwrite an existing file\n  -v, --verbose         explain what is being done\n      --help            display this help and exit\n      --version         output version information and exit";
 
 impl Command for CpCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-f", "-i", "-n", "-r", "-R", "-t", "-T", "-v", "--force", "--interactive", "--no-clobber", "--recursive", "--target-directory", "--no-target-directory", "--verbose", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         // handle help and version flags first
         if args.iter().any(|a| a == "--help") {
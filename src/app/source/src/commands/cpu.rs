@@ -56,34 +56,16 @@ pub enum Instruction {
 pub struct CpuCommand;
 
 impl Command for CpuCommand {
+    fn complete(&self, args: &[String], _ctx: &TerminalContext) -> Option<Vec<String>> {
+        // subcommand first, then whatever file it's for
+        args.is_empty().then(|| ["run", "new", "help", "docs"].iter().map(|s| s.to_string()).collect())
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         match args.get(0).map(|s| s.as_str()) {
-            Some("run") => {
-                // run an assembly file through our vm
-                if let Some(filename) = args.get(1) {
-                    // handle relative/absolute paths
-                    let path = if filename.starts_with('/') {
-                        filename.to_string()
-                    } else {
-                        format!("{}/{}", ctx.cwd, filename)
-                    };
-                    
-                    // read and parse the assembly file
-                    let file_content = ctx.vfs.read_file(&path)
-                        .map_err(|e| format!("Error reading file: {}", e))?;
-                    
-                    let content = String::from_utf8(file_content.to_vec())
-                        .map_err(|_| "File contains invalid UTF-8".to_string())?;
-                    
-                    // assemble source to bytecode
-                    let program = assemble(&content)
-                        .map_err(|e| format!("Assembly error: {}", e))?;
-                    
-                    // run it and return output
-                    Ok(run(&program))
-                } else {
-                    Err("Usage: cpu run <filename>".to_string())
-                }
+            Some("run") => match args.get(1) {
+                Some(filename) => run_file(filename, ctx),
+                None => Err("Usage: cpu run <filename>".to_string()),
             },
             Some("new") => {
                 // create new assembly file with basic template
@@ -129,6 +111,8 @@ impl Command for CpuCommand {
                      - cpu help            : Show this help\n\
                      - cpu docs            : Show assembly language documentation\n\
                      \n\
+                     Start a file with #!/bin/cpu and chmod +x it to run it by name.\n\
+                     \n\
                      Assembly Instructions:\n\
                      - push <n>     : Push value onto stack\n\
                      - pop          : Remove top value from stack\n\
@@ -221,6 +205,9 @@ impl Command for CpuCommand {
                      ```"
                 ))
             },
+            // `#!/bin/cpu` at the top of an executable .asm file ends up
+            // here as `cpu /path/to/file`
+            Some(filename) if ctx.vfs.resolve_path(&ctx.resolve_path(filename)).is_some() => run_file(filename, ctx),
             _ => {
                 Err("Usage: cpu [run|new|help|docs]".to_string())
             }
@@ -228,6 +215,73 @@ impl Command for CpuCommand {
     }
 }
 
+// run an assembly file through our vm
+fn run_file(filename: &str, ctx: &mut TerminalContext) -> CommandResult {
+    // handle relative/absolute paths
+    let path = if filename.starts_with('/') {
+        filename.to_string()
+    } else {
+        format!("{}/{}", ctx.cwd, filename)
+    };
+
+    // read and parse the assembly file
+    let file_content = ctx.vfs.read_file(&path)
+        .map_err(|e| format!("Error reading file: {}", e))?;
+
+    let content = String::from_utf8(file_content.to_vec())
+        .map_err(|_| "File contains invalid UTF-8".to_string())?;
+
+    // assemble source to bytecode
+    let program = assemble(&content)
+        .map_err(|e| format!("Assembly error: {}", e))?;
+
+    // short programs just run and return their output, and so does one
+    // whose output is going into a pipe, a file or a $(...) - the rest of
+    // it can't follow there later
+    let mut machine = Machine::default();
+    if !execute(&program, &mut machine, SLICE) {
+        return Ok(machine.result());
+    }
+    if ctx.capturing > 0 {
+        while execute(&program, &mut machine, SLICE) {}
+        return Ok(machine.result());
+    }
+
+    // a long one carries on a slice at a time in between everything
+    // else, so ps shows it and kill (or ctrl-c) can stop it
+    #[cfg(target_arch = "wasm32")]
+    {
+        let job = ctx.processes.detach(ctx.processes.current()).unwrap_or_default();
+        let shown = machine.take_lines();
+        wasm_bindgen_futures::spawn_local(async move {
+            loop {
+                gloo_timers::future::TimeoutFuture::new(0).await;
+                if !job.proceed().await {
+                    return;
+                }
+                let more = execute(&program, &mut machine, SLICE);
+                let lines = machine.take_lines();
+                if !lines.is_empty() {
+                    crate::send_async_result(&lines);
+                }
+                if !more {
+                    let rest = machine.result();
+                    if !rest.is_empty() {
+                        crate::send_async_result(rest.trim_end_matches('\n'));
+                    }
+                    return;
+                }
+            }
+        });
+        Ok(shown)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        while execute(&program, &mut machine, SLICE) {}
+        Ok(machine.result())
+    }
+}
+
 // two-pass assembler: collect labels first, then parse instructions
 pub fn assemble(src: &str) -> Result<Vec<Instruction>, String> {
     let mut program = Vec::new();
@@ -389,16 +443,71 @@ fn parse_address(addr: &str, labels: &std::collections::HashMap<String, usize>,
     addr.parse().map_err(|_| format!("Invalid address at line {}", line+1))
 }
 
-// virtual machine executor - runs the compiled program
-pub fn run(program: &[Instruction]) -> String {
-    let mut stack = Vec::new();
-    let mut memory = vec![0; 1024]; // 1kb memory - should be plenty
-    let mut output = String::new();
-    let mut pc = 0; // program counter
+// everything a running program has, kept between slices so a long run
+// can be spread out and stopped part way
+pub struct Machine {
+    stack: Vec<i32>,
+    memory: Vec<i32>,
+    output: String,
+    pc: usize, // program counter
+    // some output's already been handed over
+    printed: bool,
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Machine {
+            stack: Vec::new(),
+            memory: vec![0; 1024], // 1kb memory - should be plenty
+            output: String::new(),
+            pc: 0,
+            printed: false,
+        }
+    }
+}
+
+impl Machine {
+    // what's been printed so far, up to the last full line
+    #[cfg(target_arch = "wasm32")]
+    fn take_lines(&mut self) -> String {
+        match self.output.rfind('\n') {
+            Some(end) => {
+                let rest = self.output.split_off(end + 1);
+                let lines = std::mem::replace(&mut self.output, rest);
+                self.printed = true;
+                lines.trim_end_matches('\n').to_string()
+            }
+            None => String::new(),
+        }
+    }
+
+    // the output, or the final stack if the program didn't print anything
+    pub fn result(self) -> String {
+        if !self.output.is_empty() || self.printed {
+            self.output
+        } else {
+            format!("Final stack: {:?}\n", self.stack)
+        }
+    }
+}
+
+// how many instructions run before a long program gives the page (and
+// kill) a look in
+const SLICE: usize = 1_000_000;
+
+// virtual machine executor - runs up to `steps` instructions of the
+// compiled program. true if it's not done yet
+pub fn execute(program: &[Instruction], machine: &mut Machine, steps: usize) -> bool {
+    let Machine { stack, memory, output, pc, .. } = machine;
+    let mut steps = steps;
     
     // main execution loop
-    while pc < program.len() {
-        match program[pc] {
+    while *pc < program.len() {
+        if steps == 0 {
+            return true;
+        }
+        steps -= 1;
+        match program[*pc] {
             Instruction::Push(n) => stack.push(n),
             Instruction::Pop => { stack.pop(); },
             Instruction::Add => {
@@ -465,7 +574,7 @@ pub fn run(program: &[Instruction]) -> String {
             }
             Instruction::Jump(addr) => {
                 if addr < program.len() {
-                    pc = addr;
+                    *pc = addr;
                     continue; // skip pc increment
                 } else {
                     output.push_str(&format!("Error: Jump target out of bounds: {}\n", addr));
@@ -476,7 +585,7 @@ pub fn run(program: &[Instruction]) -> String {
                 if let Some(val) = stack.pop() {
                     if val != 0 {
                         if addr < program.len() {
-                            pc = addr;
+                            *pc = addr;
                             continue; // skip pc increment
                         } else {
                             output.push_str(&format!("Error: Jump target out of bounds: {}\n", addr));
@@ -489,7 +598,7 @@ pub fn run(program: &[Instruction]) -> String {
                 if let Some(val) = stack.pop() {
                     if val == 0 {
                         if addr < program.len() {
-                            pc = addr;
+                            *pc = addr;
                             continue; // skip pc increment
                         } else {
                             output.push_str(&format!("Error: Jump target out of bounds: {}\n", addr));
@@ -584,13 +693,13 @@ pub fn run(program: &[Instruction]) -> String {
                 stack.push(n << 12);
             }
             Instruction::Auipc(n) => {
-                stack.push((pc as i32) + (n << 12));
+                stack.push((*pc as i32) + (n << 12));
             }
             Instruction::Beq(addr) => {
                 if let (Some(b), Some(a)) = (stack.pop(), stack.pop()) {
                     if a == b {
                         if addr < program.len() {
-                            pc = addr;
+                            *pc = addr;
                             continue;
                         } else {
                             output.push_str(&format!("Error: Branch target out of bounds: {}\n", addr));
@@ -603,7 +712,7 @@ pub fn run(program: &[Instruction]) -> String {
                 if let (Some(b), Some(a)) = (stack.pop(), stack.pop()) {
                     if a != b {
                         if addr < program.len() {
-                            pc = addr;
+                            *pc = addr;
                             continue;
                         } else {
                             output.push_str(&format!("Error: Branch target out of bounds: {}\n", addr));
@@ -616,7 +725,7 @@ pub fn run(program: &[Instruction]) -> String {
                 if let (Some(b), Some(a)) = (stack.pop(), stack.pop()) {
                     if a < b {
                         if addr < program.len() {
-                            pc = addr;
+                            *pc = addr;
                             continue;
                         } else {
                             output.push_str(&format!("Error: Branch target out of bounds: {}\n", addr));
@@ -629,7 +738,7 @@ pub fn run(program: &[Instruction]) -> String {
                 if let (Some(b), Some(a)) = (stack.pop(), stack.pop()) {
                     if a >= b {
                         if addr < program.len() {
-                            pc = addr;
+                            *pc = addr;
                             continue;
                         } else {
                             output.push_str(&format!("Error: Branch target out of bounds: {}\n", addr));
@@ -640,9 +749,9 @@ pub fn run(program: &[Instruction]) -> String {
             }
             // Function call/return
             Instruction::Call(addr) => {
-                stack.push((pc + 1) as i32); // push return address
+                stack.push((*pc + 1) as i32); // push return address
                 if addr < program.len() {
-                    pc = addr;
+                    *pc = addr;
                     continue;
                 } else {
                     output.push_str(&format!("Error: Call target out of bounds: {}\n", addr));
@@ -653,7 +762,7 @@ pub fn run(program: &[Instruction]) -> String {
                 if let Some(ret_addr) = stack.pop() {
                     let ret_addr = ret_addr as usize;
                     if ret_addr < program.len() {
-                        pc = ret_addr;
+                        *pc = ret_addr;
                         continue;
                     } else {
                         output.push_str(&format!("Error: Return address out of bounds: {}\n", ret_addr));
@@ -662,19 +771,13 @@ pub fn run(program: &[Instruction]) -> String {
                 }
             }
             Instruction::PushRa => {
-                stack.push((pc + 1) as i32);
+                stack.push((*pc + 1) as i32);
             }
             Instruction::PopRa => {
                 stack.pop(); // just pop, user responsible for stack discipline
             }
         }
-        pc += 1;
-    }
-    
-    if !output.is_empty() {
-        output
-    } else {
-        // if program didn't output anything, show final stack state
-        format!("Final stack: {:?}\n", stack)
+        *pc += 1;
     }
+    false
 }
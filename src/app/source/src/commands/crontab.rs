@@ -0,0 +1,45 @@
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+use crate::schedule::bad_lines;
+
+/// crontab [FILE | -l | -r]
+/// Install FILE (or stdin) as ~/.crontab, show it or remove it.
+pub struct CrontabCommand;
+
+impl Command for CrontabCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-l", "-r"]
+    }
+
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        self.execute_with_stdin(args, None, ctx)
+    }
+
+    fn execute_with_stdin(&self, args: &[String], stdin: Option<&str>, ctx: &mut TerminalContext) -> CommandResult {
+        let home = ctx.lookup_var("HOME").map(|h| h.trim_end_matches('/')).unwrap_or_default();
+        let path = format!("{}/.crontab", home);
+        let user = ctx.lookup_var("USER").cloned().unwrap_or_default();
+        let text = match args.first().map(String::as_str) {
+            Some("-l") => {
+                let bytes = ctx.vfs.read_file(&path).map_err(|_| format!("no crontab for {}", user))?;
+                return Ok(String::from_utf8_lossy(bytes).trim_end_matches('\n').to_string());
+            }
+            Some("-r") => {
+                ctx.vfs.read_file(&path).map_err(|_| format!("no crontab for {}", user))?;
+                ctx.delete_with_events(&path).map_err(|e| format!("crontab: {}", e))?;
+                return Ok(String::new());
+            }
+            None | Some("-") => stdin.ok_or("crontab: no crontab given - pipe one in or name a file, see `help crontab`")?.to_string(),
+            Some(file) => {
+                let bytes = ctx.vfs.read_file(&ctx.resolve_path(file)).map_err(|_| format!("crontab: {}: No such file or directory", file))?;
+                String::from_utf8_lossy(bytes).into_owned()
+            }
+        };
+        // a broken line leaves the old crontab as it was, like cron's own
+        if let Some(line) = bad_lines(&text).first() {
+            return Err(format!("crontab: line {}: bad time or command\nerrors in crontab file, can't install.", line));
+        }
+        ctx.write_file_with_events(&path, text.as_bytes()).map_err(|e| format!("crontab: {}", e))?;
+        Ok(String::new())
+    }
+}
@@ -77,6 +77,9 @@ impl Command for CurlCommand {
             let custom_headers_clone = custom_headers.clone();
             let output_file_clone = output_file.clone();
             
+            // in ps until the response is in, and kill drops it
+            let job = ctx.processes.detach(ctx.processes.current()).unwrap_or_default();
+            
             // spawn async task because we're not animals
             spawn_local(async move {
                 let window = match window() {
@@ -117,7 +120,11 @@ impl Command for CurlCommand {
                 };
                 
                 // actually make the request
-                match JsFuture::from(window.fetch_with_request(&request)).await {
+                let response = JsFuture::from(window.fetch_with_request(&request)).await;
+                if !job.proceed().await {
+                    return;
+                }
+                match response {
                     Ok(response_val) => {
                         if let Ok(response) = response_val.dyn_into::<Response>() {
                             let status = response.status();
@@ -9,6 +9,10 @@ const ECHO_VERSION: &str = "echo 1.0.0";
 const ECHO_HELP: &str = "Usage: echo [STRING]...\nWrite arguments to the standard output, separated by spaces and followed by a newline.\n\n  -n             do not output the trailing newline\n      --help     display this help and exit\n      --version  output version information and exit";
 
 impl Command for EchoCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-n", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], _ctx: &mut TerminalContext) -> CommandResult {
         if args.iter().any(|a| a == "--help") {
             return Ok(ECHO_HELP.to_string());
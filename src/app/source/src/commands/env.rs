@@ -18,6 +18,10 @@ If no COMMAND, print the resulting environment.
 "#;
 
 impl Command for EnvCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-i", "-u", "--ignore-environment", "--unset", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         // quick returns for help/version flags
         if args.iter().any(|a| a == "--help") {
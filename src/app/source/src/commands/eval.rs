@@ -0,0 +1,71 @@
+use crate::command::{Command, CommandResult};
+use crate::context::{Flow, TerminalContext};
+use crate::shell::exec::{self, Output, Stream};
+
+/// eval [ARG...]
+/// Join the ARGs with spaces and run the result as shell input.
+pub struct EvalCommand;
+
+/// exec COMMAND [ARG...]
+/// Run COMMAND in place of the shell - whatever called exec stops after it.
+pub struct ExecCommand;
+
+impl Command for EvalCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        // only when called directly rather than from the shell, which
+        // uses execute_into - the two streams can't stay apart here
+        let mut out = Output::default();
+        self.execute_into(args, None, ctx, &mut out);
+        if ctx.depth == 0 && matches!(ctx.flow, Some(Flow::Exit(_) | Flow::Interrupt)) {
+            ctx.flow = None;
+        }
+        match ctx.exit_code {
+            Some(code) if code != 0 => Err(out.render()),
+            _ => Ok(out.render()),
+        }
+    }
+
+    fn execute_into(&self, args: &[String], stdin: Option<&str>, ctx: &mut TerminalContext, out: &mut Output) -> bool {
+        let script = args.join(" ");
+        if script.trim().is_empty() {
+            ctx.exit_code = Some(0);
+            return true;
+        }
+        let Some(registry) = ctx.registry.clone() else {
+            out.push(Stream::Err, "eval: command registry not available".to_string());
+            ctx.exit_code = Some(1);
+            return true;
+        };
+        // same ctx, so variables, functions and break/return all carry
+        // straight through to the caller
+        let status = exec::run_here(&script, stdin, ctx, &registry, out);
+        ctx.exit_code = Some(match ctx.flow {
+            Some(Flow::Exit(code)) => code,
+            _ => status,
+        });
+        true
+    }
+}
+
+impl Command for ExecCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        self.execute_with_stdin(args, None, ctx)
+    }
+
+    fn execute_with_stdin(&self, args: &[String], stdin: Option<&str>, ctx: &mut TerminalContext) -> CommandResult {
+        // plain `exec` with nothing to run is a no-op
+        let Some((name, args)) = args.split_first() else { return Ok(String::new()) };
+        let registry = ctx.registry.clone().ok_or("exec: command registry not available")?;
+        let Some(cmd) = registry.get(name) else {
+            ctx.exit_code = Some(127);
+            return Err(format!("exec: {}: not found", name));
+        };
+        ctx.exit_code = None;
+        let result = cmd.execute_with_stdin(args, stdin, ctx);
+        let status = ctx.exit_code.take().unwrap_or(if result.is_ok() { 0 } else { 1 });
+        // there's no shell left to come back to
+        ctx.flow = Some(Flow::Exit(status));
+        ctx.exit_code = Some(status);
+        result
+    }
+}
@@ -12,6 +12,10 @@ Set export attribute for variables (add to environment for child commands).
 "#;
 
 impl Command for ExportCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-p", "--help"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         // show help if asked for
         if args.iter().any(|a| a == "--help") {
@@ -6,21 +6,37 @@ pub struct GrepCommand;
 
 impl Command for GrepCommand {
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        self.execute_with_stdin(args, None, ctx)
+    }
+
+    fn execute_with_stdin(&self, args: &[String], stdin: Option<&str>, ctx: &mut TerminalContext) -> CommandResult {
         if args.is_empty() {
             return Err("Usage: grep PATTERN [FILE]...".to_string());
         }
         
+        // like real grep: 0 = something matched, 1 = nothing did, 2 = trouble
         let pattern = &args[0];
         let regex = match Regex::new(pattern) {
             Ok(r) => r,
-            Err(e) => return Err(format!("Invalid regex pattern: {}", e)),
+            Err(e) => {
+                ctx.exit_code = Some(2);
+                return Err(format!("Invalid regex pattern: {}", e));
+            }
         };
         
         let mut output = Vec::new();
+        let mut matched = false;
+        let mut trouble = false;
         
         if args.len() == 1 {
-            // stdin not implemented, meh
-            return Err("Reading from stdin not supported".to_string());
+            // no files - filter whatever was piped in, plain lines like real grep
+            return match stdin {
+                Some(input) => {
+                    let lines: Vec<&str> = input.lines().filter(|line| regex.is_match(line)).collect();
+                    if lines.is_empty() { Err(String::new()) } else { Ok(lines.join("\n")) }
+                }
+                None => Err("Usage: grep PATTERN [FILE]...".to_string()),
+            };
         }
         
         for filename in &args[1..] {
@@ -38,6 +54,7 @@ impl Command for GrepCommand {
                         for (i, line) in content.lines().enumerate() {
                             // check if line matches our regex
                             if regex.is_match(line) {
+                                matched = true;
                                 // include filename if multiple files given
                                 if args.len() > 2 {
                                     output.push(format!("{}:{}: {}", filename, i + 1, line));
@@ -51,11 +68,19 @@ impl Command for GrepCommand {
                 Err(e) => {
                     // can't read? just show error and move on
                     output.push(format!("grep: {}: {}", filename, e));
+                    trouble = true;
                 }
             }
         }
         
         // join all matches with newlines
-        Ok(output.join("\n"))
+        if trouble {
+            ctx.exit_code = Some(2);
+            Err(output.join("\n"))
+        } else if matched {
+            Ok(output.join("\n"))
+        } else {
+            Err(output.join("\n"))
+        }
     }
 }
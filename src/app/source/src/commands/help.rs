@@ -9,6 +9,10 @@ const HELP_VERSION: &str = "help 1.0.0";
 const HELP_USAGE: &str = "Usage: help [COMMAND]\nDisplay help information about available commands.\n\n  COMMAND        show help for specific command\n      --help     display this help and exit\n      --version  output version information and exit";
 
 impl Command for HelpCommand {
+    fn complete(&self, _args: &[String], ctx: &TerminalContext) -> Option<Vec<String>> {
+        ctx.get_command_registry().map(|registry| registry.get_command_names())
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         // handle simple flag cases first
         if args.iter().any(|a| a == "--help") {
@@ -59,13 +63,16 @@ impl Command for HelpCommand {
                     "ln" | "chmod" | "chown" | "chgrp" | "pwd" | "cd" => {
                         file_ops.push(cmd);
                     }
-                    "ps" | "kill" | "killall" | "cpu" => {
+                    "ps" | "kill" | "killall" | "jobs" | "fg" | "bg" | "wait" | "disown" | "cpu" |
+                    "sleep" | "watch" | "at" | "atq" | "atrm" | "crontab" => {
                         system_ops.push(cmd);
                     }
-                    "cat" | "echo" | "grep" | "sed" | "edit" | "curl" | "ping" => {
+                    "cat" | "echo" | "printf" | "grep" | "sed" | "edit" | "curl" | "ping" => {
                         text_ops.push(cmd);
                     }
-                    "env" | "export" | "set" | "alias" | "unalias" | "source" | "functions" => {
+                    "env" | "export" | "set" | "alias" | "unalias" | "source" | "functions" |
+                    "true" | "false" | ":" | "break" | "continue" | "return" | "local" | "let" | "test" | "[" |
+                    "read" | "shift" | "exit" | "eval" | "exec" | "trap" => {
                         env_ops.push(cmd);
                     }
                     "help" | "history" | "clear" | "rawcreate" | "storage" => {
@@ -142,6 +149,21 @@ fn get_command_help(cmd_name: &str) -> String {
         "mv" => "mv [OPTION]... SOURCE... DEST\nMove/rename SOURCE to DEST, or multiple SOURCE(s) to DIRECTORY\n\nOptions:\n  --help    display this help and exit".to_string(),
         "rm" => "rm [OPTION]... FILE...\nRemove (unlink) the FILE(s)\n\nOptions:\n  -r, -R    remove directories and their contents recursively\n  --help    display this help and exit".to_string(),
         "rmdir" => "rmdir [OPTION]... DIRECTORY...\nRemove empty directories\n\nOptions:\n  --help    display this help and exit".to_string(),
+        "true" | ":" => "true\nDo nothing, successfully (exit status 0)\n\nOptions:\n  --help    display this help and exit".to_string(),
+        "false" => "false\nDo nothing, unsuccessfully (exit status 1)\n\nOptions:\n  --help    display this help and exit".to_string(),
+        "break" => "break [N]\nExit from within a for, while or until loop\n\nIf N is given, break out of N enclosing loops.".to_string(),
+        "continue" => "continue [N]\nResume the next iteration of the enclosing for, while or until loop\n\nIf N is given, resume the Nth enclosing loop.".to_string(),
+        "return" => "return [N]\nReturn from a shell function or sourced script\n\nThe status is N, or the status of the last command if N is omitted.".to_string(),
+        "let" => "let EXPR...\nEvaluate arithmetic expressions\n\nEach EXPR uses C operators and can assign variables, e.g. let i+=1.\nThe status is 1 if the last EXPR evaluates to 0, 0 otherwise.".to_string(),
+        "test" | "[" => "test EXPR, [ EXPR ]\nEvaluate a conditional expression\n\nFiles:    -e -f -d -L -r -w -x -s FILE, A -nt B, A -ot B, A -ef B\nStrings:  -z S, -n S, A = B, A != B, A < B, A > B\nNumbers:  A -eq B, -ne, -lt, -le, -gt, -ge\nCombine with ! EXPR, EXPR -a EXPR, EXPR -o EXPR and ( EXPR ).\nStatus is 0 for true, 1 for false and 2 for a malformed expression.\n\n[[ EXPR ]] is the shell version: no word splitting or globbing,\n&& || ! and ( ) to combine, == and != match the right side as a\npattern and =~ as a regular expression.".to_string(),
+        "read" => "read [-r] [-p PROMPT] [NAME...]\nRead a line from standard input into variables\n\nThe line is split on IFS; the last NAME gets the rest of it. With no\nNAME the whole line goes in REPLY. Backslashes escape the next char\nunless -r is given. Status is 1 at end of input, so\n  while read -r line; do ...; done < file\nwalks a file line by line.".to_string(),
+        "printf" => "printf [-v VAR] FORMAT [ARGUMENT...]\nFormat and print arguments\n\nFORMAT takes C style conversions - %s %d %i %u %o %x %X %c %f %e %g\nwith flags, width and precision (%-8s, %05.1f, %*d) - plus %b (expand\nescapes in the argument), %q (shell quoted) and %%. The format is\nreused until all the arguments are used. -v VAR stores the result\nin VAR instead of printing it.".to_string(),
+        "shift" => "shift [N]\nShift the positional parameters left by N (default 1)".to_string(),
+        "exit" => "exit [N]\nStop the current script or command line with status N (default $?)".to_string(),
+        "eval" => "eval [ARG...]\nJoin the arguments into one string and run it as a command".to_string(),
+        "exec" => "exec COMMAND [ARG...]\nRun COMMAND in place of the shell\n\nNothing after it in the script or line runs; its status is the result.".to_string(),
+        "trap" => "trap [-lp] [[ACTION] SIGNAL...]\nRun ACTION when the shell gets SIGNAL, or EXIT when it exits\n\nOptions:\n  -l        list the signal names and numbers\n  -p        show the traps that are set\n\nAn empty ACTION ignores the signal, - puts it back to the default.\nTraps run between commands; ctrl-c is SIGINT, kill $$ sends TERM.\nA script's traps are its own, and its EXIT trap runs as it ends.\nWith no INT trap, ctrl-c stops a loop or sourced script at the next\ncommand and $? is 130.".to_string(),
+        "local" => "local NAME[=VALUE]...\nCreate variables visible only inside the current function\n\nThe previous values come back when the function returns.".to_string(),
         "cat" => "cat [OPTION]... [FILE]...\nConcatenate FILE(s) and print on the standard output\n\nOptions:\n  --help    display this help and exit".to_string(),
         "grep" => "grep [OPTION]... PATTERN [FILE]...\nSearch for PATTERN in each FILE\n\nOptions:\n  -i        ignore case distinctions\n  --help    display this help and exit".to_string(),
         "sed" => "sed [OPTION]... SCRIPT [FILE]...\nStream editor for filtering and transforming text\n\nOptions:\n  --help    display this help and exit".to_string(),
@@ -150,17 +172,28 @@ fn get_command_help(cmd_name: &str) -> String {
         "chgrp" => "chgrp [OPTION]... GROUP FILE...\nChange group ownership\n\nOptions:\n  --help    display this help and exit".to_string(),
         "ln" => "ln [OPTION]... TARGET LINK_NAME\nCreate a link to TARGET with the name LINK_NAME\n\nOptions:\n  -s        make symbolic links instead of hard links\n  --help    display this help and exit".to_string(),
         "ps" => "ps [OPTION]...\nReport a snapshot of the current processes\n\nOptions:\n  -e, -A    select all processes\n  --help    display this help and exit".to_string(),
-        "kill" => "kill [OPTION]... PID...\nTerminate processes by PID\n\nOptions:\n  -9        force kill\n  --help    display this help and exit".to_string(),
+        "kill" => "kill [OPTION]... PID|%JOB...\nSend a signal (default TERM) to processes or jobs\n\nOptions:\n  -s SIG, -SIG  the signal to send, e.g. -9 or -STOP\n  -l            list the signal names\n  --help        display this help and exit".to_string(),
         "killall" => "killall [OPTION]... NAME...\nKill processes by name\n\nOptions:\n  --help    display this help and exit".to_string(),
+        "jobs" => "jobs [-lprs] [JOBSPEC...]\nList the jobs started with &\n\nOptions:\n  -l        show the pid too\n  -p        only the pids\n  -r        only running jobs\n  -s        only stopped jobs\n\nA job is %N, %+ (the current one), %- (the one before) or %NAME.\nFinished jobs are listed once as Done, then dropped.\n\nThe command itself still runs to the end before the prompt comes back,\nso `while ...; done &` or a slow builtin holds it up. Only what it leaves\ngoing - a ping, a long cpu run - carries on in the background.".to_string(),
+        "fg" => "fg [JOBSPEC]\nBring a job to the foreground, continuing it if it was stopped".to_string(),
+        "bg" => "bg [JOBSPEC...]\nContinue stopped jobs in the background".to_string(),
+        "wait" => "wait [PID|JOBSPEC...]\nCollect the status of finished jobs (all of them with no arguments)\n\nA job that's still running can't be waited on in the browser; its\nDone notice shows when it's over.".to_string(),
+        "disown" => "disown [-a] [JOBSPEC...]\nRemove jobs from the job table, leaving them running\n\nOptions:\n  -a        all jobs".to_string(),
+        "sleep" => "sleep NUMBER[SUFFIX]...\nPause for the total of the NUMBERs\n\nSuffixes are s (seconds, the default), m, h and d, and NUMBER can have\na fraction. In the browser the pause can't hold up the commands after\nit; it shows in ps until it's over, and `sleep 10 &` is a job that's\nDone when the time's up.".to_string(),
+        "watch" => "watch [-n SECS] [-t] COMMAND...\nRun COMMAND every SECS seconds (default 2), showing each run's output\n\nOptions:\n  -n, --interval SECS   how often, down to 0.1\n  -t, --no-title        leave out the `Every ...` header\n\nIt carries on in the background until it's killed - ctrl-c, or\n`killall watch`.".to_string(),
+        "at" => "at [-f FILE] TIME\nRun the commands on stdin (or in FILE) once, at TIME\n\nTIME is `now`, HH:MM, HHMM, 4pm, noon, midnight or teatime, then\noptionally today or tomorrow, then optionally `+ N minutes|hours|days|weeks`.\nA time of day that's passed means tomorrow.\n\nOptions:\n  -f FILE   read the commands from FILE\n  -l        list the queued jobs, like atq\n  -r JOB    remove jobs, like atrm\n  -c JOB    show what a job will run\n\nExample: echo 'date >> /tmp/when' | at now + 5 minutes".to_string(),
+        "atq" => "atq\nList the jobs queued with at, soonest first".to_string(),
+        "atrm" => "atrm JOB...\nRemove jobs queued with at before they run".to_string(),
+        "crontab" => "crontab [FILE | -l | -r]\nInstall FILE (or stdin) as your crontab, show it or remove it\n\nOptions:\n  -l        show ~/.crontab\n  -r        remove ~/.crontab\n\nThe cron daemon (pid 2) checks /etc/crontab and ~/.crontab every minute.\nEach line is five times then a command:\n\n  minute hour day-of-month month day-of-week  command\n\nwith * for any, lists (1,15), ranges (9-17), steps (*/10) and names\n(jan, mon-fri). @reboot, @hourly, @daily, @weekly, @monthly and\n@yearly stand in for the times. /etc/crontab has a user column\nbefore the command. Killing cron stops it all.".to_string(),
         "env" => "env [OPTION]... [NAME=VALUE]... [COMMAND [ARG]...]\nRun a program in a modified environment\n\nOptions:\n  --help    display this help and exit".to_string(),
         "export" => "export [NAME[=VALUE]]...\nSet export attribute for shell variables\n\nOptions:\n  --help    display this help and exit".to_string(),
         "alias" => "alias [NAME[=VALUE]]...\nDefine or display aliases\n\nOptions:\n  --help    display this help and exit".to_string(),
         "unalias" => "unalias NAME...\nRemove each NAME from the list of defined aliases\n\nOptions:\n  --help    display this help and exit".to_string(),
-        "set" => "set [OPTION]... [NAME[=VALUE]]...\nSet or unset values of shell options and positional parameters\n\nOptions:\n  -e        exit immediately if a command exits with a non-zero status\n  -x        print commands and their arguments as they are executed\n  --help    display this help and exit".to_string(),
-        "source" => "source FILENAME [ARGUMENTS]\nRead and execute commands from FILENAME in the current shell environment\n\nOptions:\n  --help    display this help and exit".to_string(),
+        "set" => "set [OPTION]... [--] [ARG]...\nSet or unset values of shell options and positional parameters\n\nOptions:\n  -e        exit immediately if a command exits with a non-zero status\n  -x        print commands and their arguments as they are executed\n  -o NAME   turn on a named option (errexit, xtrace, nullglob, failglob)\n  +o NAME   turn it off again\n  --        the ARGs after it become $1 $2 ... (none clears them)\n  --help    display this help and exit".to_string(),
+        "source" => "source FILENAME [ARGUMENTS]\nRead and execute commands from FILENAME in the current shell environment\n\nOptions:\n  --help    display this help and exit\n\nA while, until or for (( )) loop gives up after $LOOP_LIMIT rounds\n(100000 if unset, 0 for no limit) so a runaway one can't hang the tab.".to_string(),
         "functions" => "functions\nDisplay all defined shell functions\n\nOptions:\n  --help    display this help and exit".to_string(),
-        "history" => "history\nDisplay command history\n\nOptions:\n  --help    display this help and exit".to_string(),
-        "cpu" => "cpu SUBCOMMAND [ARGS]...\nCPU emulator commands\n\nSubcommands:\n  run FILE      execute assembly file\n  debug FILE    debug assembly file step by step\n  template TYPE create assembly template\n  --help        display this help and exit".to_string(),
+        "history" => "history [N]\nDisplay or edit the command history\n\nOptions:\n  N             show only the last N entries\n  -c            clear the history\n  -d OFFSET     delete entry OFFSET (negative counts from the end)\n  -w [FILE]     write the history to FILE (default $HISTFILE or ~/.history)\n  -r [FILE]     append the lines of FILE to the history\n\nEach line run at the prompt is appended to $HISTFILE (or ~/.history) as\nit goes, and read back in at startup, both kept to the last $HISTSIZE.\nAt the prompt !! is the last command, !N entry N, !-N the Nth from\nlast, !prefix the latest starting with prefix and !$ the last word of\nthe last command. ^old^new reruns the last command with old replaced.".to_string(),
+        "cpu" => "cpu SUBCOMMAND [ARGS]...\nCPU emulator commands\n\nSubcommands:\n  run FILE      execute assembly file\n  debug FILE    debug assembly file step by step\n  template TYPE create assembly template\n  --help        display this help and exit\n\nA long run carries on in the background, unless its output is piped,\nredirected or captured with $(...) - then it finishes first.".to_string(),
         "edit" => "edit FILE\nOpen FILE in nano-style editor\n\nOptions:\n  --help    display this help and exit".to_string(),
         "clear" => "clear\nClear the terminal screen\n\nOptions:\n  --help    display this help and exit".to_string(),
         "storage" => "storage COMMAND [OPTIONS]\nManage persistent file system storage with compression\n\nCommands:\n  save         save current VFS to storage\n  load         load VFS from storage\n  stats        show storage statistics\n  clear        clear all storage\n  autosave     manage auto-save settings\n\nOptions:\n  --help       display this help and exit".to_string(),
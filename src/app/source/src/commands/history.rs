@@ -1,18 +1,131 @@
 use crate::command::{Command, CommandResult};
 use crate::context::TerminalContext;
 
+/// history [N] | -c | -d OFFSET | -w [FILE] | -r [FILE]
+/// Show, edit, save or load the command history (saved in ~/.history).
 pub struct HistoryCommand;
 
 impl Command for HistoryCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-c", "-d", "-r", "-w"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
-        if args.is_empty() {
-            let out = ctx.history.iter().enumerate().map(|(i, cmd)| format!("{:4}  {}", i+1, cmd)).collect::<Vec<_>>().join("\n");
-            Ok(out)
-        } else if args.len() == 1 && args[0] == "-c" {
-            ctx.history.clear();
-            Ok("History cleared".to_string())
-        } else {
-            Err("history: usage: history [-c]".to_string())
+        match args.first().map(String::as_str) {
+            None => Ok(list(ctx, ctx.history.len())),
+            Some("-c") => {
+                ctx.history.clear();
+                Ok("History cleared".to_string())
+            }
+            Some("-d") => {
+                let offset = args.get(1).ok_or("history: -d: option requires an argument")?;
+                // negative offsets count back from the end, like bash
+                let idx = offset.parse::<i64>().ok().and_then(|n| {
+                    let idx = if n < 0 { ctx.history.len() as i64 + n } else { n - 1 };
+                    usize::try_from(idx).ok().filter(|&idx| idx < ctx.history.len())
+                });
+                let idx = idx.ok_or_else(|| format!("history: {}: history position out of range", offset))?;
+                ctx.history.remove(idx);
+                Ok(String::new())
+            }
+            Some("-w") => {
+                let path = file_arg(args, ctx);
+                let mut data = ctx.history.join("\n");
+                data.push('\n');
+                ctx.write_file_with_events(&path, data.as_bytes()).map_err(|e| format!("history: {}: {}", path, e))?;
+                // the whole list's in there now, nothing left to append
+                if path == ctx.history_file() {
+                    ctx.history_unsaved.clear();
+                }
+                Ok(String::new())
+            }
+            Some("-r") => {
+                let path = file_arg(args, ctx);
+                let bytes = ctx.vfs.read_file(&path).map_err(|_| format!("history: {}: No such file or directory", path))?;
+                let lines: Vec<String> = String::from_utf8_lossy(bytes).lines().map(String::from).collect();
+                for line in lines {
+                    ctx.push_history(&line);
+                }
+                Ok(String::new())
+            }
+            Some(n) => match n.parse::<usize>() {
+                Ok(n) => Ok(list(ctx, n)),
+                Err(_) => Err("history: usage: history [-c] [-d offset] [-w|-r [file]] [n]".to_string()),
+            },
         }
     }
 }
+
+// the last `count` entries, numbered from the start of the history
+fn list(ctx: &TerminalContext, count: usize) -> String {
+    let skip = ctx.history.len().saturating_sub(count);
+    ctx.history.iter().enumerate().skip(skip).map(|(i, cmd)| format!("{:4}  {}", i + 1, cmd)).collect::<Vec<_>>().join("\n")
+}
+
+fn file_arg(args: &[String], ctx: &TerminalContext) -> String {
+    match args.get(1) {
+        Some(file) => ctx.resolve_path(file),
+        None => ctx.history_file(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(args: &[&str], ctx: &mut TerminalContext) -> CommandResult {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        HistoryCommand.execute(&args, ctx)
+    }
+
+    #[test]
+    fn test_history_delete_and_read() {
+        let mut ctx = TerminalContext::new();
+        for line in ["ls", "pwd", "echo hi", "cd /"] {
+            ctx.push_history(line);
+        }
+        history(&["-d", "2"], &mut ctx).unwrap();
+        history(&["-d", "-1"], &mut ctx).unwrap();
+        assert_eq!(ctx.history, vec!["ls", "echo hi"]);
+        assert!(history(&["-d", "5"], &mut ctx).is_err());
+        assert_eq!(history(&["1"], &mut ctx).unwrap(), "   2  echo hi");
+
+        ctx.vfs.create_file("/home/.history", b"old one\nold two\n".to_vec()).unwrap();
+        history(&["-r"], &mut ctx).unwrap();
+        assert_eq!(ctx.history, vec!["ls", "echo hi", "old one", "old two"]);
+        assert!(history(&["-r", "/missing"], &mut ctx).is_err());
+    }
+
+    #[test]
+    fn test_history_saved_as_it_goes() {
+        let mut ctx = TerminalContext::new();
+        let saved = |ctx: &TerminalContext, path: &str| String::from_utf8_lossy(ctx.vfs.read_file(path).unwrap()).into_owned();
+        ctx.add_history("ls");
+        ctx.add_history("  ");
+        ctx.add_history("pwd");
+        // nothing's written until the batch is flushed
+        assert!(ctx.vfs.read_file("/home/.history").is_err());
+        ctx.flush_history();
+        assert_eq!(saved(&ctx, "/home/.history"), "ls\npwd\n");
+
+        ctx.set_var("HISTSIZE", "2");
+        ctx.add_history("echo hi");
+        ctx.flush_history();
+        assert_eq!(saved(&ctx, "/home/.history"), "pwd\necho hi\n");
+
+        // -w already wrote everything, a flush after it adds nothing twice
+        ctx.add_history("date");
+        history(&["-w"], &mut ctx).unwrap();
+        ctx.flush_history();
+        assert_eq!(saved(&ctx, "/home/.history"), "echo hi\ndate\n");
+
+        ctx.set_var("HISTFILE", "/tmp/hist");
+        ctx.add_history("cd /");
+        ctx.flush_history();
+        assert_eq!(saved(&ctx, "/tmp/hist"), "cd /\n");
+
+        // loading starts over from the file rather than adding to it
+        ctx.load_history();
+        assert_eq!(ctx.history, vec!["cd /"]);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+use crate::process::SIGCONT;
+
+/// jobs [-lprs] [JOBSPEC...]
+/// List the background jobs started with `&`.
+pub struct JobsCommand;
+
+/// fg [JOBSPEC]
+/// Bring a job back to the foreground, continuing it if it was stopped.
+pub struct FgCommand;
+
+/// bg [JOBSPEC...]
+/// Continue stopped jobs in the background.
+pub struct BgCommand;
+
+/// wait [PID|JOBSPEC...]
+/// Collect the status of finished jobs.
+pub struct WaitCommand;
+
+/// disown [-a] [JOBSPEC...]
+/// Drop jobs from the job table, leaving them running.
+pub struct DisownCommand;
+
+impl Command for JobsCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-l", "-p", "-r", "-s"]
+    }
+
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        let (flags, specs): (Vec<_>, Vec<_>) = args.iter().partition(|a| a.starts_with('-') && a.len() > 1);
+        let flags: String = flags.iter().map(|f| &f[1..]).collect();
+        if let Some(bad) = flags.chars().find(|c| !"lprs".contains(*c)) {
+            return Err(format!("jobs: -{}: invalid option\njobs: usage: jobs [-lprs] [jobspec ...]", bad));
+        }
+        let mut jobs = ctx.processes.jobs();
+        if !specs.is_empty() {
+            let mut picked = Vec::new();
+            for spec in specs {
+                let job = ctx.processes.find_job(Some(spec)).map_err(|e| format!("jobs: {}", e))?;
+                picked.extend(jobs.iter().filter(|(_, j)| j.id == job.id).cloned());
+            }
+            jobs = picked;
+        }
+        let mut lines = Vec::new();
+        for (mark, job) in jobs {
+            if (flags.contains('r') && (job.is_done() || job.stopped())) || (flags.contains('s') && !job.stopped()) {
+                continue;
+            }
+            if flags.contains('p') {
+                lines.push(job.pid.to_string());
+                continue;
+            }
+            lines.push(job.line(mark, flags.contains('l')));
+            // a finished job is listed the once, then it's gone
+            job.report();
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+impl Command for FgCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        let job = ctx.processes.find_job(args.first().map(String::as_str)).map_err(|e| format!("fg: {}", e))?;
+        if job.is_done() {
+            job.report();
+            ctx.exit_code = Some(job.status());
+            return Ok(job.cmd.clone());
+        }
+        ctx.processes.signal_job(&job, SIGCONT).map_err(|e| format!("fg: {}", e))?;
+        // it's the foreground's now: its output keeps coming, but there's
+        // no Done notice for it and ctrl-c is what stops it
+        ctx.processes.remove_job(job.id);
+        Ok(job.cmd.clone())
+    }
+}
+
+impl Command for BgCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        let specs: Vec<Option<&str>> = match args.is_empty() {
+            true => vec![None],
+            false => args.iter().map(|a| Some(a.as_str())).collect(),
+        };
+        let mut lines = Vec::new();
+        let mut errors = Vec::new();
+        for spec in specs {
+            let job = match ctx.processes.find_job(spec) {
+                Ok(job) => job,
+                Err(e) => {
+                    errors.push(format!("bg: {}", e));
+                    continue;
+                }
+            };
+            if !job.stopped() {
+                lines.push(format!("bg: job {} already in background", job.id));
+                continue;
+            }
+            ctx.processes.signal_job(&job, SIGCONT).map_err(|e| format!("bg: {}", e))?;
+            lines.push(format!("[{}] {} &", job.id, job.cmd));
+        }
+        match errors.is_empty() {
+            true => Ok(lines.join("\n")),
+            false => Err(lines.into_iter().chain(errors).collect::<Vec<_>>().join("\n")),
+        }
+    }
+}
+
+impl Command for WaitCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        // nothing can block the page while a job runs, so this only
+        // collects what's finished and says what hasn't
+        let jobs = match args.is_empty() {
+            true => ctx.processes.jobs().into_iter().map(|(_, job)| job).collect(),
+            false => {
+                let mut jobs = Vec::new();
+                for arg in args {
+                    let job = match arg.parse::<u32>() {
+                        Ok(pid) => ctx.processes.jobs().into_iter().map(|(_, job)| job).find(|job| job.pids().contains(&pid) || job.pid == pid),
+                        Err(_) => ctx.processes.find_job(Some(arg)).ok(),
+                    };
+                    match job {
+                        Some(job) => jobs.push(job),
+                        None if arg.starts_with('%') => return Err(format!("wait: {}: no such job", arg)),
+                        None => {
+                            ctx.exit_code = Some(127);
+                            return Err(format!("wait: pid {} is not a child of this shell", arg));
+                        }
+                    }
+                }
+                jobs
+            }
+        };
+        let mut status = 0;
+        for job in jobs {
+            if !job.is_done() {
+                ctx.exit_code = Some(1);
+                return Err(format!("wait: %{}: still running - it can't be waited on here, its Done notice will show", job.id));
+            }
+            // collected, so there's no notice for it
+            job.report();
+            status = job.status();
+        }
+        ctx.exit_code = Some(status);
+        Ok(String::new())
+    }
+}
+
+impl Command for DisownCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-a"]
+    }
+
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        if args.iter().any(|a| a == "-a") {
+            for (_, job) in ctx.processes.jobs() {
+                ctx.processes.remove_job(job.id);
+            }
+            return Ok(String::new());
+        }
+        let specs: Vec<Option<&str>> = match args.is_empty() {
+            true => vec![None],
+            false => args.iter().map(|a| Some(a.as_str())).collect(),
+        };
+        for spec in specs {
+            let job = ctx.processes.find_job(spec).map_err(|e| format!("disown: {}", e))?;
+            ctx.processes.remove_job(job.id);
+        }
+        Ok(String::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::CommandRegistry;
+    use crate::context::TerminalContext;
+    use crate::shell::exec;
+    use std::sync::Arc;
+
+    fn run(line: &str, ctx: &mut TerminalContext) -> Result<String, String> {
+        exec::run(line, ctx, &CommandRegistry::default_commands())
+    }
+
+    #[test]
+    fn test_background_jobs() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_command_registry(Arc::new(CommandRegistry::default_commands()));
+        assert_eq!(run("echo ${!:-unset}", &mut ctx).unwrap(), "unset\n");
+
+        // the job runs straight away and says which it was
+        assert_eq!(run("echo hi && false &", &mut ctx).unwrap(), "[1] 102\nhi\n");
+        assert_eq!(ctx.last_status, 0);
+        assert_eq!(run("echo $!", &mut ctx).unwrap(), "102\n");
+        assert_eq!(run("true & echo next", &mut ctx).unwrap(), "[2] 105\nnext\n");
+        assert_eq!(run("jobs", &mut ctx).unwrap(), "[1]-  Exit 1                  echo hi && false\n[2]+  Done                    true");
+        // listed as done the once, then gone
+        assert_eq!(run("jobs", &mut ctx).unwrap(), "");
+        assert_eq!(run("fg", &mut ctx).unwrap_err(), "fg: current: no such job");
+
+        run("false &", &mut ctx).ok();
+        assert_eq!(run("wait %1", &mut ctx).unwrap_err(), "");
+        assert_eq!(ctx.last_status, 1);
+        assert_eq!(run("jobs; wait 999", &mut ctx).unwrap_err(), "wait: pid 999 is not a child of this shell");
+        assert_eq!(ctx.last_status, 127);
+
+        // the job's own command runs to the end first - a loop sent off
+        // with & has finished before the next command starts
+        let shown = run("for i in 1 2; do echo $i; done & echo after", &mut ctx).unwrap();
+        assert!(shown.ends_with("\n1\n2\nafter\n"), "{:?}", shown);
+        assert!(run("jobs", &mut ctx).unwrap().contains("Done"));
+    }
+}
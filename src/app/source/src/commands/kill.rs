@@ -1,61 +1,67 @@
 use crate::command::{Command, CommandResult};
 use crate::context::TerminalContext;
+use crate::process::{parse_signal, signal_name, SIGNALS, SIGTERM};
 
 pub struct KillCommand;
 
 const KILL_VERSION: &str = "kill 1.0.0";
-const KILL_HELP: &str = r#"Usage: kill [options] <pid> [...]
+const KILL_HELP: &str = r#"Usage: kill [options] <pid>|<%job> [...]
 Send a signal to a process.
 
   -s, --signal SIGNAL   specify the signal to send (default: TERM)
-  -l, --list            list signal names
+  -SIGNAL               the same, e.g. -9 or -KILL
+  -l, --list [SIGNAL]   list signal names, or name a signal number
       --help            display this help and exit
       --version         output version information and exit
 
-This is a virtual shell. Only simulated processes are affected.
+STOP and CONT pause and resume a job, most others end it. The shell
+itself ignores signals; see ps for the pids, or give a job as %N.
 "#;
 
-const SIGNALS: &[&str] = &["HUP", "INT", "QUIT", "ILL", "ABRT", "FPE", "KILL", "SEGV", "PIPE", "ALRM", "TERM", "USR1", "USR2", "CHLD", "CONT", "STOP", "TSTP", "TTIN", "TTOU"];
-
 impl Command for KillCommand {
-    fn execute(&self, args: &[String], _ctx: &mut TerminalContext) -> CommandResult {
-        // quick exits for help, version and signal list
+    fn options(&self) -> &'static [&'static str] {
+        &["-l", "-s", "--list", "--signal", "--help", "--version"]
+    }
+
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        // quick exits for help and version
         if args.iter().any(|a| a == "--help") {
             return Ok(KILL_HELP.to_string());
         }
         if args.iter().any(|a| a == "--version") {
             return Ok(KILL_VERSION.to_string());
         }
-        if args.iter().any(|a| a == "-l" || a == "--list") {
-            return Ok(SIGNALS.join(" ")); // just dump all signals
-        }
         
         // defaults
-        let mut signal = "TERM"; // default signal
+        let mut signal = SIGTERM;
         let mut pids = Vec::new();
         
         // parse args manually cuz why not
         let mut i = 0;
         while i < args.len() {
             match args[i].as_str() {
-                "-s" | "--signal" => {
+                // kill -l lists them all, kill -l 9 names one
+                "-l" | "--list" => {
+                    return match args.get(i + 1) {
+                        Some(n) => parse_signal(n).filter(|&n| n != 0).map(|n| signal_name(n).to_string()).ok_or_else(|| format!("kill: {}: invalid signal specification", n)),
+                        None => Ok(SIGNALS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(" ")),
+                    };
+                }
+                "-s" | "--signal" | "-n" => {
                     // grab next arg as signal value
                     i += 1;
-                    if i < args.len() {
-                        signal = &args[i];
-                    } else {
-                        return Err("kill: option requires an argument -- 's'".to_string());
-                    }
+                    let spec = args.get(i).ok_or("kill: option requires an argument -- 's'")?;
+                    signal = parse_signal(spec).ok_or_else(|| format!("kill: {}: invalid signal specification", spec))?;
+                }
+                "--" => {
+                    pids.extend(args[i + 1..].iter().cloned());
+                    break;
                 }
-                s if s.starts_with('-') => {}, // ignore other flags
-                s => {
-                    // anything else should be a pid
-                    if let Ok(pid) = s.parse::<u32>() {
-                        pids.push(pid);
-                    } else {
-                        return Err(format!("kill: invalid pid '{}': not a number", s));
-                    }
+                // -9, -KILL, -SIGKILL
+                s if s.starts_with('-') && pids.is_empty() => {
+                    signal = parse_signal(&s[1..]).ok_or_else(|| format!("kill: {}: invalid signal specification", &s[1..]))?;
                 }
+                s => pids.push(s.to_string()),
             }
             i += 1; // next arg
         }
@@ -65,12 +71,23 @@ impl Command for KillCommand {
             return Err("kill: missing pid operand".to_string());
         }
         
-        // fake it till you make it
-        // TODO: impl. real
-        let mut output = Vec::new();
+        // carry on past a bad one, but the status says something failed
+        let mut errors = Vec::new();
         for pid in pids {
-            output.push(format!("Sent signal {} to pid {}", signal, pid));
+            let result = match pid.parse::<u32>() {
+                Ok(pid) => ctx.processes.signal(pid, signal),
+                // %1 and friends - every process in the job
+                Err(_) if pid.starts_with('%') => ctx.processes.find_job(Some(&pid)).and_then(|job| ctx.processes.signal_job(&job, signal)),
+                Err(_) => Err(format!("{}: arguments must be process or job IDs", pid)),
+            };
+            if let Err(e) = result {
+                errors.push(format!("kill: {}", e));
+            }
+        }
+        if errors.is_empty() {
+            Ok(String::new())
+        } else {
+            Err(errors.join("\n"))
         }
-        Ok(output.join("\n")) // one msg per line
     }
 }
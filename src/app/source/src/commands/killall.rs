@@ -1,6 +1,6 @@
 use crate::command::{Command, CommandResult};
 use crate::context::TerminalContext;
-use crate::commands::ps::VirtualProcess;
+use crate::process::{parse_signal, SIGNALS, SIGTERM};
 
 pub struct KillallCommand;
 
@@ -13,17 +13,15 @@ Send a signal to all processes running any of the specified commands.
       --help            display this help and exit
       --version         output version information and exit
 
-This is a virtual shell. Only simulated processes are affected.
+Names are matched against the command, as ps shows it, without its
+directory. The shell itself ignores signals.
 "#;
 
-const SIGNALS: &[&str] = &["HUP", "INT", "QUIT", "ILL", "ABRT", "FPE", "KILL", "SEGV", "PIPE", "ALRM", "TERM", "USR1", "USR2", "CHLD", "CONT", "STOP", "TSTP", "TTIN", "TTOU"];
-
-fn get_virtual_processes(ctx: &TerminalContext) -> Vec<VirtualProcess> {
-    // Use the same as ps
-    crate::commands::ps::get_virtual_processes(ctx)
-}
-
 impl Command for KillallCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-l", "-s", "--list", "--signal", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         if args.iter().any(|a| a == "--help") {
             return Ok(KILLALL_HELP.to_string());
@@ -32,22 +30,21 @@ impl Command for KillallCommand {
             return Ok(KILLALL_VERSION.to_string());
         }
         if args.iter().any(|a| a == "-l" || a == "--list") {
-            return Ok(SIGNALS.join(" "));
+            return Ok(SIGNALS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(" "));
         }
-        let mut signal = "TERM";
+        let mut signal = SIGTERM;
         let mut names = Vec::new();
         let mut i = 0;
         while i < args.len() {
             match args[i].as_str() {
                 "-s" | "--signal" => {
                     i += 1;
-                    if i < args.len() {
-                        signal = &args[i];
-                    } else {
-                        return Err("killall: option requires an argument -- 's'".to_string());
-                    }
+                    let spec = args.get(i).ok_or("killall: option requires an argument -- 's'")?;
+                    signal = parse_signal(spec).ok_or_else(|| format!("killall: {}: unknown signal", spec))?;
+                }
+                s if s.starts_with('-') => {
+                    signal = parse_signal(&s[1..]).ok_or_else(|| format!("killall: {}: unknown signal", &s[1..]))?;
                 }
-                s if s.starts_with('-') => {},
                 s => names.push(s.to_string()),
             }
             i += 1;
@@ -55,20 +52,24 @@ impl Command for KillallCommand {
         if names.is_empty() {
             return Err("killall: missing process name operand".to_string());
         }
-        let procs = get_virtual_processes(ctx);
-        let mut output = Vec::new();
+        // everything but killall itself
+        let me = ctx.processes.current();
+        let mut errors = Vec::new();
         for name in &names {
-            let mut found = false;
-            for p in &procs {
-                if &p.cmd == name {
-                    output.push(format!("Sent signal {} to {} (pid {})", signal, name, p.pid));
-                    found = true;
-                }
+            let pids: Vec<u32> = ctx.processes.list().iter().filter(|p| p.name() == name && p.pid != me).map(|p| p.pid).collect();
+            if pids.is_empty() {
+                errors.push(format!("{}: no process found", name));
             }
-            if !found {
-                output.push(format!("killall: no process found with name '{}'", name));
+            for pid in pids {
+                if let Err(e) = ctx.processes.signal(pid, signal) {
+                    errors.push(format!("killall: {}", e));
+                }
             }
         }
-        Ok(output.join("\n"))
+        if errors.is_empty() {
+            Ok(String::new())
+        } else {
+            Err(errors.join("\n"))
+        }
     }
 }
@@ -9,6 +9,10 @@ const LN_VERSION: &str = "ln 1.0.0";
 const LN_HELP: &str = "Usage: ln -s TARGET LINK_NAME\nMake a symbolic link to TARGET named LINK_NAME.\n\n  -s             make symbolic links instead of hard links\n      --help     display this help and exit\n      --version  output version information and exit";
 
 impl Command for LnCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-s", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         if args.iter().any(|a| a == "--help") {
             return Ok(LN_HELP.to_string());
@@ -0,0 +1,30 @@
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+use crate::shell::parser::is_name;
+
+/// local name[=value]...
+/// Make variables that only live until the current function returns.
+pub struct LocalCommand;
+
+impl Command for LocalCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        if ctx.locals.is_empty() {
+            return Err("local: can only be used in a function".to_string());
+        }
+        let mut errors = Vec::new();
+        for arg in args {
+            // plain `local x` starts out empty, like bash
+            let (name, value) = arg.split_once('=').unwrap_or((arg, ""));
+            if !is_name(name) {
+                errors.push(format!("local: `{}': not a valid identifier", arg));
+                continue;
+            }
+            ctx.set_local(name, value)?;
+        }
+        if errors.is_empty() {
+            Ok(String::new())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
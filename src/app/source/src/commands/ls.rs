@@ -56,6 +56,10 @@ fn get_type_char(node: &VfsNode) -> char {
 }
 
 impl Command for LsCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-a", "-l", "-1", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         // handle help/version flags - quick exit
         if args.iter().any(|a| a == "--help") {
@@ -22,6 +22,10 @@ Mandatory arguments to long options are mandatory for short options too.
       --version     output version information and exit";
 
 impl Command for MkdirCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-m", "-p", "-v", "-Z", "--parents", "--verbose", "--context", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         if args.is_empty() {
             return Err("Usage: mkdir [OPTION]... DIRECTORY...".to_string());
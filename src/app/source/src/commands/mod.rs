@@ -17,6 +17,12 @@ pub mod chgrp;
 pub mod ps;
 pub mod kill;
 pub mod killall;
+pub mod jobs;
+pub mod trap;
+pub mod sleep;
+pub mod watch;
+pub mod at;
+pub mod crontab;
 pub mod export;
 pub mod env;
 pub mod alias;
@@ -36,3 +42,12 @@ pub mod curl;
 pub mod storage;
 pub mod zip;
 pub mod unzip;
+pub mod truefalse;
+pub mod control;
+pub mod local;
+pub mod arith;
+pub mod test;
+pub mod read;
+pub mod printf;
+pub mod shift;
+pub mod eval;
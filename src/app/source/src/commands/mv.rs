@@ -11,6 +11,10 @@ const MV_VERSION: &str = "mv 1.0.0";
 const MV_HELP: &str = "Usage: mv [OPTION]... [-T] SOURCE DEST\n       mv [OPTION]... SOURCE... DIRECTORY\n       mv [OPTION]... -t DIRECTORY SOURCE...\nRename SOURCE to DEST, or move SOURCE(s) to DIRECTORY.\n\n  -f, --force           do not prompt before overwriting\n  -i, --interactive     prompt before overwrite\n  -n, --no-clobber      do not overwrite an existing file\n  -v, --verbose         explain what is being done\n  -T, --no-target-directory\n  -t, --target-directory=DIRECTORY\n      --help            display this help and exit\n      --version         output version information and exit";
 
 impl Command for MvCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-f", "-i", "-n", "-t", "-T", "-v", "--force", "--interactive", "--no-clobber", "--target-directory", "--no-target-directory", "--verbose", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         if args.iter().any(|a| a == "--help") {
             return Ok(MV_HELP.to_string());
@@ -4,7 +4,9 @@ use crate::context::TerminalContext;
 pub struct PingCommand;
 
 impl Command for PingCommand {
-    fn execute(&self, args: &[String], _ctx: &mut TerminalContext) -> CommandResult {
+    // ctx is only for the wasm side, which keeps the job in ps
+    #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         // parse args like we always do
         let mut count = 4;
         let mut quiet = false;
@@ -47,6 +49,8 @@ impl Command for PingCommand {
 
             let url_clone = url.clone();
             let quiet_clone = quiet;
+            // stays in ps until the last reply, and kill can cut it short
+            let job = ctx.processes.detach(ctx.processes.current()).unwrap_or_default();
             
             // spawn async task because blocking is for noobs
             spawn_local(async move {
@@ -65,6 +69,9 @@ impl Command for PingCommand {
                 };
 
                 for seq in 0..count {
+                    if !job.proceed().await {
+                        break;
+                    }
                     sent += 1;
                     let start_time = Date::now();
                     
@@ -123,6 +130,10 @@ impl Command for PingCommand {
                         gloo_timers::future::TimeoutFuture::new(1000).await;
                     }
                 }
+                // ctrl-c still gets the summary, like the real ping
+                if job.killed().is_some_and(|signal| signal != crate::process::SIGINT) {
+                    return;
+                }
                 
                 // show stats if not quiet
                 if !quiet_clone {
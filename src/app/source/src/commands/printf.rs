@@ -0,0 +1,428 @@
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+use crate::shell::parser::is_name;
+
+/// printf [-v VAR] FORMAT [ARGUMENT...]
+/// Write the ARGUMENTs as FORMAT says, C style: %s %d %x %f %e %g %c %b %q.
+pub struct PrintfCommand;
+
+impl Command for PrintfCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-v"]
+    }
+
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        let (var, args) = match args {
+            [flag, var, rest @ ..] if flag == "-v" => (Some(var), rest),
+            _ => (None, args),
+        };
+        let Some((format, args)) = args.split_first() else {
+            return Err("printf: usage: printf [-v var] format [arguments]".to_string());
+        };
+        let mut p = Printf { args, next: 0, errors: Vec::new() };
+        let mut out = String::new();
+        // the format gets reused until the arguments run out
+        loop {
+            let before = p.next;
+            if p.format(format, &mut out)? || p.next == before || p.next >= args.len() {
+                break;
+            }
+        }
+        if let Some(var) = var {
+            if !is_name(var) {
+                return Err(format!("printf: `{}': not a valid identifier", var));
+            }
+            ctx.assign_var(var, &out);
+            out.clear();
+        }
+        if p.errors.is_empty() {
+            return Ok(out);
+        }
+        // bad numbers still print (as 0), but the status is 1
+        if !out.is_empty() {
+            p.errors.insert(0, out);
+        }
+        Err(p.errors.join("\n"))
+    }
+}
+
+struct Printf<'a> {
+    args: &'a [String],
+    next: usize,
+    errors: Vec<String>,
+}
+
+// the bits of a conversion between the % and the letter
+#[derive(Default)]
+struct Spec {
+    left: bool,  // -
+    plus: bool,  // +
+    space: bool, // ' '
+    alt: bool,   // #
+    zero: bool,  // 0
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Printf<'_> {
+    fn arg(&mut self) -> Option<&str> {
+        let arg = self.args.get(self.next)?;
+        self.next += 1;
+        Some(arg)
+    }
+
+    // one pass over the format. true means \c said to stop altogether
+    fn format(&mut self, format: &str, out: &mut String) -> Result<bool, String> {
+        let chars: Vec<char> = format.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '\\' => {
+                    let (text, used, stop) = escape(&chars[i + 1..], false);
+                    out.push_str(&text);
+                    if stop {
+                        return Ok(true);
+                    }
+                    i += 1 + used;
+                }
+                '%' if chars.get(i + 1) == Some(&'%') => {
+                    out.push('%');
+                    i += 2;
+                }
+                '%' => {
+                    let start = i;
+                    i += 1;
+                    let mut spec = Spec::default();
+                    while let Some(&c) = chars.get(i) {
+                        match c {
+                            '-' => spec.left = true,
+                            '+' => spec.plus = true,
+                            ' ' => spec.space = true,
+                            '#' => spec.alt = true,
+                            '0' => spec.zero = true,
+                            _ => break,
+                        }
+                        i += 1;
+                    }
+                    if chars.get(i) == Some(&'*') {
+                        // a negative * width means left justify, like C
+                        let width = self.int_arg();
+                        spec.left |= width < 0;
+                        spec.width = width.unsigned_abs() as usize;
+                        i += 1;
+                    } else {
+                        spec.width = digits(&chars, &mut i);
+                    }
+                    if chars.get(i) == Some(&'.') {
+                        i += 1;
+                        spec.precision = Some(if chars.get(i) == Some(&'*') {
+                            i += 1;
+                            self.int_arg().max(0) as usize
+                        } else {
+                            digits(&chars, &mut i)
+                        });
+                    }
+                    // length modifiers don't mean anything here
+                    while matches!(chars.get(i), Some('h' | 'l' | 'L' | 'j' | 'z' | 't')) {
+                        i += 1;
+                    }
+                    let Some(&conv) = chars.get(i) else {
+                        return Err(format!("printf: `{}': missing format character", chars[start..].iter().collect::<String>()));
+                    };
+                    i += 1;
+                    if self.convert(conv, &spec, out)? {
+                        return Ok(true);
+                    }
+                }
+                c => {
+                    out.push(c);
+                    i += 1;
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    // one %-conversion. true if a %b argument had a \c in it
+    fn convert(&mut self, conv: char, spec: &Spec, out: &mut String) -> Result<bool, String> {
+        let text = match conv {
+            's' => {
+                let arg = self.arg().unwrap_or_default();
+                match spec.precision {
+                    Some(p) => arg.chars().take(p).collect(),
+                    None => arg.to_string(),
+                }
+            }
+            'b' => {
+                let chars: Vec<char> = self.arg().unwrap_or_default().chars().collect();
+                let (text, stop) = expand_escapes(&chars);
+                out.push_str(&pad(spec, "", &text, false));
+                return Ok(stop);
+            }
+            'q' => quote(self.arg().unwrap_or_default()),
+            'c' => self.arg().unwrap_or_default().chars().next().map(String::from).unwrap_or_default(),
+            'd' | 'i' => {
+                let n = self.int_arg();
+                let sign = if n < 0 { "-" } else if spec.plus { "+" } else if spec.space { " " } else { "" };
+                let digits = with_precision(n.unsigned_abs().to_string(), spec.precision);
+                out.push_str(&pad(spec, sign, &digits, spec.precision.is_none()));
+                return Ok(false);
+            }
+            'u' | 'o' | 'x' | 'X' => {
+                // negative numbers come out as their 64-bit two's complement
+                let n = self.int_arg() as u64;
+                let (digits, prefix) = match conv {
+                    'u' => (n.to_string(), ""),
+                    'o' => (format!("{:o}", n), if spec.alt && n != 0 { "0" } else { "" }),
+                    'x' => (format!("{:x}", n), if spec.alt && n != 0 { "0x" } else { "" }),
+                    _ => (format!("{:X}", n), if spec.alt && n != 0 { "0X" } else { "" }),
+                };
+                let digits = with_precision(digits, spec.precision);
+                out.push_str(&pad(spec, prefix, &digits, spec.precision.is_none()));
+                return Ok(false);
+            }
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' => {
+                let n = self.float_arg();
+                let sign = if n.is_sign_negative() && n != 0.0 { "-" } else if spec.plus { "+" } else if spec.space { " " } else { "" };
+                let precision = spec.precision.unwrap_or(6);
+                let n = n.abs();
+                let text = if !n.is_finite() {
+                    if n.is_nan() { "nan".to_string() } else { "inf".to_string() }
+                } else {
+                    match conv.to_ascii_lowercase() {
+                        'f' => format!("{:.*}", precision, n),
+                        'e' => exponent(n, precision),
+                        _ => general(n, precision, spec.alt),
+                    }
+                };
+                let text = if conv.is_ascii_uppercase() { text.to_uppercase() } else { text };
+                out.push_str(&pad(spec, sign, &text, n.is_finite()));
+                return Ok(false);
+            }
+            other => return Err(format!("printf: `{}': invalid format character", other)),
+        };
+        out.push_str(&pad(spec, "", &text, false));
+        Ok(false)
+    }
+
+    fn int_arg(&mut self) -> i64 {
+        let Some(arg) = self.arg().map(str::to_string) else { return 0 };
+        match parse_int(&arg) {
+            Some(n) => n,
+            None => {
+                self.errors.push(format!("printf: {}: invalid number", arg));
+                0
+            }
+        }
+    }
+
+    fn float_arg(&mut self) -> f64 {
+        let Some(arg) = self.arg().map(str::to_string) else { return 0.0 };
+        match arg.trim().parse::<f64>().ok().or_else(|| parse_int(&arg).map(|n| n as f64)) {
+            Some(n) => n,
+            None => {
+                self.errors.push(format!("printf: {}: invalid number", arg));
+                0.0
+            }
+        }
+    }
+}
+
+fn digits(chars: &[char], i: &mut usize) -> usize {
+    let mut n = 0usize;
+    while let Some(d) = chars.get(*i).and_then(|c| c.to_digit(10)) {
+        n = n.saturating_mul(10).saturating_add(d as usize);
+        *i += 1;
+    }
+    n
+}
+
+// numbers the way the shell writes them: 0x1f, 017, or 'c for a
+// character's code
+fn parse_int(arg: &str) -> Option<i64> {
+    let arg = arg.trim();
+    if arg.is_empty() {
+        return Some(0);
+    }
+    if let Some(rest) = arg.strip_prefix(['\'', '"']) {
+        return Some(rest.chars().next().map(|c| c as i64).unwrap_or(0));
+    }
+    let (negative, digits) = match arg.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, arg.strip_prefix('+').unwrap_or(arg)),
+    };
+    let n = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        i64::from_str_radix(&digits[1..], 8).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    Some(if negative { -n } else { n })
+}
+
+// at least `precision` digits, zero filled
+fn with_precision(digits: String, precision: Option<usize>) -> String {
+    match precision {
+        Some(p) if digits.len() < p => format!("{}{}", "0".repeat(p - digits.len()), digits),
+        // %.0d of 0 prints nothing at all
+        Some(0) if digits == "0" => String::new(),
+        _ => digits,
+    }
+}
+
+// fill out to the field width. zeros go between the sign and the digits
+fn pad(spec: &Spec, sign: &str, body: &str, numeric: bool) -> String {
+    let len = sign.chars().count() + body.chars().count();
+    let fill = spec.width.saturating_sub(len);
+    if spec.left {
+        format!("{}{}{}", sign, body, " ".repeat(fill))
+    } else if spec.zero && numeric {
+        format!("{}{}{}", sign, "0".repeat(fill), body)
+    } else {
+        format!("{}{}{}", " ".repeat(fill), sign, body)
+    }
+}
+
+// %e - rust writes 1.5e2 where C wants 1.5e+02
+fn exponent(n: f64, precision: usize) -> String {
+    let text = format!("{:.*e}", precision, n);
+    let (mantissa, exp) = text.split_once('e').unwrap_or((&text, "0"));
+    let exp: i32 = exp.parse().unwrap_or(0);
+    format!("{}e{}{:02}", mantissa, if exp < 0 { '-' } else { '+' }, exp.abs())
+}
+
+// %g - %e for very big or small numbers, %f otherwise, trailing zeros
+// dropped unless there's a #
+fn general(n: f64, precision: usize, alt: bool) -> String {
+    let p = precision.max(1);
+    let exp = if n == 0.0 {
+        0
+    } else {
+        // after rounding to p digits, 9.9999 might have become 10
+        let text = format!("{:.*e}", p - 1, n);
+        text.split_once('e').and_then(|(_, e)| e.parse::<i32>().ok()).unwrap_or(0)
+    };
+    let text = if exp < -4 || exp >= p as i32 {
+        exponent(n, p - 1)
+    } else {
+        format!("{:.*}", (p as i32 - 1 - exp).max(0) as usize, n)
+    };
+    if alt {
+        return text;
+    }
+    let (mantissa, exp) = match text.split_once('e') {
+        Some((m, e)) => (m.to_string(), format!("e{}", e)),
+        None => (text, String::new()),
+    };
+    let mantissa = if mantissa.contains('.') { mantissa.trim_end_matches('0').trim_end_matches('.').to_string() } else { mantissa };
+    mantissa + &exp
+}
+
+// %q - quoted so the shell would read it back as the same word
+fn quote(arg: &str) -> String {
+    if arg.is_empty() {
+        return "''".to_string();
+    }
+    if arg.chars().all(|c| c.is_ascii_alphanumeric() || "_-./:=,+@%".contains(c)) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+// %b - the argument gets the same escapes as the format itself
+fn expand_escapes(chars: &[char]) -> (String, bool) {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '\\' {
+            let (text, used, stop) = escape(&chars[i + 1..], true);
+            out.push_str(&text);
+            if stop {
+                return (out, true);
+            }
+            i += 1 + used;
+        } else {
+            out.push(chars[i]);
+            i += 1;
+        }
+    }
+    (out, false)
+}
+
+// the escape after a backslash: what it stands for, how many chars it
+// took and whether it was \c. in %b octal escapes start with a 0
+fn escape(rest: &[char], in_arg: bool) -> (String, usize, bool) {
+    let Some(&c) = rest.first() else { return ("\\".to_string(), 0, false) };
+    let simple = match c {
+        '\\' => Some('\\'),
+        'a' => Some('\x07'),
+        'b' => Some('\x08'),
+        'e' | 'E' => Some('\x1b'),
+        'f' => Some('\x0c'),
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        't' => Some('\t'),
+        'v' => Some('\x0b'),
+        '"' => Some('"'),
+        '\'' => Some('\''),
+        _ => None,
+    };
+    if let Some(ch) = simple {
+        return (ch.to_string(), 1, false);
+    }
+    match c {
+        'c' => (String::new(), 1, true),
+        'x' => {
+            let hex: String = rest[1..].iter().take(2).take_while(|c| c.is_ascii_hexdigit()).collect();
+            match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                Some(ch) => (ch.to_string(), 1 + hex.len(), false),
+                None => ("\\x".to_string(), 1, false),
+            }
+        }
+        '0'..='7' => {
+            let skip = usize::from(in_arg && c == '0');
+            let oct: String = rest[skip..].iter().take(3).take_while(|c| c.is_digit(8)).collect();
+            let value = u32::from_str_radix(&oct, 8).unwrap_or(0);
+            (char::from_u32(value).map(String::from).unwrap_or_default(), skip + oct.len(), false)
+        }
+        other => (format!("\\{}", other), 1, false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printf(args: &[&str]) -> CommandResult {
+        let mut ctx = TerminalContext::new();
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        PrintfCommand.execute(&args, &mut ctx)
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(printf(&["%s=%d\\n", "a", "1", "b", "0x10"]).unwrap(), "a=1\nb=16\n");
+        assert_eq!(printf(&["[%5s|%-5s|%.2s]", "ab", "cd", "efgh"]).unwrap(), "[   ab|cd   |ef]");
+        assert_eq!(printf(&["%05d %+d %x %#o %X %c", "-42", "7", "255", "8", "'a", "xyz"]).unwrap(), "-0042 +7 ff 010 61 x");
+        assert_eq!(printf(&["%.2f %8.3f %e %g %g", "3.14159", "2", "12345.678", "0.0001", "1e10"]).unwrap(),
+            "3.14    2.000 1.234568e+04 0.0001 1e+10");
+        assert_eq!(printf(&["%*d|%-*d|", "4", "7", "3", "1"]).unwrap(), "   7|1  |");
+        assert_eq!(printf(&["%b %q", "a\\tb\\0101", "it's"]).unwrap(), "a\tbA 'it'\\''s'");
+        assert_eq!(printf(&["100%%\\x41\\101"]).unwrap(), "100%AA");
+        // missing args are empty/zero, \c stops everything
+        assert_eq!(printf(&["<%s><%d>"]).unwrap(), "<><0>");
+        assert_eq!(printf(&["%s\\c more", "x"]).unwrap(), "x");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(printf(&["%d", "abc"]).unwrap_err(), "0\nprintf: abc: invalid number");
+        assert!(printf(&["%y"]).is_err());
+        assert!(printf(&[]).is_err());
+        let mut ctx = TerminalContext::new();
+        let args: Vec<String> = ["-v", "out", "%03d", "5"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(PrintfCommand.execute(&args, &mut ctx).unwrap(), "");
+        assert_eq!(ctx.lookup_var("out").unwrap(), "005");
+    }
+}
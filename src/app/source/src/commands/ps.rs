@@ -1,5 +1,6 @@
 use crate::command::{Command, CommandResult};
 use crate::context::TerminalContext;
+use chrono::{DateTime, Local};
 
 pub struct PsCommand;
 
@@ -15,31 +16,27 @@ Report a snapshot of the current processes.
       --help     display this help and exit
       --version  output version information and exit
 
-This is a virtual shell. Only simulated processes are shown.
+Shows this shell, the commands and scripts it's running and any jobs
+still going in the background (curl, ping...). Without -e, only the
+ones on the terminal are listed.
 "#;
 
-#[derive(Debug, Clone)]
-pub struct VirtualProcess {
-    pub pid: u32,
-    pub ppid: u32,
-    pub user: String,
-    pub tty: String,
-    pub cmd: String,
-    pub state: String,
-}
-
-pub fn get_virtual_processes(ctx: &TerminalContext) -> Vec<VirtualProcess> {
-    // fake processes
-    // TODO: keep track of real processing
-    vec![
-        VirtualProcess { pid: 1, ppid: 0, user: "root".to_string(), tty: "?".to_string(), cmd: "init".to_string(), state: "S".to_string() },
-        VirtualProcess { pid: 2, ppid: 1, user: "root".to_string(), tty: "?".to_string(), cmd: "kthreadd".to_string(), state: "S".to_string() },
-        VirtualProcess { pid: 100, ppid: 1, user: "user".to_string(), tty: "tty1".to_string(), cmd: "bash".to_string(), state: "S".to_string() },
-        VirtualProcess { pid: 101, ppid: 100, user: "user".to_string(), tty: "tty1".to_string(), cmd: "ps".to_string(), state: "R".to_string() },
-    ]
+// 125 seconds as 02:05, longer ones as 1:02:05
+fn elapsed(started: DateTime<Local>) -> String {
+    let secs = (Local::now() - started).num_seconds().max(0);
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{:02}:{:02}", m, s)
+    }
 }
 
 impl Command for PsCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-A", "-e", "-f", "-o", "-p", "-u", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         // handle help/version flags - quick exit
         if args.iter().any(|a| a == "--help") {
@@ -87,7 +84,11 @@ impl Command for PsCommand {
         }
         
         // get and filter processes
-        let mut procs = get_virtual_processes(ctx);
+        let table = &ctx.processes;
+        let mut procs = table.list();
+        if !show_all {
+            procs.retain(|p| p.tty != "?");
+        }
         if let Some(user) = user_filter {
             procs.retain(|p| p.user == user);
         }
@@ -117,7 +118,10 @@ impl Command for PsCommand {
                         "user" => p.user.clone(),
                         "tty" => p.tty.clone(),
                         "cmd" | "command" | "args" => p.cmd.clone(),
-                        "stat" | "state" => p.state.clone(),
+                        "comm" => p.name().to_string(),
+                        "stat" | "state" => table.state(p).to_string(),
+                        "stime" | "start" => p.started.format("%H:%M").to_string(),
+                        "etime" => elapsed(p.started),
                         _ => "?".to_string(),
                     };
                     out.push_str(&format!("{:>8} ", val));
@@ -126,15 +130,15 @@ impl Command for PsCommand {
             }
         } else if full {
             // full format
-            out.push_str("  PID  PPID USER     TTY      STAT CMD\n");
+            out.push_str("  PID  PPID USER     STIME TTY      STAT CMD\n");
             for p in &procs {
-                out.push_str(&format!("{:5} {:5} {:<8} {:<8} {:<4} {}\n", p.pid, p.ppid, p.user, p.tty, p.state, p.cmd));
+                out.push_str(&format!("{:5} {:5} {:<8} {:<5} {:<8} {:<4} {}\n", p.pid, p.ppid, p.user, p.started.format("%H:%M"), p.tty, table.state(p), p.cmd));
             }
         } else {
             // default format
             out.push_str("  PID TTY      STAT CMD\n");
             for p in &procs {
-                out.push_str(&format!("{:5} {:<8} {:<4} {}\n", p.pid, p.tty, p.state, p.cmd));
+                out.push_str(&format!("{:5} {:<8} {:<4} {}\n", p.pid, p.tty, table.state(p), p.cmd));
             }
         }
         
@@ -0,0 +1,157 @@
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+use crate::shell::parser::is_name;
+
+/// read [-r] [-p PROMPT] [NAME...]
+/// Read one line of stdin and split it on IFS into the NAMEs (REPLY if none).
+pub struct ReadCommand;
+
+impl Command for ReadCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-p", "-r"]
+    }
+
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        self.execute_with_stdin(args, None, ctx)
+    }
+
+    fn execute_with_stdin(&self, args: &[String], stdin: Option<&str>, ctx: &mut TerminalContext) -> CommandResult {
+        let mut raw = false;
+        let mut names = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "-r" if names.is_empty() => raw = true,
+                // input never comes from a terminal, so the prompt has nowhere to go
+                "-p" if names.is_empty() => {
+                    iter.next().ok_or("read: -p: option requires an argument")?;
+                }
+                opt if opt.starts_with('-') && opt.len() > 1 && names.is_empty() => {
+                    ctx.exit_code = Some(2);
+                    return Err(format!("read: {}: invalid option", opt));
+                }
+                name if is_name(name) => names.push(name.to_string()),
+                name => return Err(format!("read: `{}': not a valid identifier", name)),
+            }
+        }
+
+        // nothing left is end of input - status 1, which ends `while read`
+        let input = stdin.unwrap_or_default();
+        if input.is_empty() {
+            for name in &names {
+                ctx.assign_var(name, "");
+            }
+            return Err(String::new());
+        }
+        // unlike bash a last line with no newline still counts, since most
+        // command output here doesn't end in one
+        let (line, used) = take_line(input, raw);
+        ctx.stdin_pos += used;
+
+        if names.is_empty() {
+            let line: String = line.iter().map(|(c, _)| c).collect();
+            ctx.assign_var("REPLY", &line);
+            return Ok(String::new());
+        }
+        let ifs = ctx.lookup_var("IFS").cloned().unwrap_or_else(|| " \t\n".to_string());
+        let mut fields = split(&line, &ifs, names.len()).into_iter();
+        for name in &names {
+            ctx.assign_var(name, &fields.next().unwrap_or_default());
+        }
+        Ok(String::new())
+    }
+}
+
+// the first line of input and how many bytes it used up, newline
+// included. each char remembers whether a backslash protected it from
+// splitting; with -r backslashes are just text
+fn take_line(input: &str, raw: bool) -> (Vec<(char, bool)>, usize) {
+    let mut line = Vec::new();
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\n' => return (line, i + 1),
+            '\\' if !raw => match chars.next() {
+                // backslash-newline carries on onto the next line
+                Some((_, '\n')) => {}
+                Some((_, next)) => line.push((next, true)),
+                None => break,
+            },
+            c => line.push((c, false)),
+        }
+    }
+    (line, input.len())
+}
+
+// split into at most `count` fields the way read does: runs of IFS
+// whitespace count as one separator, other IFS chars each end a field,
+// and the last field gets whatever's left of the line
+fn split(line: &[(char, bool)], ifs: &str, count: usize) -> Vec<String> {
+    let is_sep = |&(c, escaped): &(char, bool)| !escaped && ifs.contains(c);
+    let is_blank = |ch: &(char, bool)| is_sep(ch) && ch.0.is_whitespace();
+    let text = |range: &[(char, bool)]| range.iter().map(|(c, _)| c).collect::<String>();
+
+    let mut i = 0;
+    while i < line.len() && is_blank(&line[i]) {
+        i += 1;
+    }
+    let mut fields = Vec::new();
+    while fields.len() + 1 < count && i < line.len() {
+        let start = i;
+        while i < line.len() && !is_sep(&line[i]) {
+            i += 1;
+        }
+        fields.push(text(&line[start..i]));
+        // one separator, with any whitespace around it
+        while i < line.len() && is_blank(&line[i]) {
+            i += 1;
+        }
+        if i < line.len() && is_sep(&line[i]) {
+            i += 1;
+            while i < line.len() && is_blank(&line[i]) {
+                i += 1;
+            }
+        }
+    }
+    let mut end = line.len();
+    while end > i && is_blank(&line[end - 1]) {
+        end -= 1;
+    }
+    fields.push(text(&line[i..end]));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(args: &[&str], stdin: &str, ctx: &mut TerminalContext) -> CommandResult {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        ReadCommand.execute_with_stdin(&args, Some(stdin), ctx)
+    }
+
+    #[test]
+    fn test_read_splits_on_ifs() {
+        let mut ctx = TerminalContext::new();
+        read(&["a", "b"], "  one two  three \nnext", &mut ctx).unwrap();
+        assert_eq!((ctx.lookup_var("a").unwrap().as_str(), ctx.lookup_var("b").unwrap().as_str()), ("one", "two  three"));
+        assert_eq!(ctx.stdin_pos, 18);
+        read(&["x", "y", "z"], "x\\ y z\n", &mut ctx).unwrap();
+        assert_eq!((ctx.lookup_var("x").unwrap().as_str(), ctx.lookup_var("y").unwrap().as_str()), ("x y", "z"));
+        assert_eq!(ctx.lookup_var("z").unwrap(), "");
+        ctx.set_var("IFS", ":");
+        read(&["user", "pw", "rest"], "root:x:0:0::/root", &mut ctx).unwrap();
+        assert_eq!(ctx.lookup_var("rest").unwrap(), "0:0::/root");
+    }
+
+    #[test]
+    fn test_read_raw_and_eof() {
+        let mut ctx = TerminalContext::new();
+        read(&["-r", "line"], "a\\tb \\\nc", &mut ctx).unwrap();
+        assert_eq!(ctx.lookup_var("line").unwrap(), "a\\tb \\");
+        read(&[], "  kept  \n", &mut ctx).unwrap();
+        assert_eq!(ctx.lookup_var("REPLY").unwrap(), "  kept  ");
+        assert!(read(&["line"], "", &mut ctx).is_err());
+        assert!(read(&["1x"], "a", &mut ctx).is_err());
+    }
+}
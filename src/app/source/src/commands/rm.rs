@@ -10,6 +10,10 @@ const RM_VERSION: &str = "rm 1.0.0";
 const RM_HELP: &str = "Usage: rm [OPTION]... [FILE]...\nRemove (unlink) the FILE(s).\n\n  -f, --force           ignore nonexistent files and arguments, never prompt\n  -i                    prompt before every removal\n  -I                    prompt once before removing more than three files, or when removing recursively\n  -r, -R, --recursive   remove directories and their contents recursively\n  -d, --dir             remove empty directories\n  -v, --verbose         explain what is being done\n      --help            display this help and exit\n      --version         output version information and exit";
 
 impl Command for RmCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-d", "-f", "-r", "-R", "-v", "--dir", "--force", "--recursive", "--verbose", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         if args.iter().any(|a| a == "--help") {
             return Ok(RM_HELP.to_string());
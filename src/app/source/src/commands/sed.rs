@@ -16,7 +16,15 @@ Stream editor for filtering and transforming text.
 "#;
 
 impl Command for SedCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-e", "-E", "-n", "-r", "--expression", "--quiet", "--silent", "--regexp-extended", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        self.execute_with_stdin(args, None, ctx)
+    }
+
+    fn execute_with_stdin(&self, args: &[String], stdin: Option<&str>, ctx: &mut TerminalContext) -> CommandResult {
         if args.iter().any(|a| a == "--help") {
             return Ok(SED_HELP.to_string());
         }
@@ -98,9 +106,8 @@ impl Command for SedCommand {
         for file in input_files {
             // grab file contents or bail
             let lines: Vec<String> = if file == "-" {
-                // stdin not implemented, just return empty for now
-                // whatever, we'll fix it later
-                vec![]
+                // piped input, or nothing if run on its own
+                stdin.unwrap_or_default().lines().map(|l| l.to_string()).collect()
             } else {
                 match ctx.vfs.read_file(&file) {
                     Ok(bytes) => String::from_utf8_lossy(bytes).lines().map(|l| l.to_string()).collect(),
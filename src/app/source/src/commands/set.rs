@@ -4,6 +4,19 @@ use crate::context::TerminalContext;
 pub struct SetCommand;
 
 impl Command for SetCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-e", "-o", "-x"]
+    }
+
+    fn complete(&self, args: &[String], _ctx: &TerminalContext) -> Option<Vec<String>> {
+        // option names after -o/+o, nothing else takes an argument
+        let names = ["errexit", "xtrace", "nullglob", "failglob"];
+        match args.last().map(String::as_str) {
+            Some("-o" | "+o") => Some(names.iter().map(|s| s.to_string()).collect()),
+            _ => Some(Vec::new()),
+        }
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         // no args? just dump all vars and options
         if args.is_empty() {
@@ -15,6 +28,8 @@ impl Command for SetCommand {
             // tack on shell options at the end
             out.push(format!("set -e: {}", ctx.options.errexit));
             out.push(format!("set -x: {}", ctx.options.xtrace));
+            out.push(format!("set -o nullglob: {}", ctx.options.nullglob));
+            out.push(format!("set -o failglob: {}", ctx.options.failglob));
             return Ok(out.join("\n"));
         }
 
@@ -26,12 +41,28 @@ impl Command for SetCommand {
                 "+e" => ctx.options.errexit = false, // disable errexit
                 "-x" => ctx.options.xtrace = true,   // enable debug trace
                 "+x" => ctx.options.xtrace = false,  // disable debug trace
-                s if s.contains('=') => {
-                    // handle var assignment (foo=bar)
-                    let mut parts = s.splitn(2, '=');
-                    let name = parts.next().unwrap();
-                    let value = parts.next().unwrap_or(""); // empty val is fine
-                    ctx.vars.insert(name.to_string(), value.to_string());
+                flag @ ("-o" | "+o") => {
+                    // long option names: set -o nullglob / set +o nullglob
+                    i += 1;
+                    let on = flag == "-o";
+                    match args.get(i).map(String::as_str) {
+                        Some("errexit") => ctx.options.errexit = on,
+                        Some("xtrace") => ctx.options.xtrace = on,
+                        Some("nullglob") => ctx.options.nullglob = on,
+                        Some("failglob") => ctx.options.failglob = on,
+                        Some(name) => return Err(format!("set: {}: invalid option name", name)),
+                        None => return Err(format!("set: {}: option name required", flag)),
+                    }
+                }
+                // `set -- a b` - the rest are the new $1 $2..., and with
+                // nothing after the -- they're all cleared
+                "--" => {
+                    ctx.set_args(args[i + 1..].to_vec());
+                    break;
+                }
+                s if !s.starts_with(['-', '+']) => {
+                    ctx.set_args(args[i..].to_vec());
+                    break;
                 }
                 _ => {}, // meh, ignore anything else
             }
@@ -40,3 +71,23 @@ impl Command for SetCommand {
         Ok(String::new()) // nothing to say
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::command::CommandRegistry;
+    use crate::context::TerminalContext;
+    use crate::shell::exec;
+
+    fn run(line: &str, ctx: &mut TerminalContext) -> Result<String, String> {
+        exec::run(line, ctx, &CommandRegistry::default_commands())
+    }
+
+    #[test]
+    fn test_set_positionals() {
+        let mut ctx = TerminalContext::new();
+        assert_eq!(run("set -- a b c; shift; echo $# $1", &mut ctx).unwrap(), "2 b\n");
+        assert_eq!(run("set -e x 'y z'; echo $# \"$2\"", &mut ctx).unwrap(), "2 y z\n");
+        assert!(ctx.options.errexit);
+        assert_eq!(run("set --; echo $#", &mut ctx).unwrap(), "0\n");
+    }
+}
@@ -0,0 +1,21 @@
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+
+/// shift [N]
+/// Drop the first N positional parameters ($2 becomes $1 and so on).
+pub struct ShiftCommand;
+
+impl Command for ShiftCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        let n = match args.first() {
+            None => 1,
+            Some(arg) => arg.parse::<usize>().map_err(|_| format!("shift: {}: numeric argument required", arg))?,
+        };
+        // shifting past the end fails and leaves the args alone
+        if n > ctx.args.len() {
+            return Err(format!("shift: {}: shift count out of range", n));
+        }
+        ctx.args.drain(..n);
+        Ok(String::new())
+    }
+}
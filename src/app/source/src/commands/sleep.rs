@@ -0,0 +1,77 @@
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+
+/// sleep NUMBER[SUFFIX]...
+/// Pause for the total of the NUMBERs - seconds, or with m, h or d.
+pub struct SleepCommand;
+
+// `1.5`, `2m`, `1h` or `1d`, in seconds
+fn interval(arg: &str) -> Option<f64> {
+    let (number, unit) = match arg.strip_suffix(['s', 'm', 'h', 'd']) {
+        Some(number) => (number, &arg[number.len()..]),
+        None => (arg, "s"),
+    };
+    let secs = number.parse::<f64>().ok().filter(|n| n.is_finite() && *n >= 0.0)?;
+    Some(secs * match unit {
+        "m" => 60.0,
+        "h" => 3600.0,
+        "d" => 86400.0,
+        _ => 1.0,
+    })
+}
+
+impl Command for SleepCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        if args.is_empty() {
+            return Err("sleep: missing operand".to_string());
+        }
+        let mut secs = 0.0;
+        for arg in args {
+            secs += interval(arg).ok_or_else(|| format!("sleep: invalid time interval '{}'", arg))?;
+        }
+
+        // the page can't block, so the sleeping carries on after this
+        // returns: it's in ps until it's done, `sleep 10 &` is a job that
+        // finishes when the time's up, and kill or ctrl-c ends it early
+        let job = ctx.processes.detach(ctx.processes.current()).unwrap_or_default();
+        #[cfg(target_arch = "wasm32")]
+        {
+            wasm_bindgen_futures::spawn_local(async move {
+                let end = js_sys::Date::now() + secs * 1000.0;
+                loop {
+                    let left = end - js_sys::Date::now();
+                    if left <= 0.0 || !job.proceed().await {
+                        break;
+                    }
+                    // in short naps so a kill doesn't wait for the whole thing
+                    gloo_timers::future::TimeoutFuture::new(left.min(100.0) as u32).await;
+                }
+            });
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            std::thread::sleep(std::time::Duration::from_secs_f64(secs));
+            drop(job);
+        }
+        Ok(String::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sleep_intervals() {
+        assert_eq!(interval("1.5"), Some(1.5));
+        assert_eq!(interval("2m"), Some(120.0));
+        assert_eq!(interval("1d"), Some(86400.0));
+        assert_eq!(interval("-1"), None);
+        assert_eq!(interval("m"), None);
+
+        let mut ctx = TerminalContext::new();
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(SleepCommand.execute(&args(&["0", "0.01s"]), &mut ctx), Ok(String::new()));
+        assert_eq!(SleepCommand.execute(&args(&["soon"]), &mut ctx).unwrap_err(), "sleep: invalid time interval 'soon'");
+    }
+}
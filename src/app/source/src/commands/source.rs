@@ -1,69 +1,114 @@
-use crate::command::{Command, CommandResult, run_command};
-use crate::context::TerminalContext;
+use crate::command::{Command, CommandResult};
+use crate::context::{Flow, TerminalContext};
+use crate::shell::exec::{self, Output, Stream};
 
 pub struct SourceCommand;
 
 impl Command for SourceCommand {
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
-        // bail if no args given
-        if args.is_empty() {
-            return Err("source: filename argument required".to_string());
+        // only when called directly rather than from the shell, which
+        // uses execute_into - the two streams can't stay apart here
+        let mut out = Output::default();
+        self.execute_into(args, None, ctx, &mut out);
+        // and with no line around it, an exit stops here
+        if ctx.depth == 0 && matches!(ctx.flow, Some(Flow::Exit(_) | Flow::Interrupt)) {
+            ctx.flow = None;
         }
-        let filename = &args[0];
-        
-        // figure out the actual path to the file
-        let file_path = if filename.starts_with('/') {
-            // absolute path, use as is
-            filename.to_string()
-        } else if !filename.contains('/') && ctx.env.get("PATH").is_some() {
-            // no slashes = look in $PATH first
-            let path_env = ctx.env.get("PATH").unwrap();
-            let mut found_path = None;
-            for dir in path_env.split(':') {
-                let full_path = format!("{}/{}", dir, filename);
-                if ctx.vfs.resolve_path(&full_path).is_some() {
-                    // found it, stop looking
-                    found_path = Some(full_path);
-                    break;
-                }
+        match ctx.exit_code {
+            Some(code) if code != 0 => Err(out.render()),
+            _ => Ok(out.render()),
+        }
+    }
+
+    fn execute_into(&self, args: &[String], _stdin: Option<&str>, ctx: &mut TerminalContext, out: &mut Output) -> bool {
+        let status = match source(args, ctx, out) {
+            Ok(status) => status,
+            Err(e) => {
+                out.push(Stream::Err, e);
+                1
             }
-            // fallback to cwd if not in path
-            found_path.unwrap_or(format!("{}/{}", ctx.cwd, filename))
-        } else {
-            // relative path, prepend cwd
-            format!("{}/{}", ctx.cwd, filename)
         };
-        
-        // try to read the file
-        let file_content = match ctx.vfs.read_file(&file_path) {
-            Ok(content_bytes) => {
-                match String::from_utf8(content_bytes.to_vec()) {
-                    Ok(s) => s,
-                    Err(_) => return Err("source: file contains invalid UTF-8".to_string()),
-                }
+        ctx.exit_code = Some(status);
+        true
+    }
+}
+
+// run the file in the current shell, giving back its status
+fn source(args: &[String], ctx: &mut TerminalContext, out: &mut Output) -> Result<i32, String> {
+    // bail if no args given
+    if args.is_empty() {
+        return Err("source: filename argument required".to_string());
+    }
+    let filename = &args[0];
+
+    // figure out the actual path to the file
+    let file_path = if filename.starts_with('/') {
+        // absolute path, use as is
+        filename.to_string()
+    } else if !filename.contains('/') && ctx.env.get("PATH").is_some() {
+        // no slashes = look in $PATH first
+        let path_env = ctx.env.get("PATH").unwrap();
+        let mut found_path = None;
+        for dir in path_env.split(':') {
+            let full_path = format!("{}/{}", dir, filename);
+            if ctx.vfs.resolve_path(&full_path).is_some() {
+                // found it, stop looking
+                found_path = Some(full_path);
+                break;
             }
-            Err(_) => return Err(format!("source: {}: file not found or unreadable", filename)),
-        };
-        
-        // track last cmd result to return at end
-        let mut last_result = Ok(String::new());
-        
-        // borrow checker hack - take ownership of registry temporarily
-        let registry = ctx.registry.take()
-            .ok_or("source: command registry not available".to_string())?;
-        
-        // run each line in the script
-        for line in file_content.lines() {
-            let line = line.trim();
-            // skip empty lines and comments
-            if line.is_empty() || line.starts_with('#') { continue; }
-            last_result = run_command(line, ctx, &registry);
         }
-        
-        // put the registry back when done
-        ctx.registry = Some(registry);
-        
-        // return result of last command
-        last_result
+        // fallback to cwd if not in path
+        found_path.unwrap_or(format!("{}/{}", ctx.cwd, filename))
+    } else {
+        // relative path, prepend cwd
+        format!("{}/{}", ctx.cwd, filename)
+    };
+    
+    // try to read the file
+    let file_content = match ctx.vfs.read_file(&file_path) {
+        Ok(content_bytes) => {
+            match String::from_utf8(content_bytes.to_vec()) {
+                Ok(s) => s,
+                Err(_) => return Err("source: file contains invalid UTF-8".to_string()),
+            }
+        }
+        Err(_) => return Err(format!("source: {}: file not found or unreadable", filename)),
+    };
+    
+    // a clone of the Arc, so eval and $(...) in the script still find it
+    let registry = ctx.registry.clone()
+        .ok_or("source: command registry not available".to_string())?;
+
+    // `source file a b` - a and b become $1 $2 for the script,
+    // with no extra args the caller's positionals are left alone
+    let saved_args = if args.len() > 1 {
+        let old = ctx.args.clone();
+        ctx.set_args(args[1..].to_vec());
+        Some(old)
+    } else {
+        None
+    };
+
+    // hand the whole file to the shell so if/while/for can span lines;
+    // it still runs one command at a time and stops early under set -e
+    ctx.source_depth += 1;
+    let mut status = exec::run_here(&file_content, None, ctx, &registry, out);
+    ctx.source_depth -= 1;
+
+    match ctx.flow {
+        // `return N` at the top of the script ends it with status N
+        Some(Flow::Return(code)) => {
+            ctx.flow = None;
+            status = code;
+        }
+        // exit carries on up and ends the line, but $? is still its code
+        Some(Flow::Exit(code)) => status = code,
+        _ => {}
+    }
+
+    // put the positionals back when done
+    if let Some(old) = saved_args {
+        ctx.set_args(old);
     }
+    Ok(status)
 }
@@ -29,6 +29,15 @@ Examples:
 "#;
 
 impl Command for StorageCommand {
+    fn complete(&self, args: &[String], _ctx: &TerminalContext) -> Option<Vec<String>> {
+        let words: &[&str] = match args.first().map(String::as_str) {
+            None => &["save", "load", "stats", "clear", "autosave"],
+            Some("clear") => &["--force"],
+            Some(_) => &[],
+        };
+        Some(words.iter().map(|s| s.to_string()).collect())
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         // handle help and version flags
         if args.iter().any(|a| a == "--help") {
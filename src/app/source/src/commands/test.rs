@@ -0,0 +1,41 @@
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+use crate::shell::cond;
+
+/// test EXPR
+/// Check files and compare strings or numbers; status 0 when EXPR is true.
+pub struct TestCommand;
+
+/// [ EXPR ]
+/// Same as test, but the last argument has to be `]`.
+pub struct BracketCommand;
+
+// true is status 0, false status 1, and a malformed expression status 2
+fn run(name: &str, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+    match cond::test(args, ctx) {
+        Ok(true) => Ok(String::new()),
+        Ok(false) => Err(String::new()),
+        Err(e) => {
+            ctx.exit_code = Some(2);
+            Err(format!("{}: {}", name, e))
+        }
+    }
+}
+
+impl Command for TestCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        run("test", args, ctx)
+    }
+}
+
+impl Command for BracketCommand {
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        match args.split_last() {
+            Some((last, rest)) if last == "]" => run("[", rest, ctx),
+            _ => {
+                ctx.exit_code = Some(2);
+                Err("[: missing `]'".to_string())
+            }
+        }
+    }
+}
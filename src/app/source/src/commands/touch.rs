@@ -11,6 +11,10 @@ const TOUCH_VERSION: &str = "touch 1.0.0";
 const TOUCH_HELP: &str = "Usage: touch [OPTION]... FILE...\nUpdate the access and modification times of each FILE to the current time.\n\n  -a         change only the access time\n  -m         change only the modification time\n      --help     display this help and exit\n      --version  output version information and exit";
 
 impl Command for TouchCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-a", "-m", "--help", "--version"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         if args.iter().any(|a| a == "--help") {
             return Ok(TOUCH_HELP.to_string());
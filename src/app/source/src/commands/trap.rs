@@ -0,0 +1,153 @@
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+use crate::process::{parse_signal, signal_name, SIGNALS};
+
+/// trap [-lp] [[ACTION] SIGNAL...]
+/// Run ACTION when the shell gets SIGNAL, or when it exits (EXIT).
+pub struct TrapCommand;
+
+// EXIT, or a signal name without the SIG - how ctx.traps keys them
+fn trap_name(spec: &str) -> Option<String> {
+    if spec == "0" || spec.eq_ignore_ascii_case("EXIT") {
+        return Some("EXIT".to_string());
+    }
+    parse_signal(spec).filter(|&n| n != 0).map(|n| signal_name(n).to_string())
+}
+
+// `trap -- 'echo bye' EXIT` - what -p prints, ready to be run again
+fn show(name: &str, action: &str) -> String {
+    let signal = if name == "EXIT" { name.to_string() } else { format!("SIG{}", name) };
+    format!("trap -- '{}' {}", action.replace('\'', "'\\''"), signal)
+}
+
+impl Command for TrapCommand {
+    fn complete(&self, args: &[String], _ctx: &TerminalContext) -> Option<Vec<String>> {
+        // the action comes first, then signals
+        (!args.is_empty()).then(|| std::iter::once("EXIT").chain(SIGNALS.iter().map(|(name, _)| *name)).map(String::from).collect())
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        &["-l", "-p"]
+    }
+
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        let args = match args.first().map(String::as_str) {
+            Some("--") => &args[1..],
+            _ => args,
+        };
+        match args.first().map(String::as_str) {
+            Some("-l") => {
+                return Ok(SIGNALS.iter().map(|(name, num)| format!("{:2}) SIG{}", num, name)).collect::<Vec<_>>().join("\n"));
+            }
+            // the traps that are set, all of them or just the ones asked about
+            None | Some("-p") => {
+                let mut names: Vec<&String> = ctx.traps.keys().collect();
+                if args.len() > 1 {
+                    let wanted: Vec<String> = args[1..].iter().filter_map(|a| trap_name(a)).collect();
+                    names.retain(|name| wanted.contains(name));
+                }
+                names.sort_by_key(|name| parse_signal(name).unwrap_or(0));
+                return Ok(names.iter().map(|name| show(name, &ctx.traps[*name])).collect::<Vec<_>>().join("\n"));
+            }
+            _ => {}
+        }
+
+        // `trap INT` on its own resets INT, like `trap - INT`
+        let (action, signals) = match trap_name(&args[0]) {
+            Some(_) if args.len() == 1 => (None, args),
+            _ if args[0] == "-" => (None, &args[1..]),
+            _ => (Some(args[0].clone()), &args[1..]),
+        };
+        if signals.is_empty() {
+            return Err("trap: usage: trap [-lp] [[arg] signal_spec ...]".to_string());
+        }
+        let mut errors = Vec::new();
+        for spec in signals {
+            let Some(name) = trap_name(spec) else {
+                errors.push(format!("trap: {}: invalid signal specification", spec));
+                continue;
+            };
+            // an empty action still counts: the signal is then ignored
+            match &action {
+                Some(action) => ctx.traps.insert(name, action.clone()),
+                None => ctx.traps.remove(&name),
+            };
+        }
+        if errors.is_empty() {
+            Ok(String::new())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::CommandRegistry;
+    use crate::context::{TerminalContext, SHELL_PID};
+    use crate::process::SIGINT;
+    use crate::shell::exec;
+    use std::sync::Arc;
+
+    fn run(line: &str, ctx: &mut TerminalContext) -> Result<String, String> {
+        exec::run(line, ctx, &CommandRegistry::default_commands())
+    }
+
+    #[test]
+    fn test_traps() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_command_registry(Arc::new(CommandRegistry::default_commands()));
+        run("trap 'echo caught $?' INT TERM; trap 'echo \"it'\\''s over\"' 0", &mut ctx).unwrap();
+        assert_eq!(run("trap", &mut ctx).unwrap(), "trap -- 'echo \"it'\\''s over\"' EXIT\ntrap -- 'echo caught $?' SIGINT\ntrap -- 'echo caught $?' SIGTERM");
+
+        // a signal to the shell runs its trap before the next command
+        assert_eq!(run("false; kill -TERM $$; echo after", &mut ctx).unwrap(), "caught 0\nafter\n");
+        assert_eq!(run("false; kill -INT $$", &mut ctx).unwrap(), "caught 0\n");
+        run("trap - TERM; trap '' INT", &mut ctx).unwrap();
+        assert_eq!(run("kill -TERM $$; kill -INT $$; echo quiet", &mut ctx).unwrap(), "quiet\n");
+        assert_eq!(run("trap -p INT", &mut ctx).unwrap(), "trap -- '' SIGINT");
+        assert_eq!(run("trap x NOPE", &mut ctx).unwrap_err(), "trap: NOPE: invalid signal specification");
+
+        // EXIT runs when the shell is told to exit
+        assert_eq!(run("exit 3", &mut ctx).unwrap_err(), "it's over\n");
+        assert!(!ctx.traps.contains_key("EXIT"));
+    }
+
+    #[test]
+    fn test_script_traps() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_command_registry(Arc::new(CommandRegistry::default_commands()));
+        let script = "trap 'echo cleanup' EXIT\ntrap 'echo stopping; exit 5' TERM\necho start\nkill $$\necho never\n";
+        ctx.vfs.create_file("/bin/job", script.as_bytes().to_vec()).unwrap();
+        run("chmod +x /bin/job; trap 'echo mine' EXIT", &mut ctx).unwrap();
+
+        // the script's traps are its own, and go with it
+        assert_eq!(run("job", &mut ctx).unwrap_err(), "start\nstopping\ncleanup\n");
+        assert_eq!(ctx.last_status, 5);
+        assert_eq!(run("trap", &mut ctx).unwrap(), "trap -- 'echo mine' EXIT");
+    }
+
+    #[test]
+    fn test_interrupt_stops_sourced_loop() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_command_registry(Arc::new(CommandRegistry::default_commands()));
+        let script = "i=0\nwhile true; do\n  i=$((i + 1)); echo $i\n  [ $i -eq 3 ] && kill -INT $$\ndone\necho never\n";
+        ctx.vfs.create_file("/tmp/count.sh", script.as_bytes().to_vec()).unwrap();
+
+        // with no INT trap, ctrl-c drops the script and the rest of the line
+        assert_eq!(run("source /tmp/count.sh; echo after", &mut ctx).unwrap_err(), "1\n2\n3\n");
+        assert_eq!(ctx.last_status, 130);
+        assert_eq!(run("echo $i", &mut ctx).unwrap(), "3\n");
+
+        // one that came in while something else was running is seen
+        // after the script's first command
+        ctx.processes.signal(SHELL_PID, SIGINT).unwrap();
+        assert_eq!(run("source /tmp/count.sh", &mut ctx).unwrap_err(), "");
+        assert_eq!(run("echo $i", &mut ctx).unwrap(), "0\n");
+
+        // a trap takes it instead, and the loop carries on to the end
+        run("trap 'echo caught' INT", &mut ctx).unwrap();
+        ctx.vfs.write_file("/tmp/count.sh", b"for i in 1 2; do kill -INT $$; echo $i; done".to_vec()).unwrap();
+        assert_eq!(run("source /tmp/count.sh", &mut ctx).unwrap(), "caught\n1\ncaught\n2\n");
+    }
+}
@@ -0,0 +1,23 @@
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+
+/// true
+/// Do nothing, successfully. Mostly useful in `&&`/`||` chains and loops.
+pub struct TrueCommand;
+
+/// false
+/// Do nothing, unsuccessfully.
+pub struct FalseCommand;
+
+impl Command for TrueCommand {
+    fn execute(&self, _args: &[String], _ctx: &mut TerminalContext) -> CommandResult {
+        Ok(String::new())
+    }
+}
+
+impl Command for FalseCommand {
+    fn execute(&self, _args: &[String], _ctx: &mut TerminalContext) -> CommandResult {
+        // empty Err = status 1 with nothing printed
+        Err(String::new())
+    }
+}
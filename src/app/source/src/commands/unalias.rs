@@ -4,6 +4,14 @@ use crate::context::TerminalContext;
 pub struct UnaliasCommand;
 
 impl Command for UnaliasCommand {
+    fn complete(&self, _args: &[String], ctx: &TerminalContext) -> Option<Vec<String>> {
+        Some(ctx.aliases.keys().cloned().collect())
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        &["-a"]
+    }
+
     fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
         if args.is_empty() {
             return Err("unalias: usage: unalias [-a] name [name ...]".to_string());
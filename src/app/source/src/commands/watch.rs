@@ -0,0 +1,95 @@
+use chrono::Duration;
+
+use crate::command::{Command, CommandResult};
+use crate::context::TerminalContext;
+use crate::schedule::watch_header;
+use crate::shell::exec;
+
+/// watch [-n SECS] [-t] COMMAND...
+/// Run COMMAND every SECS seconds (2 by default) until it's killed.
+pub struct WatchCommand;
+
+const USAGE: &str = "Usage: watch [-n SECS] [-t] COMMAND...";
+
+impl Command for WatchCommand {
+    fn options(&self) -> &'static [&'static str] {
+        &["-n", "-t", "--interval", "--no-title"]
+    }
+
+    fn execute(&self, args: &[String], ctx: &mut TerminalContext) -> CommandResult {
+        let mut secs = 2.0;
+        let mut title = true;
+        let mut i = 0;
+        while i < args.len() {
+            let value = match args[i].as_str() {
+                "-t" | "--no-title" => {
+                    title = false;
+                    i += 1;
+                    continue;
+                }
+                "-n" | "--interval" => {
+                    i += 1;
+                    args.get(i).map(String::as_str)
+                }
+                arg if arg.starts_with("--interval=") => Some(&arg["--interval=".len()..]),
+                arg if arg.starts_with("-n") => Some(&arg[2..]),
+                "--" => {
+                    i += 1;
+                    break;
+                }
+                arg if arg.starts_with('-') => return Err(format!("watch: invalid option -- '{}'\n{}", arg.trim_start_matches('-'), USAGE)),
+                _ => break,
+            };
+            let value = value.ok_or_else(|| format!("watch: option requires an argument -- 'n'\n{}", USAGE))?;
+            secs = value.parse::<f64>().ok().filter(|n| n.is_finite()).ok_or_else(|| format!("watch: failed to parse argument: '{}'", value))?;
+            i += 1;
+        }
+        if i >= args.len() {
+            return Err(USAGE.to_string());
+        }
+        // like procps, anything quicker than a tenth of a second is bumped up
+        let every = Duration::milliseconds((secs.max(0.1) * 1000.0) as i64);
+        let cmd = args[i..].join(" ");
+
+        let registry = ctx.registry.clone().ok_or("watch: command registry not available".to_string())?;
+        let cwd = ctx.cwd.clone();
+        let output = exec::run_detached(&cmd, &cwd, ctx, &registry);
+        // the refreshes come from the scheduler, for as long as the handle's
+        // held - `kill` or ctrl-c drops it
+        let job = ctx.processes.detach(ctx.processes.current()).unwrap_or_default();
+        ctx.schedule.watch(&cmd, every, title, &cwd, job);
+        let header = if title { watch_header(every, &cmd) } else { String::new() };
+        Ok(header + output.trim_end_matches('\n'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::CommandRegistry;
+    use crate::context::TerminalContext;
+    use crate::shell::exec;
+    use std::sync::Arc;
+
+    fn run(line: &str, ctx: &mut TerminalContext) -> Result<String, String> {
+        exec::run(line, ctx, &CommandRegistry::default_commands())
+    }
+
+    #[test]
+    fn test_watch() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_command_registry(Arc::new(CommandRegistry::default_commands()));
+        let output = run("watch -n 0.5 echo hi", &mut ctx).unwrap();
+        assert!(output.starts_with("Every 0.5s: echo hi    "), "{}", output);
+        assert!(output.ends_with("\n\nhi"), "{}", output);
+        assert_eq!(run("watch -t -n1 'echo $((1 + 1))'", &mut ctx).unwrap(), "2");
+
+        // each one stays in ps until it's killed
+        let watching = |ctx: &TerminalContext| ctx.processes.list().iter().filter(|p| p.cmd.starts_with("watch")).count();
+        assert_eq!(watching(&ctx), 2);
+        run("killall watch", &mut ctx).unwrap();
+        assert_eq!(watching(&ctx), 0);
+
+        assert_eq!(run("watch -n", &mut ctx).unwrap_err(), "watch: option requires an argument -- 'n'\nUsage: watch [-n SECS] [-t] COMMAND...");
+        assert_eq!(run("watch", &mut ctx).unwrap_err(), "Usage: watch [-n SECS] [-t] COMMAND...");
+    }
+}
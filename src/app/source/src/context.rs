@@ -1,3 +1,5 @@
+use crate::process::ProcessTable;
+use crate::schedule::Scheduler;
 use crate::vfs::VirtualFileSystem;
 use crate::vfs_events::emit_vfs_event;
 use std::collections::HashMap;
@@ -6,6 +8,8 @@ use std::sync::Arc;
 pub struct ShellOptions {
     pub errexit: bool, // set -e
     pub xtrace: bool,  // set -x
+    pub nullglob: bool, // set -o nullglob - a glob with no matches vanishes
+    pub failglob: bool, // set -o failglob - a glob with no matches is an error
     // Add more options as needed
 }
 
@@ -14,6 +18,8 @@ impl Default for ShellOptions {
         Self {
             errexit: false,
             xtrace: false,
+            nullglob: false,
+            failglob: false,
         }
     }
 }
@@ -29,6 +35,50 @@ pub struct TerminalContext {
     pub functions: HashMap<String, String>, // shell functions: name -> body
     pub options: ShellOptions, // shell options
     pub history: Vec<String>, // command history
+    pub history_unsaved: Vec<String>, // lines added since the history file was last written
+    pub last_status: i32, // exit status of the last pipeline, for $?
+    pub exit_code: Option<i32>, // set by a command that wants a status other than 0/1
+    pub flow: Option<Flow>, // pending break-out, see Flow
+    pub depth: usize, // how many shell::exec::run calls deep we are (source in source...)
+    pub loop_depth: u32, // loops we're currently inside, for break/continue
+    pub condition_depth: u32, // >0 while running an if/while test, set -e ignores those
+    pub locals: Vec<HashMap<String, Option<String>>>, // one frame per function call: what `local` shadowed
+    pub source_depth: u32, // sourced scripts we're inside, `return` works there too
+    pub pending_stderr: String, // stderr from a $(...) that hasn't been shown yet
+    pub stdin_pos: usize, // bytes of the current stdin `read` has used up
+    pub capturing: u32, // >0 while stdout goes to a pipe, a file or a $(...) rather than the terminal
+    pub script: Option<String>, // path of the executable script being run, for $0
+    pub processes: ProcessTable, // pids for everything running, for ps and kill
+    pub traps: HashMap<String, String>, // signal name (or EXIT) -> what `trap` set to run
+    pub trapping: bool, // running a trap, which holds off any more until it's done
+    pub schedule: Scheduler, // at jobs, watches and cron's place in the crontabs
+}
+
+/// non-local control flow, raised somewhere deep in the executor and
+/// unwound by everything above it until something handles it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flow {
+    Exit(i32),      // stop running the current script/line with this status
+    Break(u32),     // break N - leave this many enclosing loops
+    Continue(u32),  // continue N - skip to the next round of the Nth loop out
+    Return(i32),    // return N - leave the current function (or sourced script)
+    Interrupt,      // ctrl-c with no INT trap - drop everything back to the prompt
+}
+
+// pid the shell itself shows up as, for $$
+pub const SHELL_PID: u32 = 100;
+
+const DEFAULT_USER: &str = "user";
+
+// what a fresh shell starts out with in its environment
+fn default_env() -> HashMap<String, String> {
+    let mut env = HashMap::new();
+    env.insert("HOME".to_string(), "/home".to_string());
+    env.insert("PATH".to_string(), "/bin:/usr/bin".to_string());
+    env.insert("USER".to_string(), DEFAULT_USER.to_string());
+    env.insert("PS1".to_string(), "[virt::core] ➤ ".to_string());
+    env.insert("PS2".to_string(), "> ".to_string());
+    env
 }
 
 impl TerminalContext {
@@ -40,12 +90,13 @@ impl TerminalContext {
         let _ = vfs.create_dir("/tmp");
         let _ = vfs.create_dir("/usr");
         let _ = vfs.create_dir("/var");
+        let _ = vfs.create_dir("/var/log");
         let _ = vfs.create_dir("/bin");
         let _ = vfs.create_dir("/etc");
         
         Self {
             vfs,
-            env: HashMap::new(),
+            env: default_env(),
             vars: HashMap::new(),
             cwd: "/".to_string(),
             aliases: HashMap::new(),
@@ -54,13 +105,30 @@ impl TerminalContext {
             functions: HashMap::new(),
             options: ShellOptions::default(),
             history: Vec::new(),
+            history_unsaved: Vec::new(),
+            last_status: 0,
+            exit_code: None,
+            flow: None,
+            depth: 0,
+            loop_depth: 0,
+            condition_depth: 0,
+            locals: Vec::new(),
+            source_depth: 0,
+            pending_stderr: String::new(),
+            stdin_pos: 0,
+            capturing: 0,
+            script: None,
+            processes: ProcessTable::new(DEFAULT_USER),
+            traps: HashMap::new(),
+            trapping: false,
+            schedule: Scheduler::default(),
         }
     }
     
     pub fn new_with_vfs(vfs: VirtualFileSystem) -> Self {
         Self {
             vfs,
-            env: HashMap::new(),
+            env: default_env(),
             vars: HashMap::new(),
             cwd: "/".to_string(),
             aliases: HashMap::new(),
@@ -69,9 +137,37 @@ impl TerminalContext {
             functions: HashMap::new(),
             options: ShellOptions::default(),
             history: Vec::new(),
+            history_unsaved: Vec::new(),
+            last_status: 0,
+            exit_code: None,
+            flow: None,
+            depth: 0,
+            loop_depth: 0,
+            condition_depth: 0,
+            locals: Vec::new(),
+            source_depth: 0,
+            pending_stderr: String::new(),
+            stdin_pos: 0,
+            capturing: 0,
+            script: None,
+            processes: ProcessTable::new(DEFAULT_USER),
+            traps: HashMap::new(),
+            trapping: false,
+            schedule: Scheduler::default(),
         }
     }
     
+    /// turn a possibly relative path into an absolute one based on cwd
+    pub fn resolve_path(&self, path: &str) -> String {
+        if path.starts_with('/') {
+            path.to_string()
+        } else if self.cwd == "/" {
+            format!("/{}", path)
+        } else {
+            format!("{}/{}", self.cwd, path)
+        }
+    }
+
     pub fn set_args(&mut self, args: Vec<String>) {
         self.args = args;
     }
@@ -90,6 +186,94 @@ impl TerminalContext {
     pub fn get_var(&self, name: &str) -> Option<&String> {
         self.vars.get(name)
     }
+    /// look a name up the way $name does - shell vars first, then env
+    pub fn lookup_var(&self, name: &str) -> Option<&String> {
+        self.vars.get(name).or_else(|| self.env.get(name))
+    }
+    /// `name=value` - updates whichever copy $name would see: a shell var
+    /// (which includes locals), else the env if it's exported, else a new shell var
+    pub fn assign_var(&mut self, name: &str, value: &str) {
+        if let Some(v) = self.vars.get_mut(name) {
+            *v = value.to_string();
+        } else if let Some(v) = self.env.get_mut(name) {
+            *v = value.to_string();
+        } else {
+            self.set_var(name, value);
+        }
+    }
+    /// `local name=value` - remember what the name held before (once per
+    /// call) so it comes back when the function returns
+    pub fn set_local(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let previous = self.vars.get(name).cloned();
+        let frame = self.locals.last_mut().ok_or("local: can only be used in a function")?;
+        frame.entry(name.to_string()).or_insert(previous);
+        self.set_var(name, value);
+        Ok(())
+    }
+    /// remember a line run from the prompt. it's held back for
+    /// flush_history to append to the history file, so the next session
+    /// has it too
+    pub fn add_history(&mut self, line: &str) {
+        if self.push_history(line) {
+            self.history_unsaved.push(line.to_string());
+        }
+    }
+    /// append the lines added since the last flush to the history file in
+    /// one write, keeping the file to $HISTSIZE like the list in memory.
+    /// run once per batch of commands rather than once per line
+    pub fn flush_history(&mut self) {
+        if self.history_unsaved.is_empty() {
+            return;
+        }
+        let path = self.history_file();
+        let saved = self.vfs.read_file(&path).map(|bytes| String::from_utf8_lossy(bytes).into_owned()).unwrap_or_default();
+        let unsaved = std::mem::take(&mut self.history_unsaved);
+        let lines: Vec<&str> = saved.lines().chain(unsaved.iter().map(String::as_str)).collect();
+        let keep = &lines[lines.len().saturating_sub(self.history_size())..];
+        let data = keep.iter().map(|line| format!("{}\n", line)).collect::<String>();
+        // no home to save in isn't worth an error on every flush
+        self.write_file_with_events(&path, data.as_bytes()).ok();
+    }
+    /// add a line to the history without saving it - `history -r` and
+    /// startup, where it came from the file in the first place. only the
+    /// last $HISTSIZE (default 1000) are kept. false for a blank line
+    pub fn push_history(&mut self, line: &str) -> bool {
+        if line.trim().is_empty() {
+            return false;
+        }
+        self.history.push(line.to_string());
+        let max = self.history_size();
+        if self.history.len() > max {
+            let extra = self.history.len() - max;
+            self.history.drain(..extra);
+        }
+        true
+    }
+    fn history_size(&self) -> usize {
+        self.lookup_var("HISTSIZE").and_then(|n| n.parse().ok()).unwrap_or(1000)
+    }
+    /// start the history off from the history file, in place of whatever's
+    /// in memory - run once at startup, after the rc files have had their
+    /// chance to set $HISTFILE
+    pub fn load_history(&mut self) {
+        let path = self.history_file();
+        let Ok(bytes) = self.vfs.read_file(&path) else {
+            return;
+        };
+        let lines: Vec<String> = String::from_utf8_lossy(bytes).lines().map(String::from).collect();
+        self.history.clear();
+        self.history_unsaved.clear();
+        for line in lines {
+            self.push_history(&line);
+        }
+    }
+    /// where `history -w` and `-r` go by default - $HISTFILE, else ~/.history
+    pub fn history_file(&self) -> String {
+        match self.lookup_var("HISTFILE") {
+            Some(file) => self.resolve_path(file),
+            None => format!("{}/.history", self.lookup_var("HOME").map(|h| h.trim_end_matches('/')).unwrap_or_default()),
+        }
+    }
     pub fn set_option(&mut self, errexit: Option<bool>, xtrace: Option<bool>) {
         if let Some(e) = errexit { self.options.errexit = e; }
         if let Some(x) = xtrace { self.options.xtrace = x; }
@@ -134,6 +318,7 @@ impl TerminalContext {
     }
     
     /// Write to a file with VFS event emission
+    #[cfg(target_arch = "wasm32")]
     pub fn write_file_with_events(&mut self, path: &str, content: &[u8]) -> Result<(), String> {
         web_sys::console::log_3(
             &"[CONTEXT VFS] 📝 write_file_with_events called for:".into(),
@@ -159,6 +344,12 @@ impl TerminalContext {
             }
         }
     }
+
+    // natively there's no console or frontend to tell, just the vfs
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_file_with_events(&mut self, path: &str, content: &[u8]) -> Result<(), String> {
+        self.vfs.write_file(path, content.to_vec()).or_else(|_| self.vfs.create_file(path, content.to_vec()))
+    }
     
     /// Create a symlink with VFS event emission
     pub fn create_symlink_with_events(&mut self, link_path: &str, target_path: &str) -> Result<(), String> {
@@ -5,10 +5,14 @@ pub mod command;
 pub mod context;
 pub mod commands;
 pub mod vfs_events;
+pub mod shell;
+pub mod process;
+pub mod schedule;
 
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures;
-use context::TerminalContext;
+use context::{TerminalContext, SHELL_PID};
+use process::SIGINT;
 use command::{CommandRegistry};
 use serde::{Serialize, Deserialize};
 use std::io::{Read, Write};
@@ -50,6 +54,13 @@ pub fn send_async_result(result: &str) {
 pub struct Terminal {
     ctx: TerminalContext,
     registry: CommandRegistry,
+    // lines of a command that isn't finished yet (an open `if`, quote...),
+    // waiting for the rest before any of it runs
+    pending: String,
+    // init_with_storage runs the startup files only once the saved files
+    // are in, which is whenever load_filesystem_data or the first command
+    // comes along
+    startup_pending: bool,
 }
 
 // response wrapper for js comms
@@ -81,6 +92,8 @@ impl Terminal {
         Terminal {
             ctx,
             registry,
+            pending: String::new(),
+            startup_pending: false,
         }
     }
 
@@ -88,9 +101,11 @@ impl Terminal {
     #[wasm_bindgen]
     pub async fn init_terminal(&mut self) -> JsValue {
         // no saved data or error loading, use fresh vfs
+        let output = self.run_startup_files();
         serde_wasm_bindgen::to_value(&serde_json::json!({
             "success": true,
-            "message": "terminal initialized with fresh filesystem"
+            "message": "terminal initialized with fresh filesystem",
+            "output": output
         })).unwrap()
     }
 
@@ -99,12 +114,30 @@ impl Terminal {
     pub async fn init_with_storage(&mut self) -> JsValue {
         // Initialize with persistent storage support
         // The actual persistence is handled by the frontend ZenFS system
+        // the saved startup files aren't loaded yet, so they run later
+        self.startup_pending = true;
         serde_wasm_bindgen::to_value(&serde_json::json!({
             "success": true,
             "message": "terminal initialized with persistent storage"
         })).unwrap()
     }
 
+    // /etc/profile then ~/.shellrc. any that are missing get a default
+    // written out first (with events, so it's saved) for the user to edit
+    fn run_startup_files(&mut self) -> String {
+        self.startup_pending = false;
+        let mut files = shell::startup::files(&self.ctx);
+        // cron isn't a startup file, but its /etc/crontab gets seeded the
+        // same way - once the saved files are in, so an edited one stays
+        files.push((schedule::SYSTEM_CRONTAB.to_string(), schedule::DEFAULT_CRONTAB));
+        for (path, default) in files {
+            if self.ctx.vfs.resolve_path(&path).is_none() {
+                let _ = self.ctx.write_file_with_events(&path, default.as_bytes());
+            }
+        }
+        shell::startup::run(&mut self.ctx, &self.registry)
+    }
+
     /// load filesystem data from frontend (ZenFS)
     #[wasm_bindgen]
     pub fn load_filesystem_data(&mut self, files_json: &str) -> JsValue {
@@ -191,11 +224,14 @@ impl Terminal {
                     }
                 }
 
+                let startup_output = if self.startup_pending { self.run_startup_files() } else { String::new() };
+
                 serde_wasm_bindgen::to_value(&serde_json::json!({
                     "success": true,
                     "loaded": loaded_count,
                     "errors": error_count,
-                    "message": format!("Loaded {} files from persistent storage", loaded_count)
+                    "message": format!("Loaded {} files from persistent storage", loaded_count),
+                    "startup_output": startup_output
                 })).unwrap()
             }
             Err(e) => {
@@ -338,9 +374,48 @@ impl Terminal {
     // main entry point - run a command and return result
     #[wasm_bindgen]
     pub fn execute_command(&mut self, input: &str) -> JsValue {
-        let input = input.trim();
+        // a line typed into the editor is file text, not shell input: no !
+        // expansion, no waiting for a closing quote and no history entry
+        if input.starts_with("edit_input ") {
+            let response = match command::run_command(input, &mut self.ctx, &self.registry) {
+                Ok(output) => CommandResponse { success: true, output, special_action: None },
+                Err(e) => CommandResponse { success: false, output: format!("Error: {}", e), special_action: None },
+            };
+            return serde_wasm_bindgen::to_value(&response).unwrap();
+        }
+        // nothing was saved, so load_filesystem_data never came
+        let startup_output = if self.startup_pending { self.run_startup_files() } else { String::new() };
+        // a continuation line keeps its indentation, it may be here-doc text
+        let input = if self.pending.is_empty() { input.trim() } else { input.trim_end_matches(['\r', '\n']) };
+        
+        // !! and friends first - a line that changed gets echoed back
+        // like bash does, one that can't be expanded doesn't run at all
+        let (line, echo) = match shell::history::expand(input, &self.ctx.history) {
+            Ok(Some(line)) => (line.clone(), Some(line)),
+            Ok(None) => (input.to_string(), None),
+            Err(e) => {
+                self.pending.clear();
+                return serde_wasm_bindgen::to_value(&CommandResponse {
+                    success: false,
+                    output: format!("Error: {}", e),
+                    special_action: None,
+                }).unwrap();
+            }
+        };
+        let line = if self.pending.is_empty() { line } else { format!("{}\n{}", std::mem::take(&mut self.pending), line) };
+
+        // not finished yet - hold on to it and ask for more with PS2
+        if shell::parser::incomplete(&line) {
+            self.pending = line;
+            return serde_wasm_bindgen::to_value(&CommandResponse {
+                success: true,
+                output: echo.unwrap_or_default(),
+                special_action: Some("continue_input".to_string()),
+            }).unwrap();
+        }
+        self.ctx.add_history(&line);
         
-        let response = match command::run_command(input, &mut self.ctx, &self.registry) {
+        let mut response = match command::run_command(&line, &mut self.ctx, &self.registry) {
             Ok(output) => {
                 // Check for special action markers
                 let cmd_response = match output.as_str() {
@@ -364,10 +439,86 @@ impl Terminal {
                 special_action: None,
             },
         };
+        if let Some(line) = echo {
+            response.output = if response.output.is_empty() { line } else { format!("{}\n{}", line, response.output) };
+        }
+        if !startup_output.is_empty() {
+            response.output = if response.output.is_empty() { startup_output } else { format!("{}\n{}", startup_output, response.output) };
+        }
 
         serde_wasm_bindgen::to_value(&response).unwrap()
     }
 
+    // history lines starting with prefix, newest first and without
+    // repeats - what Ctrl-R in the frontend steps through
+    #[wasm_bindgen]
+    pub fn search_history(&self, prefix: &str) -> JsValue {
+        let mut matches: Vec<&String> = Vec::new();
+        for line in self.ctx.history.iter().rev() {
+            if line.starts_with(prefix) && !matches.contains(&line) {
+                matches.push(line);
+            }
+        }
+        serde_wasm_bindgen::to_value(&matches).unwrap()
+    }
+
+    // the prompt for the next line - $PS1 with its escapes and
+    // expansions done, or $PS2 partway through an unfinished command
+    #[wasm_bindgen]
+    pub fn render_prompt(&mut self) -> String {
+        // the startup files set PS1, so they have to have run by now.
+        // anything they print goes above the prompt
+        let mut shown = String::new();
+        if self.startup_pending {
+            shown = self.run_startup_files();
+            if !shown.is_empty() {
+                shown.push('\n');
+            }
+        }
+        shown.push_str(&shell::prompt::prompt(&mut self.ctx, !self.pending.is_empty()));
+        shown
+    }
+
+    // ctrl-c - SIGINT to the shell, which passes it on to whatever's
+    // running in the foreground (a ping, a long cpu run) and runs its INT
+    // trap. a half-typed multi-line command is dropped too. gives back
+    // whatever the trap printed
+    #[wasm_bindgen]
+    pub fn interrupt(&mut self) -> String {
+        self.pending.clear();
+        self.ctx.processes.signal(SHELL_PID, SIGINT).ok();
+        let mut out = shell::exec::Output::default();
+        shell::exec::run_traps(&mut self.ctx, &self.registry, &mut out);
+        out.render()
+    }
+
+    // at jobs, watch refreshes and cron - the page calls this every
+    // second and whatever came due reports through the async callback.
+    // nothing runs before the saved crontabs are loaded
+    #[wasm_bindgen]
+    pub fn tick(&mut self) {
+        if self.startup_pending {
+            return;
+        }
+        // the lines run since the last tick go to the history file together
+        self.ctx.flush_history();
+        for output in schedule::run_due(&mut self.ctx, &self.registry) {
+            send_async_result(output.trim_end_matches('\n'));
+        }
+    }
+
+    // tab completion for the word ending at `cursor` (a char offset).
+    // returns {start, candidates}: the frontend swaps chars start..cursor
+    // for the one candidate, or lists them when there's more than one
+    #[wasm_bindgen]
+    pub fn complete(&self, line: &str, cursor: usize) -> JsValue {
+        let done = shell::complete::complete(line, cursor, &self.ctx, &self.registry);
+        serde_wasm_bindgen::to_value(&serde_json::json!({
+            "start": done.start,
+            "candidates": done.candidates,
+        })).unwrap()
+    }
+
     // get current working directory
     #[wasm_bindgen]
     pub fn get_current_directory(&self) -> String {
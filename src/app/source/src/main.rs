@@ -2,6 +2,10 @@ mod vfs;
 mod command;
 mod context;
 mod commands;
+mod vfs_events;
+mod shell;
+mod process;
+mod schedule;
 
 use context::TerminalContext;
 use command::{Command, CommandRegistry};
@@ -10,20 +14,70 @@ use std::io::{self, Write};
 fn main() {
     let mut ctx = TerminalContext::new();
     let registry = CommandRegistry::default_commands();
+    // source and $(...) run commands through the context's own copy
+    ctx.set_command_registry(std::sync::Arc::new(CommandRegistry::default_commands()));
     let stdin = io::stdin();
     let mut stdout = io::stdout();
+    // /etc/profile and ~/.shellrc, written out fresh since nothing's saved
+    for (path, default) in shell::startup::files(&ctx) {
+        let _ = ctx.vfs.create_file(&path, default.as_bytes().to_vec());
+    }
+    let _ = ctx.vfs.create_file(schedule::SYSTEM_CRONTAB, schedule::DEFAULT_CRONTAB.as_bytes().to_vec());
+    let startup = shell::startup::run(&mut ctx, &registry);
+    if !startup.is_empty() {
+        println!("{}", startup);
+    }
+    // lines of a command that isn't finished yet
+    let mut pending = String::new();
     loop {
-        print!("[virt::core] ➤ ");
+        // the browser gets Done notices through the async callback; here
+        // they come before the next prompt, like bash
+        for (_, job) in ctx.processes.jobs() {
+            if let Some(notice) = job.report() {
+                println!("{}", notice);
+            }
+        }
+        // no timer here either, so at, watch and cron catch up at each prompt
+        for output in schedule::run_due(&mut ctx, &registry) {
+            println!("{}", output.trim_end_matches('\n'));
+        }
+        print!("{}", shell::prompt::prompt(&mut ctx, !pending.is_empty()));
         stdout.flush().unwrap();
         let mut input = String::new();
-        if stdin.read_line(&mut input).is_err() {
+        if stdin.read_line(&mut input).unwrap_or(0) == 0 {
             break;
         }
-        let input = input.trim();
-        if input == "exit" { break; }
-        match command::run_command(input, &mut ctx, &registry) {
+        // no line editing here, so a line ending in a tab lists what
+        // the tab would have completed to
+        if let Some(line) = input.trim_end_matches(['\r', '\n']).strip_suffix('\t') {
+            let done = shell::complete::complete(line, line.chars().count(), &ctx, &registry);
+            println!("{}", done.candidates.join("  "));
+            continue;
+        }
+        let input = if pending.is_empty() { input.trim() } else { input.trim_end_matches(['\r', '\n']) };
+        if input == "exit" && pending.is_empty() { break; }
+        let input = match shell::history::expand(input, &ctx.history) {
+            Ok(Some(line)) => {
+                println!("{}", line);
+                line
+            }
+            Ok(None) => input.to_string(),
+            Err(e) => {
+                println!("Error: {}", e);
+                pending.clear();
+                continue;
+            }
+        };
+        let input = if pending.is_empty() { input } else { format!("{}\n{}", std::mem::take(&mut pending), input) };
+        if shell::parser::incomplete(&input) {
+            pending = input;
+            continue;
+        }
+        ctx.add_history(&input);
+        match command::run_command(&input, &mut ctx, &registry) {
             Ok(output) => if !output.is_empty() { println!("{}", output); },
             Err(e) => println!("Error: {}", e),
         }
+        ctx.flush_history();
     }
 }
@@ -0,0 +1,546 @@
+// the process table - every command, script and async job gets a pid
+// while it runs, so ps has something real to show and kill something
+// real to signal
+
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::Arc;
+
+use crate::context::SHELL_PID;
+
+const INIT_PID: u32 = 1;
+/// the cron daemon - killing it stops the crontabs running
+pub const CRON_PID: u32 = 2;
+
+// name and number, in the order `kill -l` lists them
+pub const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", 1), ("INT", 2), ("QUIT", 3), ("ILL", 4), ("ABRT", 6), ("FPE", 8), ("KILL", 9),
+    ("SEGV", 11), ("PIPE", 13), ("ALRM", 14), ("TERM", 15), ("USR1", 10), ("USR2", 12),
+    ("CHLD", 17), ("CONT", 18), ("STOP", 19), ("TSTP", 20), ("TTIN", 21), ("TTOU", 22),
+];
+
+pub const SIGINT: i32 = 2;
+pub const SIGKILL: i32 = 9;
+pub const SIGTERM: i32 = 15;
+pub const SIGCONT: i32 = 18;
+
+/// `TERM`, `SIGTERM` or `15` - the signal number, if it's one we know
+pub fn parse_signal(spec: &str) -> Option<i32> {
+    if let Ok(n) = spec.parse::<i32>() {
+        // 0 just checks the process is there
+        return (n == 0 || SIGNALS.iter().any(|&(_, num)| num == n)).then_some(n);
+    }
+    let name = spec.to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    SIGNALS.iter().find(|&&(sig, _)| sig == name).map(|&(_, num)| num)
+}
+
+pub fn signal_name(signal: i32) -> &'static str {
+    SIGNALS.iter().find(|&&(_, num)| num == signal).map_or("?", |&(name, _)| name)
+}
+
+fn is_stop(signal: i32) -> bool {
+    matches!(signal, 19..=22)
+}
+
+/// the bit of a process whatever is doing its work holds on to - an
+/// async job checks it between steps to see if it's been killed or
+/// stopped
+#[derive(Debug, Default)]
+pub struct Job {
+    killed: AtomicI32,
+    stopped: AtomicBool,
+    done: AtomicBool,
+}
+
+impl Job {
+    /// the signal that killed it, if one did
+    pub fn killed(&self) -> Option<i32> {
+        Some(self.killed.load(Ordering::SeqCst)).filter(|&sig| sig != 0)
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// for an async job between steps: sits out a STOP until CONT, then
+    /// says whether to carry on - false once it's been killed
+    #[cfg(target_arch = "wasm32")]
+    pub async fn proceed(&self) -> bool {
+        while self.stopped() && self.killed().is_none() {
+            gloo_timers::future::TimeoutFuture::new(100).await;
+        }
+        self.killed().is_none()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::SeqCst) || self.killed().is_some()
+    }
+
+    // what the default action for each signal comes down to
+    fn deliver(&self, signal: i32) {
+        match signal {
+            0 | 17 => {} // CHLD is ignored
+            SIGCONT => self.stopped.store(false, Ordering::SeqCst),
+            sig if is_stop(sig) => self.stopped.store(true, Ordering::SeqCst),
+            sig => self.killed.store(sig, Ordering::SeqCst),
+        }
+    }
+}
+
+/// what `detach` hands out. dropping it marks the job done, so however
+/// the async work ends it drops out of ps
+#[derive(Debug, Default)]
+pub struct JobHandle(Arc<Job>);
+
+impl std::ops::Deref for JobHandle {
+    type Target = Job;
+
+    fn deref(&self) -> &Job {
+        &self.0
+    }
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        self.0.done.store(true, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug)]
+pub struct Process {
+    pub pid: u32,
+    pub ppid: u32,
+    pub user: String,
+    pub tty: String,
+    pub cmd: String,
+    pub started: DateTime<Local>,
+    pub job: Arc<Job>,
+    // carrying on after the command that started it returned
+    pub background: bool,
+}
+
+impl Process {
+    fn new(pid: u32, ppid: u32, user: &str, tty: &str, cmd: &str) -> Self {
+        Process {
+            pid,
+            ppid,
+            user: user.to_string(),
+            tty: tty.to_string(),
+            cmd: cmd.to_string(),
+            started: Local::now(),
+            job: Arc::default(),
+            background: false,
+        }
+    }
+
+    /// the first word of the command line without its directory - what
+    /// killall matches against
+    pub fn name(&self) -> &str {
+        let first = self.cmd.split_whitespace().next().unwrap_or_default();
+        first.rsplit('/').next().unwrap_or(first)
+    }
+}
+
+/// a `cmd &` job - the processes it left running, for jobs, fg and
+/// friends. whatever's watching for it to finish holds on to it too
+#[derive(Debug)]
+pub struct BackgroundJob {
+    pub id: usize,
+    // what $! and `jobs -p` give
+    pub pid: u32,
+    pub cmd: String,
+    // what the command returned when it gave the shell back
+    status: i32,
+    procs: Vec<(u32, Arc<Job>)>,
+    reported: AtomicBool,
+}
+
+impl BackgroundJob {
+    pub fn is_done(&self) -> bool {
+        self.procs.iter().all(|(_, job)| job.is_done())
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.procs.iter().any(|(_, job)| job.stopped() && !job.is_done())
+    }
+
+    pub fn pids(&self) -> Vec<u32> {
+        self.procs.iter().map(|&(pid, _)| pid).collect()
+    }
+
+    /// like sh, 128 + the signal if something in it got killed
+    pub fn status(&self) -> i32 {
+        match self.procs.iter().find_map(|(_, job)| job.killed()) {
+            Some(signal) => 128 + signal,
+            None => self.status,
+        }
+    }
+
+    // Running, Stopped, Done, Exit 3, Terminated...
+    fn state(&self) -> String {
+        if !self.is_done() {
+            return if self.stopped() { "Stopped" } else { "Running" }.to_string();
+        }
+        match self.procs.iter().find_map(|(_, job)| job.killed()) {
+            Some(1) => "Hangup".to_string(),
+            Some(SIGINT) => "Interrupt".to_string(),
+            Some(SIGKILL) => "Killed".to_string(),
+            Some(SIGTERM) => "Terminated".to_string(),
+            Some(signal) => format!("Signal {}", signal),
+            None if self.status == 0 => "Done".to_string(),
+            None => format!("Exit {}", self.status),
+        }
+    }
+
+    /// the line `jobs` shows, `mark` being + for the current job and -
+    /// for the one before. `with_pid` is jobs -l
+    pub fn line(&self, mark: char, with_pid: bool) -> String {
+        let pid = if with_pid { format!("{:>5}", self.pid) } else { String::new() };
+        let running = if self.is_done() { "" } else { " &" };
+        format!("[{}]{} {} {:<24}{}{}", self.id, mark, pid, self.state(), self.cmd, running)
+    }
+
+    /// the Done line, the first time it's asked for once the job's over -
+    /// after that the job is gone from `jobs`
+    pub fn report(&self) -> Option<String> {
+        (self.is_done() && !self.reported.swap(true, Ordering::SeqCst)).then(|| self.line(' ', false))
+    }
+
+    fn reported(&self) -> bool {
+        self.reported.load(Ordering::SeqCst)
+    }
+}
+
+/// send the job's Done line through the async callback once it's over,
+/// however long that takes
+#[cfg(target_arch = "wasm32")]
+pub fn notify_when_done(job: Arc<BackgroundJob>) {
+    wasm_bindgen_futures::spawn_local(async move {
+        while !job.is_done() {
+            gloo_timers::future::TimeoutFuture::new(200).await;
+        }
+        if let Some(notice) = job.report() {
+            crate::send_async_result(&notice);
+        }
+    });
+}
+
+#[derive(Debug)]
+pub struct ProcessTable {
+    procs: BTreeMap<u32, Process>,
+    // the foreground chain - a script, the command it's running... - with
+    // the innermost last. everything in it but the last one is waiting
+    running: Vec<u32>,
+    next_pid: u32,
+    // oldest first, so the last one is the current job
+    jobs: Vec<Arc<BackgroundJob>>,
+    last_background: Option<u32>,
+    // signals sent to the shell that its traps haven't seen yet
+    pending: Vec<i32>,
+}
+
+impl ProcessTable {
+    pub fn new(user: &str) -> Self {
+        let mut procs = BTreeMap::new();
+        procs.insert(INIT_PID, Process::new(INIT_PID, 0, "root", "?", "init"));
+        procs.insert(CRON_PID, Process::new(CRON_PID, INIT_PID, "root", "?", "cron"));
+        procs.insert(SHELL_PID, Process::new(SHELL_PID, INIT_PID, user, "tty1", "sh"));
+        ProcessTable { procs, running: Vec::new(), next_pid: SHELL_PID + 1, jobs: Vec::new(), last_background: None, pending: Vec::new() }
+    }
+
+    /// the process whatever runs next is a child of
+    pub fn current(&self) -> u32 {
+        self.running.last().copied().unwrap_or(SHELL_PID)
+    }
+
+    /// a new foreground process, running until `finish`
+    pub fn start(&mut self, cmd: &str, user: &str) -> u32 {
+        // finished background jobs have no one to wait for them
+        self.procs.retain(|_, p| !p.background || !p.job.is_done());
+        let pid = self.next_pid;
+        self.next_pid += 1;
+        let process = Process::new(pid, self.current(), user, "tty1", cmd);
+        self.procs.insert(pid, process);
+        self.running.push(pid);
+        pid
+    }
+
+    /// the process is done with the foreground. it leaves the table unless
+    /// it went on in the background; the signal comes back if it was killed
+    pub fn finish(&mut self, pid: u32) -> Option<i32> {
+        self.running.retain(|&p| p != pid);
+        let process = self.procs.get(&pid)?;
+        let killed = process.job.killed();
+        if !process.background || killed.is_some() {
+            self.procs.remove(&pid);
+        }
+        killed
+    }
+
+    /// keep `pid` in the table after its command returns - for work that
+    /// carries on asynchronously, holding on to the handle until it's done
+    pub fn detach(&mut self, pid: u32) -> Option<JobHandle> {
+        let process = self.procs.get_mut(&pid)?;
+        process.background = true;
+        Some(JobHandle(process.job.clone()))
+    }
+
+    pub fn alive(&self, pid: u32) -> bool {
+        self.procs.get(&pid).is_some_and(|p| !p.job.is_done())
+    }
+
+    /// everything still alive, by pid
+    pub fn list(&self) -> Vec<&Process> {
+        self.procs.values().filter(|p| !p.job.is_done()).collect()
+    }
+
+    /// ps's STAT - T stopped, R for whatever's actually running right
+    /// now, S for everything waiting on something
+    pub fn state(&self, process: &Process) -> char {
+        if process.job.stopped() {
+            'T'
+        } else if process.pid == self.current() && !self.running.is_empty() {
+            'R'
+        } else {
+            'S'
+        }
+    }
+
+    /// the pid the next process will get - everything started from here
+    /// on is newer than this
+    pub fn next_pid(&self) -> u32 {
+        self.next_pid
+    }
+
+    /// make a job out of whatever was started since `first` and is still
+    /// going. nothing still going just means a job that's already done
+    pub fn background(&mut self, first: u32, cmd: &str, status: i32) -> Arc<BackgroundJob> {
+        let procs: Vec<_> = self.procs.range(first..).filter(|(_, p)| !p.job.is_done()).map(|(&pid, p)| (pid, p.job.clone())).collect();
+        let pid = match procs.first() {
+            Some(&(pid, _)) => pid,
+            None if first < self.next_pid => first,
+            None => {
+                // nothing was started at all, but $! still wants a pid
+                self.next_pid += 1;
+                first
+            }
+        };
+        self.jobs.retain(|job| !job.reported());
+        let id = self.jobs.last().map_or(1, |job| job.id + 1);
+        let job = Arc::new(BackgroundJob { id, pid, cmd: cmd.to_string(), status, procs, reported: AtomicBool::new(false) });
+        self.jobs.push(job.clone());
+        self.last_background = Some(pid);
+        job
+    }
+
+    /// $! - the pid of the last job started
+    pub fn last_background(&self) -> Option<u32> {
+        self.last_background
+    }
+
+    /// the jobs that haven't been reported done yet, with the mark `jobs`
+    /// shows next to each
+    pub fn jobs(&mut self) -> Vec<(char, Arc<BackgroundJob>)> {
+        self.jobs.retain(|job| !job.reported());
+        let count = self.jobs.len();
+        self.jobs.iter().enumerate().map(|(i, job)| {
+            let mark = match count - i {
+                1 => '+',
+                2 => '-',
+                _ => ' ',
+            };
+            (mark, job.clone())
+        }).collect()
+    }
+
+    /// a job by its spec: %1, %+ or %% (or nothing) for the current one, %-
+    /// for the one before, %name for one whose command starts with name
+    /// and %?text for one with text anywhere in it
+    pub fn find_job(&mut self, spec: Option<&str>) -> Result<Arc<BackgroundJob>, String> {
+        let jobs = self.jobs();
+        let spec = spec.map(|s| s.strip_prefix('%').unwrap_or(s));
+        let found = match spec {
+            None | Some("" | "+" | "%") => jobs.last(),
+            Some("-") => jobs.iter().rev().nth(1),
+            Some(n) if n.parse::<usize>().is_ok() => jobs.iter().find(|(_, job)| Ok(job.id) == n.parse()),
+            Some(text) => match text.strip_prefix('?') {
+                Some(text) => jobs.iter().rev().find(|(_, job)| job.cmd.contains(text)),
+                None => jobs.iter().rev().find(|(_, job)| job.cmd.starts_with(text)),
+            },
+        };
+        found.map(|(_, job)| job.clone()).ok_or_else(|| format!("{}: no such job", spec.map_or("current".to_string(), |s| format!("%{}", s))))
+    }
+
+    /// forget a job - its processes carry on, but with no notice at the end
+    pub fn remove_job(&mut self, id: usize) {
+        self.jobs.retain(|job| job.id != id);
+    }
+
+    /// signal every process of a job that's still going
+    pub fn signal_job(&mut self, job: &BackgroundJob, signal: i32) -> Result<(), String> {
+        if job.is_done() {
+            return Err(format!("%{}: job has terminated", job.id));
+        }
+        for pid in job.pids() {
+            // one of them being over already doesn't matter
+            self.signal(pid, signal).ok();
+        }
+        Ok(())
+    }
+
+    /// the signals the shell has had since the last call, oldest first
+    pub fn take_pending(&mut self) -> Vec<i32> {
+        std::mem::take(&mut self.pending)
+    }
+
+    // ctrl-c: whatever's going on asynchronously that isn't a job - a
+    // ping, a long cpu run, something brought back with fg - gets SIGINT
+    fn interrupt_foreground(&mut self) {
+        let in_jobs: Vec<u32> = self.jobs.iter().flat_map(|job| job.pids()).collect();
+        self.procs.retain(|pid, p| {
+            if !p.background || p.job.is_done() || in_jobs.contains(pid) {
+                return true;
+            }
+            p.job.deliver(SIGINT);
+            false
+        });
+    }
+
+    /// send a signal. the shell itself doesn't die of them, like an
+    /// interactive bash, but it keeps them for `trap` - and a SIGINT to
+    /// the shell is ctrl-c, which its foreground work gets too. init
+    /// can't be touched at all
+    pub fn signal(&mut self, pid: u32, signal: i32) -> Result<(), String> {
+        let Some(process) = self.list().into_iter().find(|p| p.pid == pid) else {
+            return Err(format!("({}) - No such process", pid));
+        };
+        match pid {
+            INIT_PID => Err(format!("({}) - Operation not permitted", pid)),
+            SHELL_PID => {
+                if signal == SIGINT {
+                    self.interrupt_foreground();
+                }
+                if signal != 0 {
+                    self.pending.push(signal);
+                }
+                Ok(())
+            }
+            _ => {
+                process.job.deliver(signal);
+                // a killed background job is gone now; a foreground one
+                // finds out when it gets back to finish()
+                if process.background && process.job.killed().is_some() {
+                    self.procs.remove(&pid);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(table: &ProcessTable, pid: u32) -> Option<&Process> {
+        table.list().into_iter().find(|p| p.pid == pid)
+    }
+
+    #[test]
+    fn test_signals() {
+        assert_eq!(parse_signal("9"), Some(9));
+        assert_eq!(parse_signal("sigterm"), Some(SIGTERM));
+        assert_eq!(parse_signal("INT"), Some(2));
+        assert_eq!(parse_signal("0"), Some(0));
+        assert_eq!(parse_signal("NOPE"), None);
+        assert_eq!(parse_signal("99"), None);
+        assert_eq!(signal_name(9), "KILL");
+    }
+
+    #[test]
+    fn test_foreground_and_background() {
+        let mut table = ProcessTable::new("user");
+        let script = table.start("/bin/build all", "user");
+        let child = table.start("ping -c 3 host", "user");
+        assert_eq!(get(&table, child).unwrap().ppid, script);
+        assert_eq!(get(&table, script).unwrap().name(), "build");
+        assert_eq!((table.state(get(&table, script).unwrap()), table.state(get(&table, child).unwrap())), ('S', 'R'));
+
+        // ping goes on in the background once its command returns
+        let job = table.detach(child).unwrap();
+        assert_eq!(table.finish(child), None);
+        assert_eq!(table.finish(script), None);
+        assert_eq!(table.list().iter().map(|p| p.pid).collect::<Vec<_>>(), vec![1, CRON_PID, SHELL_PID, child]);
+
+        table.signal(child, 19).unwrap();
+        assert!(job.stopped());
+        assert_eq!(table.state(get(&table, child).unwrap()), 'T');
+        table.signal(child, SIGCONT).unwrap();
+        table.signal(child, SIGTERM).unwrap();
+        assert_eq!(job.killed(), Some(SIGTERM));
+        assert!(get(&table, child).is_none());
+        assert!(table.signal(child, SIGTERM).is_err());
+        assert!(table.signal(1, 9).is_err());
+        assert!(table.signal(SHELL_PID, 9).is_ok());
+
+        // a finished job drops out of the list
+        let pid = table.start("curl x", "user");
+        drop(table.detach(pid));
+        table.finish(pid);
+        assert_eq!(table.list().len(), 3);
+        assert!(table.signal(pid, SIGTERM).is_err());
+    }
+
+    #[test]
+    fn test_jobs() {
+        let mut table = ProcessTable::new("user");
+        // `ping host &` - ping carries on after the command returns
+        let first = table.next_pid();
+        let pid = table.start("ping host", "user");
+        let handle = table.detach(pid).unwrap();
+        table.finish(pid);
+        let job = table.background(first, "ping host", 0);
+        assert_eq!((job.id, job.pid, table.last_background()), (1, pid, Some(pid)));
+        assert_eq!(job.line('+', false), "[1]+  Running                 ping host &");
+
+        // one that's over as soon as it's started still gets a pid
+        let done = table.background(table.next_pid(), "x=1", 0);
+        assert_eq!((done.id, done.pid), (2, pid + 1));
+        assert_eq!(table.find_job(Some("%-")).unwrap().id, 1);
+        assert_eq!(table.find_job(Some("%?host")).unwrap().id, 1);
+        assert_eq!(table.find_job(Some("%3")).unwrap_err(), "%3: no such job");
+        assert_eq!(done.report().unwrap(), "[2]   Done                    x=1");
+        assert!(done.report().is_none());
+        assert_eq!(table.find_job(None).unwrap().id, 1);
+
+        table.signal_job(&job, 19).unwrap();
+        assert!(handle.stopped());
+        assert_eq!(job.line('+', true), format!("[1]+   {} Stopped                 ping host &", pid));
+        table.signal_job(&job, SIGKILL).unwrap();
+        assert_eq!((job.status(), job.report().unwrap()), (128 + SIGKILL, "[1]   Killed                  ping host".to_string()));
+        assert!(table.jobs().is_empty());
+    }
+
+    #[test]
+    fn test_interrupt() {
+        let mut table = ProcessTable::new("user");
+        // a job and something in the foreground, both still going
+        let first = table.next_pid();
+        let pid = table.start("ping a", "user");
+        let job = table.detach(pid).unwrap();
+        table.finish(pid);
+        table.background(first, "ping a", 0);
+        let pid = table.start("cpu run loop.asm", "user");
+        let foreground = table.detach(pid).unwrap();
+        table.finish(pid);
+
+        // ctrl-c only stops the foreground one, and the shell hears of it
+        table.signal(SHELL_PID, SIGINT).unwrap();
+        assert_eq!((job.killed(), foreground.killed()), (None, Some(SIGINT)));
+        table.signal(SHELL_PID, SIGTERM).unwrap();
+        assert_eq!(table.take_pending(), vec![SIGINT, SIGTERM]);
+        assert!(table.take_pending().is_empty());
+    }
+}
@@ -0,0 +1,359 @@
+// timed work - `at` jobs, `watch` refreshes and the crontabs. nothing in
+// here runs by itself: the page calls run_due every second (natively it's
+// before each prompt) and whatever has come due runs then
+
+use chrono::{DateTime, Datelike, Duration, Local, Timelike};
+
+use crate::command::CommandRegistry;
+use crate::context::TerminalContext;
+use crate::process::{JobHandle, CRON_PID};
+use crate::shell::exec;
+
+pub const SYSTEM_CRONTAB: &str = "/etc/crontab";
+
+pub const DEFAULT_CRONTAB: &str = "\
+# /etc/crontab - read by cron every minute, edits count from the next one.
+# ~/.crontab works the same without the user column
+#
+# m  h  dom mon dow  user  command
+0    0  *   *   *    root  for f in /var/log/*.log; do [ -f \"$f\" ] && mv \"$f\" \"$f.1\"; done
+";
+
+// after a long gap between looks (a sleeping laptop, a tab in the
+// background) cron only catches up on this many minutes
+const CATCH_UP_MINUTES: i64 = 60;
+
+const MONTHS: &[&str] = &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const DAYS: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// a command `at` is holding on to until its time comes
+#[derive(Debug)]
+pub struct AtJob {
+    pub id: usize,
+    pub when: DateTime<Local>,
+    pub cmd: String,
+    cwd: String,
+}
+
+// a `watch` that's still going - it stays in ps until it's killed
+#[derive(Debug)]
+struct Watch {
+    cmd: String,
+    every: Duration,
+    title: bool,
+    next: DateTime<Local>,
+    cwd: String,
+    job: JobHandle,
+}
+
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    at: Vec<AtJob>,
+    last_at: usize,
+    watches: Vec<Watch>,
+    // the last minute the crontabs were checked for, None until cron's first look
+    cron_minute: Option<DateTime<Local>>,
+}
+
+// something that's due, and the header a watch puts over its output
+struct Task {
+    cmd: String,
+    cwd: String,
+    header: Option<String>,
+}
+
+impl Scheduler {
+    /// queue `cmd` to run in `cwd` at `when`, giving back its job number
+    pub fn at(&mut self, when: DateTime<Local>, cmd: &str, cwd: &str) -> usize {
+        self.last_at += 1;
+        self.at.push(AtJob { id: self.last_at, when, cmd: cmd.to_string(), cwd: cwd.to_string() });
+        self.at.sort_by_key(|job| job.when);
+        self.last_at
+    }
+
+    /// the at jobs still waiting, soonest first
+    pub fn at_jobs(&self) -> &[AtJob] {
+        &self.at
+    }
+
+    pub fn remove_at(&mut self, id: usize) -> bool {
+        let before = self.at.len();
+        self.at.retain(|job| job.id != id);
+        self.at.len() < before
+    }
+
+    /// rerun `cmd` every so often until `job` is killed. the first run is
+    /// watch's own output, so this starts from the one after
+    pub fn watch(&mut self, cmd: &str, every: Duration, title: bool, cwd: &str, job: JobHandle) {
+        let next = Local::now() + every;
+        self.watches.push(Watch { cmd: cmd.to_string(), every, title, next, cwd: cwd.to_string(), job });
+    }
+}
+
+/// what goes over each refresh of a watch, like `Every 2.0s: date`
+pub fn watch_header(every: Duration, cmd: &str) -> String {
+    let secs = every.num_milliseconds() as f64 / 1000.0;
+    format!("Every {:.1}s: {}    {}\n\n", secs, cmd, Local::now().format("%a %b %e %H:%M:%S %Y"))
+}
+
+/// run everything that's come due - at jobs, watch refreshes and crontab
+/// lines for each minute since the last look - and hand back what each
+/// one printed
+pub fn run_due(ctx: &mut TerminalContext, registry: &CommandRegistry) -> Vec<String> {
+    let tasks = due(ctx, Local::now());
+    tasks
+        .into_iter()
+        .map(|task| task.header.unwrap_or_default() + &exec::run_detached(&task.cmd, &task.cwd, ctx, registry))
+        .filter(|output| !output.is_empty())
+        .collect()
+}
+
+fn due(ctx: &mut TerminalContext, now: DateTime<Local>) -> Vec<Task> {
+    let schedule = &mut ctx.schedule;
+    let (ready, waiting): (Vec<AtJob>, Vec<AtJob>) = std::mem::take(&mut schedule.at).into_iter().partition(|job| job.when <= now);
+    schedule.at = waiting;
+    let mut tasks: Vec<Task> = ready.into_iter().map(|job| Task { cmd: job.cmd, cwd: job.cwd, header: None }).collect();
+
+    // a killed watch is dropped, and with it its handle, so it leaves ps
+    schedule.watches.retain(|watch| watch.job.killed().is_none());
+    for watch in &mut schedule.watches {
+        if watch.job.stopped() || watch.next > now {
+            continue;
+        }
+        watch.next = now + watch.every;
+        let header = watch.title.then(|| watch_header(watch.every, &watch.cmd));
+        tasks.push(Task { cmd: watch.cmd.clone(), cwd: watch.cwd.clone(), header });
+    }
+
+    tasks.extend(cron_due(ctx, now));
+    tasks
+}
+
+// the crontab lines for every minute since cron last looked, up to `now`
+fn cron_due(ctx: &mut TerminalContext, now: DateTime<Local>) -> Vec<Task> {
+    let minute = now.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(now);
+    let last = ctx.schedule.cron_minute.replace(minute);
+    if !ctx.processes.alive(CRON_PID) || last == Some(minute) {
+        return Vec::new();
+    }
+    let home = ctx.lookup_var("HOME").cloned().unwrap_or_else(|| "/".to_string());
+    let lines = crontabs(ctx, &home);
+    let task = |cmd: &String| Task { cmd: cmd.clone(), cwd: home.clone(), header: None };
+
+    // cron's just started: @reboot lines run now, the rest from the next minute on
+    let Some(last) = last else {
+        return lines.iter().filter(|(when, _)| *when == When::Reboot).map(|(_, cmd)| task(cmd)).collect();
+    };
+    let mut tasks = Vec::new();
+    let mut t = (last + Duration::minutes(1)).max(minute - Duration::minutes(CATCH_UP_MINUTES - 1));
+    while t <= minute {
+        tasks.extend(lines.iter().filter(|(when, _)| when.matches(&t)).map(|(_, cmd)| task(cmd)));
+        t += Duration::minutes(1);
+    }
+    tasks
+}
+
+// both crontabs as they are right now
+fn crontabs(ctx: &TerminalContext, home: &str) -> Vec<(When, String)> {
+    let user = format!("{}/.crontab", home.trim_end_matches('/'));
+    let mut lines = Vec::new();
+    for (path, system) in [(SYSTEM_CRONTAB, true), (user.as_str(), false)] {
+        if let Ok(bytes) = ctx.vfs.read_file(path) {
+            lines.extend(String::from_utf8_lossy(bytes).lines().filter_map(|line| parse_line(line, system)));
+        }
+    }
+    lines
+}
+
+/// the numbers of the lines in a user crontab that won't run - each one
+/// should be a comment, a NAME=value setting or five times and a command
+pub fn bad_lines(text: &str) -> Vec<usize> {
+    let setting = |line: &str| line.split_once('=').is_some_and(|(name, _)| !name.is_empty() && !name.contains(char::is_whitespace));
+    let ok = |line: &str| line.trim().is_empty() || line.trim().starts_with('#') || setting(line.trim()) || parse_line(line, false).is_some();
+    text.lines().enumerate().filter(|(_, line)| !ok(line)).map(|(i, _)| i + 1).collect()
+}
+
+// when a crontab line runs
+#[derive(Debug, PartialEq)]
+enum When {
+    Reboot,
+    // a bit for each value the field allows
+    Times { minute: u64, hour: u64, dom: u64, month: u64, dow: u64, dom_any: bool, dow_any: bool },
+}
+
+impl When {
+    fn matches(&self, t: &DateTime<Local>) -> bool {
+        let When::Times { minute, hour, dom, month, dow, dom_any, dow_any } = *self else {
+            return false;
+        };
+        let bit = |mask: u64, n: u32| mask & (1 << n) != 0;
+        let dom_ok = bit(dom, t.day());
+        let dow_ok = bit(dow, t.weekday().num_days_from_sunday());
+        // with both day fields restricted either one will do, like cron
+        let day = match dom_any || dow_any {
+            true => dom_ok && dow_ok,
+            false => dom_ok || dow_ok,
+        };
+        bit(minute, t.minute()) && bit(hour, t.hour()) && bit(month, t.month()) && day
+    }
+}
+
+/// one crontab line: when it runs and what. /etc/crontab lines have the
+/// user between the two, which is skipped - everything runs as the shell's
+/// user. comments, blank lines and anything that doesn't parse give None
+fn parse_line(line: &str, system: bool) -> Option<(When, String)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (when, rest) = match line.strip_prefix('@') {
+        Some(special) => {
+            let (name, rest) = special.split_once(char::is_whitespace)?;
+            let fields = match name {
+                "reboot" => return command(When::Reboot, rest, system),
+                "yearly" | "annually" => "0 0 1 1 *",
+                "monthly" => "0 0 1 * *",
+                "weekly" => "0 0 * * 0",
+                "daily" | "midnight" => "0 0 * * *",
+                "hourly" => "0 * * * *",
+                _ => return None,
+            };
+            (parse_times(fields)?, rest)
+        }
+        None => {
+            let mut rest = line;
+            let mut fields = Vec::new();
+            for _ in 0..5 {
+                let (field, after) = rest.split_once(char::is_whitespace)?;
+                fields.push(field);
+                rest = after.trim_start();
+            }
+            (parse_times(&fields.join(" "))?, rest)
+        }
+    };
+    command(when, rest, system)
+}
+
+// what's left of a line after the times
+fn command(when: When, rest: &str, system: bool) -> Option<(When, String)> {
+    let rest = rest.trim();
+    let cmd = match system {
+        true => rest.split_once(char::is_whitespace)?.1.trim(),
+        false => rest,
+    };
+    (!cmd.is_empty()).then(|| (when, cmd.to_string()))
+}
+
+fn parse_times(fields: &str) -> Option<When> {
+    let fields: Vec<&str> = fields.split_whitespace().collect();
+    let [minute, hour, dom, month, dow] = fields.as_slice() else {
+        return None;
+    };
+    let mut dow_mask = field(dow, 0, 7, DAYS)?;
+    // 7 is Sunday too
+    if dow_mask & (1 << 7) != 0 {
+        dow_mask |= 1;
+    }
+    Some(When::Times {
+        minute: field(minute, 0, 59, &[])?,
+        hour: field(hour, 0, 23, &[])?,
+        dom: field(dom, 1, 31, &[])?,
+        month: field(month, 1, 12, MONTHS)?,
+        dow: dow_mask,
+        dom_any: *dom == "*",
+        dow_any: *dow == "*",
+    })
+}
+
+// one field: *, 5, 1-5, */15, 1-30/2, mon-fri, or a list of any of those
+fn field(text: &str, min: u32, max: u32, names: &[&str]) -> Option<u64> {
+    let value = |s: &str| s.parse::<u32>().ok().or_else(|| names.iter().position(|n| n.eq_ignore_ascii_case(s)).map(|i| i as u32 + min));
+    let mut mask = 0u64;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>().ok().filter(|&s| s > 0)?),
+            None => (part, 1),
+        };
+        let (lo, hi) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((lo, hi)) => (value(lo)?, value(hi)?),
+            // 5/10 is from 5 to the end in steps of 10
+            None if step > 1 => (value(range)?, max),
+            None => (value(range)?, value(range)?),
+        };
+        if lo < min || hi > max || lo > hi {
+            return None;
+        }
+        for n in (lo..=hi).step_by(step) {
+            mask |= 1 << n;
+        }
+    }
+    Some(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::Arc;
+
+    fn time(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        // October 2026 starts on a Thursday
+        Local.with_ymd_and_hms(2026, 10, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_crontab_lines() {
+        let (when, cmd) = parse_line("*/15 9-17 * * mon-fri  echo tick", false).unwrap();
+        assert_eq!(cmd, "echo tick");
+        assert!(when.matches(&time(16, 9, 45)));
+        assert!(!when.matches(&time(16, 9, 50)));
+        assert!(!when.matches(&time(16, 18, 0)));
+        assert!(!when.matches(&time(17, 9, 0)));
+
+        // the user column is skipped in /etc/crontab
+        let (when, cmd) = parse_line("0 0 * * *  root  rm -f /tmp/*", true).unwrap();
+        assert_eq!(cmd, "rm -f /tmp/*");
+        assert_eq!(when, parse_line("@daily x", false).unwrap().0);
+
+        // both day fields set: either one matches
+        let (when, _) = parse_line("30 12 1,15 * 7 x", false).unwrap();
+        assert!(when.matches(&time(15, 12, 30)));
+        assert!(when.matches(&time(18, 12, 30)));
+        assert!(!when.matches(&time(16, 12, 30)));
+
+        assert_eq!(parse_line("@reboot  echo up", false).unwrap(), (When::Reboot, "echo up".to_string()));
+        for bad in ["# a comment", "PATH=/bin", "60 * * * * x", "* * * * *", "*/0 * * * * x", "@sometimes x"] {
+            assert!(parse_line(bad, false).is_none(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_due() {
+        let mut ctx = TerminalContext::new();
+        let registry = CommandRegistry::default_commands();
+        ctx.set_command_registry(Arc::new(CommandRegistry::default_commands()));
+        ctx.vfs.create_file(SYSTEM_CRONTAB, b"@reboot root echo booted\n* * * * * root echo minute\n".to_vec()).unwrap();
+        ctx.vfs.create_file("/home/.crontab", b"*/2 * * * * echo even >> /tmp/log\n".to_vec()).unwrap();
+
+        // cron's first look only runs @reboot
+        let cmds = |tasks: Vec<Task>| tasks.into_iter().map(|t| t.cmd).collect::<Vec<_>>();
+        assert_eq!(cmds(due(&mut ctx, time(16, 10, 0))), ["echo booted"]);
+        assert!(due(&mut ctx, time(16, 10, 0)).is_empty());
+        assert_eq!(cmds(due(&mut ctx, time(16, 10, 2))), ["echo minute", "echo minute", "echo even >> /tmp/log"]);
+
+        // at jobs come off the queue when they're due
+        ctx.schedule.at(time(16, 10, 3), "echo later", "/tmp");
+        let id = ctx.schedule.at(time(16, 11, 0), "echo never", "/");
+        assert!(ctx.schedule.remove_at(id));
+        ctx.processes.signal(CRON_PID, 15).unwrap();
+        assert_eq!(cmds(due(&mut ctx, time(16, 10, 5))), ["echo later"]);
+        assert!(ctx.schedule.at_jobs().is_empty());
+
+        // and run where they were queued, without touching the shell's cwd or $?
+        ctx.schedule.at(time(16, 10, 3), "pwd; false", "/tmp");
+        ctx.last_status = 3;
+        assert_eq!(run_due(&mut ctx, &registry), ["/tmp\n"]);
+        assert_eq!((ctx.cwd.as_str(), ctx.last_status), ("/", 3));
+    }
+}
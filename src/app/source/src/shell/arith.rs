@@ -0,0 +1,362 @@
+// integer arithmetic for $(( )), (( )) and let - C operators and
+// precedence, 64 bit wrapping math, variables read from (and assigned
+// back to) the context. by the time we get here $x and $(...) have
+// already been expanded, bare names are looked up as we go
+
+use crate::context::TerminalContext;
+
+// a variable whose value is itself an expression gets evaluated too,
+// but `a=b b=a` shouldn't send us round forever
+const MAX_RECURSION: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(i64),
+    Name(String),
+    Op(&'static str),
+}
+
+// longest first so `<<=` wins over `<<` and `<`
+const OPS: &[&str] = &[
+    "<<=", ">>=", "**", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+=", "-=", "*=", "/=", "%=", "&=",
+    "^=", "|=", "+", "-", "*", "/", "%", "<", ">", "=", "!", "~", "&", "^", "|", "?", ":", ",", "(", ")",
+];
+
+#[derive(Debug)]
+enum Expr {
+    Num(i64),
+    Var(String),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    // x = v, or x op= v when the op is there
+    Assign(String, Option<&'static str>, Box<Expr>),
+    // ++x / x-- - (name, +1 or -1, whether the new value is the result)
+    Step(String, i64, bool),
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+/// evaluate `expr`, e.g. "x += 2 * (y > 3 ? y : 3)". empty is 0
+pub fn eval(expr: &str, ctx: &mut TerminalContext) -> Result<i64, String> {
+    eval_at(expr, ctx, 0).map_err(|e| format!("{}: {}", expr.trim(), e))
+}
+
+fn eval_at(expr: &str, ctx: &mut TerminalContext, depth: usize) -> Result<i64, String> {
+    let tokens = tokenize(expr)?;
+    if tokens.is_empty() {
+        return Ok(0);
+    }
+    let mut parser = Parser { tokens, pos: 0 };
+    let tree = parser.expr(0)?;
+    if let Some(token) = parser.tokens.get(parser.pos) {
+        return Err(format!("syntax error in expression (error token is \"{}\")", token_text(token)));
+    }
+    Evaluator { ctx, depth }.eval(&tree)
+}
+
+fn token_text(token: &Token) -> String {
+    match token {
+        Token::Num(n) => n.to_string(),
+        Token::Name(name) => name.clone(),
+        Token::Op(op) => op.to_string(),
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '#' || chars[i] == '_') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(Token::Num(parse_number(&text)?));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..].iter().take(3).collect();
+            let op = OPS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| format!("syntax error: invalid arithmetic operator (error token is \"{}\")", c))?;
+            tokens.push(Token::Op(op));
+            i += op.chars().count();
+        }
+    }
+    Ok(tokens)
+}
+
+// 42, 0x2a, 052 (octal) and base#digits like 2#101010
+fn parse_number(text: &str) -> Result<i64, String> {
+    let bad = || format!("{}: value too great for base (error token is \"{}\")", text, text);
+    if let Some((base, digits)) = text.split_once('#') {
+        let base: u32 = base.parse().map_err(|_| bad())?;
+        if !(2..=36).contains(&base) {
+            return Err(format!("{}: invalid arithmetic base", base));
+        }
+        return i64::from_str_radix(digits, base).map_err(|_| bad());
+    }
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        return i64::from_str_radix(hex, 16).map_err(|_| bad());
+    }
+    if text.len() > 1 && text.starts_with('0') {
+        return i64::from_str_radix(&text[1..], 8).map_err(|_| bad());
+    }
+    text.parse().map_err(|_| bad())
+}
+
+// (left, right) binding power of an infix operator, higher binds tighter.
+// right < left makes it right associative
+fn infix_power(op: &str) -> Option<(u8, u8)> {
+    Some(match op {
+        "," => (1, 2),
+        "=" | "+=" | "-=" | "*=" | "/=" | "%=" | "<<=" | ">>=" | "&=" | "^=" | "|=" => (3, 3),
+        "?" => (4, 4),
+        "||" => (5, 6),
+        "&&" => (7, 8),
+        "|" => (9, 10),
+        "^" => (11, 12),
+        "&" => (13, 14),
+        "==" | "!=" => (15, 16),
+        "<" | "<=" | ">" | ">=" => (17, 18),
+        "<<" | ">>" => (19, 20),
+        "+" | "-" => (21, 22),
+        "*" | "/" | "%" => (23, 24),
+        "**" => (26, 25),
+        _ => return None,
+    })
+}
+
+// unary ops bind tighter than everything infix, so -2**2 is 4 like bash
+const PREFIX_POWER: u8 = 27;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Op(found)) if found == op => Ok(()),
+            Some(token) => Err(format!("syntax error in expression (error token is \"{}\")", token_text(&token))),
+            None => Err(format!("syntax error: `{}' expected", op)),
+        }
+    }
+
+    fn expr(&mut self, min_power: u8) -> Result<Expr, String> {
+        let mut lhs = match self.next() {
+            Some(Token::Num(n)) => Expr::Num(n),
+            Some(Token::Name(name)) => match self.peek_op() {
+                Some(op @ ("++" | "--")) => {
+                    self.pos += 1;
+                    Expr::Step(name, if op == "++" { 1 } else { -1 }, false)
+                }
+                _ => Expr::Var(name),
+            },
+            Some(Token::Op("(")) => {
+                let inner = self.expr(0)?;
+                self.expect(")")?;
+                inner
+            }
+            Some(Token::Op(op @ ("++" | "--"))) => match self.next() {
+                Some(Token::Name(name)) => Expr::Step(name, if op == "++" { 1 } else { -1 }, true),
+                _ => return Err(format!("syntax error: `{}' needs a variable", op)),
+            },
+            Some(Token::Op(op @ ("-" | "+" | "!" | "~"))) => Expr::Unary(op, Box::new(self.expr(PREFIX_POWER)?)),
+            Some(token) => return Err(format!("syntax error: operand expected (error token is \"{}\")", token_text(&token))),
+            None => return Err("syntax error: operand expected".to_string()),
+        };
+
+        while let Some(op) = self.peek_op() {
+            let Some((left, right)) = infix_power(op) else { break };
+            if left < min_power {
+                break;
+            }
+            self.pos += 1;
+            lhs = match op {
+                "?" => {
+                    let then = self.expr(0)?;
+                    self.expect(":")?;
+                    let otherwise = self.expr(right)?;
+                    Expr::Cond(Box::new(lhs), Box::new(then), Box::new(otherwise))
+                }
+                _ if op.ends_with('=') && !matches!(op, "==" | "!=" | "<=" | ">=") => {
+                    let Expr::Var(name) = lhs else {
+                        return Err(format!("attempted assignment to non-variable (error token is \"{}\")", op));
+                    };
+                    let rhs = self.expr(right)?;
+                    let base = op.strip_suffix('=').and_then(|b| OPS.iter().copied().find(|o| *o == b));
+                    Expr::Assign(name, base, Box::new(rhs))
+                }
+                _ => Expr::Binary(op, Box::new(lhs), Box::new(self.expr(right)?)),
+            };
+        }
+        Ok(lhs)
+    }
+}
+
+struct Evaluator<'a> {
+    ctx: &'a mut TerminalContext,
+    depth: usize,
+}
+
+impl Evaluator<'_> {
+    fn eval(&mut self, expr: &Expr) -> Result<i64, String> {
+        Ok(match expr {
+            Expr::Num(n) => *n,
+            Expr::Var(name) => self.var(name)?,
+            Expr::Unary(op, inner) => {
+                let v = self.eval(inner)?;
+                match *op {
+                    "-" => v.wrapping_neg(),
+                    "!" => (v == 0) as i64,
+                    "~" => !v,
+                    _ => v,
+                }
+            }
+            // the logic ops only look at the right side when they need to
+            Expr::Binary("&&", a, b) => (self.eval(a)? != 0 && self.eval(b)? != 0) as i64,
+            Expr::Binary("||", a, b) => (self.eval(a)? != 0 || self.eval(b)? != 0) as i64,
+            Expr::Binary(op, a, b) => {
+                let a = self.eval(a)?;
+                let b = self.eval(b)?;
+                apply(op, a, b)?
+            }
+            Expr::Cond(cond, then, otherwise) => {
+                if self.eval(cond)? != 0 { self.eval(then)? } else { self.eval(otherwise)? }
+            }
+            Expr::Assign(name, op, rhs) => {
+                let mut value = self.eval(rhs)?;
+                if let Some(op) = op {
+                    value = apply(op, self.var(name)?, value)?;
+                }
+                self.ctx.assign_var(name, &value.to_string());
+                value
+            }
+            Expr::Step(name, delta, prefix) => {
+                let old = self.var(name)?;
+                let new = old.wrapping_add(*delta);
+                self.ctx.assign_var(name, &new.to_string());
+                if *prefix { new } else { old }
+            }
+        })
+    }
+
+    // unset or empty is 0, a number is itself, anything else is
+    // evaluated as an expression in its own right
+    fn var(&mut self, name: &str) -> Result<i64, String> {
+        let value = self.ctx.lookup_var(name).cloned().unwrap_or_default();
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(0);
+        }
+        if let Ok(n) = parse_number(value) {
+            return Ok(n);
+        }
+        if self.depth >= MAX_RECURSION {
+            return Err("expression recursion level exceeded".to_string());
+        }
+        eval_at(value, self.ctx, self.depth + 1)
+    }
+}
+
+fn apply(op: &str, a: i64, b: i64) -> Result<i64, String> {
+    Ok(match op {
+        "," => b,
+        "+" => a.wrapping_add(b),
+        "-" => a.wrapping_sub(b),
+        "*" => a.wrapping_mul(b),
+        "/" | "%" if b == 0 => return Err("division by 0".to_string()),
+        "/" => a.wrapping_div(b),
+        "%" => a.wrapping_rem(b),
+        "**" if b < 0 => return Err("exponent less than 0".to_string()),
+        "**" => a.wrapping_pow(b.min(u32::MAX as i64) as u32),
+        "<<" => a.wrapping_shl(b as u32),
+        ">>" => a.wrapping_shr(b as u32),
+        "<" => (a < b) as i64,
+        "<=" => (a <= b) as i64,
+        ">" => (a > b) as i64,
+        ">=" => (a >= b) as i64,
+        "==" => (a == b) as i64,
+        "!=" => (a != b) as i64,
+        "&" => a & b,
+        "^" => a ^ b,
+        "|" => a | b,
+        _ => return Err(format!("syntax error: invalid arithmetic operator (error token is \"{}\")", op)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calc(expr: &str) -> i64 {
+        eval(expr, &mut TerminalContext::new()).unwrap()
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(calc("1 + 2 * 3"), 7);
+        assert_eq!(calc("(1 + 2) * 3"), 9);
+        assert_eq!(calc("2 ** 3 ** 2"), 512);
+        assert_eq!(calc("-2 ** 2"), 4);
+        assert_eq!(calc("7 / 2 + 7 % 2"), 4);
+        assert_eq!(calc("1 << 4 | 1"), 17);
+        assert_eq!(calc("!0 + ~0"), 0);
+        assert_eq!(calc("3 > 2 && 2 >= 2 || 0"), 1);
+        assert_eq!(calc("0 ? 1 : 2 ? 3 : 4"), 3);
+        assert_eq!(calc("0x10 + 010 + 2#11"), 27);
+        assert_eq!(calc(""), 0);
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_var("x", "5");
+        ctx.set_var("e", "x * 2");
+        assert_eq!(eval("x + e + unset", &mut ctx).unwrap(), 15);
+        assert_eq!(eval("y = x++ + 1, x", &mut ctx).unwrap(), 6);
+        assert_eq!(ctx.get_var("y").unwrap(), "6");
+        assert_eq!(eval("x *= 2", &mut ctx).unwrap(), 12);
+        assert_eq!(eval("--x", &mut ctx).unwrap(), 11);
+        // the right side of && isn't touched when it doesn't need to be
+        assert_eq!(eval("0 && (z = 1)", &mut ctx).unwrap(), 0);
+        assert!(ctx.get_var("z").is_none());
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut ctx = TerminalContext::new();
+        assert_eq!(eval("1 / 0", &mut ctx).unwrap_err(), "1 / 0: division by 0");
+        assert!(eval("1 +", &mut ctx).is_err());
+        assert!(eval("(1", &mut ctx).is_err());
+        assert!(eval("3 = 4", &mut ctx).is_err());
+        assert!(eval("1 2", &mut ctx).is_err());
+        ctx.set_var("a", "b");
+        ctx.set_var("b", "a");
+        assert!(eval("a", &mut ctx).unwrap_err().contains("recursion"));
+    }
+}
@@ -0,0 +1,105 @@
+use super::lexer::{RedirOp, Word};
+
+/// `fd op target`, e.g. `2>&1` is fd 2, DupOut, target "1"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redirect {
+    pub fd: u32,
+    pub op: RedirOp,
+    pub target: Word,
+}
+
+/// `NAME=value` in front of a command (or on its own)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub name: String,
+    pub value: Word,
+}
+
+/// a plain command: name followed by its arguments, all still unexpanded
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimpleCommand {
+    pub assigns: Vec<Assignment>,
+    pub words: Vec<Word>,
+    pub redirects: Vec<Redirect>,
+}
+
+/// one `pattern | pattern) body ;;` inside a case
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseArm {
+    pub patterns: Vec<Word>,
+    pub body: List,
+}
+
+/// the multi-line constructs - if, loops, case and friends
+#[derive(Debug, Clone, PartialEq)]
+pub enum Compound {
+    // if/elif pairs of (condition, body), then the else part if any
+    If { branches: Vec<(List, List)>, otherwise: Option<List> },
+    // while (or until, which just flips the test)
+    Loop { until: bool, cond: List, body: List },
+    // no `in` list means loop over "$@"
+    For { var: String, words: Option<Vec<Word>>, body: List },
+    Case { word: Word, arms: Vec<CaseArm> },
+    // { list; } - just groups commands, e.g. for a function body
+    Group(List),
+    // (( expr )) - succeeds when the expression is non-zero
+    Arith(Word),
+    // for (( init; cond; step )) - an empty cond counts as true
+    ArithFor { init: Word, cond: Word, step: Word, body: List },
+    // [[ expr ]] - test without the word splitting and globbing
+    Cond(CondExpr),
+}
+
+/// what goes between [[ and ]]. operands stay unexpanded words since
+/// the right of == is a pattern and the right of =~ a regex
+#[derive(Debug, Clone, PartialEq)]
+pub enum CondExpr {
+    // a lone word, true when it's not empty
+    Word(Word),
+    Unary(String, Word),
+    Binary(String, Word, Word),
+    Not(Box<CondExpr>),
+    And(Box<CondExpr>, Box<CondExpr>),
+    Or(Box<CondExpr>, Box<CondExpr>),
+}
+
+/// anything that can be a stage in a pipeline
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Simple(SimpleCommand),
+    // redirects after the closing keyword apply to the whole thing,
+    // e.g. `while ...; done > out`
+    Compound(Compound, Vec<Redirect>),
+    // name() { ... } - body is kept as source text, that's how
+    // ctx.functions stores it
+    FunctionDef { name: String, body: String },
+}
+
+/// cmd1 | cmd2 | ... - each stage's output is the next stage's stdin
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    pub commands: Vec<Command>,
+}
+
+/// how two pipelines in an and-or list are joined
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connector {
+    And, // && - run the next one only if this succeeded
+    Or,  // || - run the next one only if this failed
+}
+
+/// `a && b || c` - evaluated left to right, no precedence between && and ||
+#[derive(Debug, Clone, PartialEq)]
+pub struct AndOr {
+    pub first: Pipeline,
+    pub rest: Vec<(Connector, Pipeline)>,
+    // `... &` - run as a background job, with the command as written for
+    // `jobs` to show
+    pub background: Option<String>,
+}
+
+/// `a; b; c` - run one after the other
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct List {
+    pub items: Vec<AndOr>,
+}
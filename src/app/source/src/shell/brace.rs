@@ -0,0 +1,198 @@
+// brace expansion - `proj/{src,tests}` and `file{1..3}.txt` turn one word
+// into several. runs before anything else, on the word as lexed, so only
+// unquoted braces count and `"{a,b}"` stays as it is
+
+use super::lexer::{Word, WordPart};
+
+// bigger sequences than this are left alone rather than eating all the memory
+const MAX_SEQUENCE: i64 = 100_000;
+
+// a word flattened out so braces can be found - unquoted text char by
+// char, everything else (quotes, expansions) kept whole
+#[derive(Debug, Clone)]
+enum Atom {
+    Char(char),
+    Part(WordPart),
+}
+
+/// all the words `word` brace expands to, in order. a word with nothing
+/// to expand comes back on its own
+pub fn expand(word: &Word) -> Vec<Word> {
+    let mut atoms = Vec::new();
+    for part in &word.parts {
+        match part {
+            WordPart::Literal(s) => atoms.extend(s.chars().map(Atom::Char)),
+            other => atoms.push(Atom::Part(other.clone())),
+        }
+    }
+    expand_atoms(atoms).into_iter().map(to_word).collect()
+}
+
+fn expand_atoms(atoms: Vec<Atom>) -> Vec<Vec<Atom>> {
+    // the first `{` that has a usable `}` - the rest get their turn
+    // when the results are expanded again
+    for (open, atom) in atoms.iter().enumerate() {
+        if !matches!(atom, Atom::Char('{')) {
+            continue;
+        }
+        let Some(close) = matching_brace(&atoms, open) else { continue };
+        let Some(alternatives) = alternatives(&atoms[open + 1..close]) else { continue };
+        let mut out = Vec::new();
+        for alt in alternatives {
+            let mut next = atoms[..open].to_vec();
+            next.extend(alt);
+            next.extend_from_slice(&atoms[close + 1..]);
+            out.extend(expand_atoms(next));
+        }
+        return out;
+    }
+    vec![atoms]
+}
+
+fn matching_brace(atoms: &[Atom], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, atom) in atoms.iter().enumerate().skip(open) {
+        match atom {
+            Atom::Char('{') => depth += 1,
+            Atom::Char('}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// what's between the braces: `a,b,c` or a `1..5` style sequence.
+// anything else (`{}`, `{abc}`) isn't a brace expansion at all
+fn alternatives(inner: &[Atom]) -> Option<Vec<Vec<Atom>>> {
+    let mut alts = vec![Vec::new()];
+    let mut depth = 0;
+    for atom in inner {
+        match atom {
+            Atom::Char('{') => depth += 1,
+            Atom::Char('}') => depth -= 1,
+            Atom::Char(',') if depth == 0 => {
+                alts.push(Vec::new());
+                continue;
+            }
+            _ => {}
+        }
+        alts.last_mut()?.push(atom.clone());
+    }
+    if alts.len() > 1 {
+        return Some(alts);
+    }
+    let text: String = inner
+        .iter()
+        .map(|atom| match atom {
+            Atom::Char(c) => Some(*c),
+            Atom::Part(_) => None,
+        })
+        .collect::<Option<_>>()?;
+    let items = sequence(&text)?;
+    Some(items.into_iter().map(|item| item.chars().map(Atom::Char).collect()).collect())
+}
+
+// {1..10}, {10..1..3}, {01..10} (zero padded), {a..e}
+fn sequence(text: &str) -> Option<Vec<String>> {
+    let pieces: Vec<&str> = text.split("..").collect();
+    let (start, end, step) = match pieces.as_slice() {
+        [start, end] => (*start, *end, None),
+        [start, end, step] => (*start, *end, Some(step.parse::<i64>().ok()?)),
+        _ => return None,
+    };
+    // a step or range too big for i64 leaves the word alone, like an over-long one
+    let step = match step {
+        Some(step) => step.checked_abs()?,
+        None => 1,
+    };
+    let step = if step == 0 { 1 } else { step };
+
+    if let (Ok(a), Ok(b)) = (start.parse::<i64>(), end.parse::<i64>()) {
+        if b.checked_sub(a)?.checked_abs()? / step > MAX_SEQUENCE {
+            return None;
+        }
+        // a leading zero on either end pads everything to the same width
+        let padded = [start, end].iter().any(|s| {
+            let digits = s.trim_start_matches('-');
+            digits.len() > 1 && digits.starts_with('0')
+        });
+        let width = if padded { start.len().max(end.len()) } else { 0 };
+        return Some(
+            stepped(a, b, step)
+                .map(|n| if n < 0 { format!("-{:0w$}", n.unsigned_abs(), w = width.saturating_sub(1)) } else { format!("{:0w$}", n, w = width) })
+                .collect(),
+        );
+    }
+
+    let mut a = start.chars();
+    let mut b = end.chars();
+    match (a.next(), a.next(), b.next(), b.next()) {
+        (Some(a), None, Some(b), None) if a.is_ascii_alphabetic() && b.is_ascii_alphabetic() => {
+            Some(stepped(a as i64, b as i64, step).filter_map(|c| char::from_u32(c as u32)).map(String::from).collect())
+        }
+        _ => None,
+    }
+}
+
+// a to b inclusive, counting down if b is smaller
+fn stepped(a: i64, b: i64, step: i64) -> impl Iterator<Item = i64> {
+    let count = (b - a).abs() / step;
+    let dir = if b < a { -1 } else { 1 };
+    (0..=count).map(move |i| a + i * step * dir)
+}
+
+fn to_word(atoms: Vec<Atom>) -> Word {
+    let mut word = Word::default();
+    for atom in atoms {
+        match atom {
+            Atom::Char(c) => match word.parts.last_mut() {
+                Some(WordPart::Literal(s)) => s.push(c),
+                _ => word.parts.push(WordPart::Literal(c.to_string())),
+            },
+            Atom::Part(part) => word.parts.push(part),
+        }
+    }
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::lexer::lex_word;
+
+    fn brace(text: &str) -> Vec<String> {
+        expand(&lex_word(text).unwrap()).iter().map(Word::text).collect()
+    }
+
+    #[test]
+    fn test_lists() {
+        assert_eq!(brace("proj/{src,tests,docs}"), vec!["proj/src", "proj/tests", "proj/docs"]);
+        assert_eq!(brace("a.txt{,.bak}"), vec!["a.txt", "a.txt.bak"]);
+        assert_eq!(brace("{a,b}{1,2}"), vec!["a1", "a2", "b1", "b2"]);
+        assert_eq!(brace("x{a,{b,c}}y"), vec!["xay", "xby", "xcy"]);
+        // not expansions
+        assert_eq!(brace("{}"), vec!["{}"]);
+        assert_eq!(brace("{abc}"), vec!["{abc}"]);
+        assert_eq!(brace("'{a,b}'"), vec!["{a,b}"]);
+        assert_eq!(brace("{a,b"), vec!["{a,b"]);
+    }
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(brace("file{1..3}.txt"), vec!["file1.txt", "file2.txt", "file3.txt"]);
+        assert_eq!(brace("{3..1}"), vec!["3", "2", "1"]);
+        assert_eq!(brace("{0..10..5}"), vec!["0", "5", "10"]);
+        assert_eq!(brace("{08..10}"), vec!["08", "09", "10"]);
+        assert_eq!(brace("{a..c}"), vec!["a", "b", "c"]);
+        assert_eq!(brace("{1..c}"), vec!["{1..c}"]);
+        // ranges and steps that overflow i64 stay unexpanded instead of panicking
+        assert_eq!(brace("{-9223372036854775808..9223372036854775807}"), vec!["{-9223372036854775808..9223372036854775807}"]);
+        assert_eq!(brace("{1..3..-9223372036854775808}"), vec!["{1..3..-9223372036854775808}"]);
+        assert_eq!(brace("{-9223372036854775808..-9223372036854775807}"), vec!["-9223372036854775808", "-9223372036854775807"]);
+    }
+}
@@ -0,0 +1,223 @@
+// tab completion - work out which word the cursor is on and what could go
+// there: a command name, a path, a $variable, or whatever the command
+// itself suggests for its arguments
+
+use super::{glob, pattern};
+use crate::command::CommandRegistry;
+use crate::context::TerminalContext;
+
+// words after which the next one is a command again
+const COMMAND_STARTERS: &[&str] = &["if", "then", "elif", "else", "while", "until", "do", "{", "!", "exec"];
+
+// what has to be backslashed in a completed path to stay one plain word
+const SPECIAL: &str = " \t\n'\"\\$`&|;<>()*?[]{}!#";
+
+/// candidates for the word running from char offset `start` up to the
+/// cursor. each one is the whole word, ready to drop in
+#[derive(Debug, PartialEq)]
+pub struct Completion {
+    pub start: usize,
+    pub candidates: Vec<String>,
+}
+
+pub fn complete(line: &str, cursor: usize, ctx: &TerminalContext, registry: &CommandRegistry) -> Completion {
+    let before: Vec<char> = line.chars().take(cursor).collect();
+    // a backslashed space (from an earlier completion) doesn't end the word
+    let mut start = 0;
+    let mut i = 0;
+    while i < before.len() {
+        match before[i] {
+            '\\' => i += 1,
+            c if c.is_whitespace() || ";|&<>()`".contains(c) => start = i + 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    let word: String = before[start..].iter().collect();
+    let prefix: String = before[..start].iter().collect();
+
+    let candidates = if let Some(dollar) = word.rfind('$') {
+        variables(&word, dollar, ctx)
+    } else {
+        match command_words(&prefix) {
+            None if word.contains('/') => paths(&word, ctx),
+            None => commands(&word, ctx, registry),
+            Some(words) => arguments(&words, &word, ctx, registry),
+        }
+    };
+    Completion { start, candidates }
+}
+
+// the command and arguments typed so far in the current simple command,
+// None when the word being completed is the command itself
+fn command_words(prefix: &str) -> Option<Vec<String>> {
+    let segment = prefix.rsplit(|c| ";|&(`".contains(c)).next().unwrap_or_default();
+    let words: Vec<String> = segment
+        .split_whitespace()
+        .skip_while(|w| COMMAND_STARTERS.contains(w) || w.split_once('=').is_some_and(|(name, _)| super::parser::is_name(name)))
+        .map(String::from)
+        .collect();
+    (!words.is_empty()).then_some(words)
+}
+
+fn commands(word: &str, ctx: &TerminalContext, registry: &CommandRegistry) -> Vec<String> {
+    let mut names: Vec<String> = registry.get_command_names();
+    names.extend(ctx.aliases.keys().cloned());
+    names.extend(ctx.functions.keys().cloned());
+    filtered(names, word)
+}
+
+// an option list for -words, the command's own suggestions if it has
+// any, paths otherwise
+fn arguments(words: &[String], word: &str, ctx: &TerminalContext, registry: &CommandRegistry) -> Vec<String> {
+    let Some(cmd) = registry.get(&words[0]) else { return paths(word, ctx) };
+    if word.starts_with('-') && !cmd.options().is_empty() {
+        return filtered(cmd.options().iter().map(|o| o.to_string()).collect(), word);
+    }
+    match cmd.complete(&words[1..], ctx) {
+        Some(candidates) => filtered(candidates, word),
+        None => paths(word, ctx),
+    }
+}
+
+// $NAME or ${NAME - everything before the $ is kept as typed
+fn variables(word: &str, dollar: usize, ctx: &TerminalContext) -> Vec<String> {
+    let (lead, rest) = word.split_at(dollar + 1);
+    let (brace, partial) = match rest.strip_prefix('{') {
+        Some(partial) => (true, partial),
+        None => (false, rest),
+    };
+    let names = ctx.vars.keys().chain(ctx.env.keys()).cloned().collect();
+    filtered(names, partial)
+        .into_iter()
+        // the _underscore ones are the terminal's own bookkeeping
+        .filter(|name| !name.starts_with('_') || partial.starts_with('_'))
+        .map(|name| if brace { format!("{}{{{}}}", lead, name) } else { format!("{}{}", lead, name) })
+        .collect()
+}
+
+// files and directories starting with the word, directories with a /
+// on the end so the next tab carries on into them. names with spaces,
+// quotes and the like come back backslashed so they stay one argument
+fn paths(word: &str, ctx: &TerminalContext) -> Vec<String> {
+    let word = &unescape(word);
+    let home = ctx.lookup_var("HOME").cloned().unwrap_or_default();
+    let (shown, real) = match word.strip_prefix("~/") {
+        Some(rest) => ("~/", format!("{}/{}", home.trim_end_matches('/'), rest)),
+        None => ("", word.to_string()),
+    };
+    let pat = format!("{}*", pattern::escape(&real));
+    let dirs = glob::expand(&format!("{}/", pat), ctx);
+    let restore = |path: String| match shown {
+        "" => path,
+        _ => format!("{}{}", shown, path.strip_prefix(home.trim_end_matches('/')).unwrap_or(&path).trim_start_matches('/')),
+    };
+    glob::expand(&pat, ctx)
+        .into_iter()
+        .map(|path| if dirs.contains(&format!("{}/", path)) { format!("{}/", path) } else { path })
+        .map(restore)
+        // the ~/ stays as it is, it's meant to expand
+        .map(|path| match path.strip_prefix("~/") {
+            Some(rest) => format!("~/{}", escape(rest)),
+            None => escape(&path),
+        })
+        .collect()
+}
+
+fn escape(path: &str) -> String {
+    let mut escaped = String::new();
+    for c in path.chars() {
+        if SPECIAL.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// the word as the shell will see it, so `my\ fi` looks for `my fi*`
+fn unescape(word: &str) -> String {
+    let mut chars = word.chars();
+    let mut plain = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => plain.extend(chars.next()),
+            c => plain.push(c),
+        }
+    }
+    plain
+}
+
+fn filtered(mut names: Vec<String>, word: &str) -> Vec<String> {
+    names.retain(|name| name.starts_with(word));
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates(line: &str, ctx: &TerminalContext) -> Vec<String> {
+        let registry = CommandRegistry::default_commands();
+        complete(line, line.chars().count(), ctx, &registry).candidates
+    }
+
+    #[test]
+    fn test_commands_and_paths() {
+        let mut ctx = TerminalContext::new();
+        ctx.vfs.create_dir("/tmp/src").unwrap();
+        ctx.vfs.create_file("/tmp/src/main.asm", Vec::new()).unwrap();
+        ctx.vfs.create_file("/tmp/notes.txt", Vec::new()).unwrap();
+        ctx.vfs.create_file("/tmp/.hidden", Vec::new()).unwrap();
+        ctx.cwd = "/tmp".to_string();
+        ctx.aliases.insert("ll".to_string(), "ls -l".to_string());
+        assert_eq!(candidates("mkd", &ctx), vec!["mkdir"]);
+        assert_eq!(candidates("echo hi | l", &ctx), vec!["let", "ll", "ln", "local", "ls"]);
+        assert_eq!(candidates("cat ", &ctx), vec!["notes.txt", "src/"]);
+        assert_eq!(candidates("cat src/m", &ctx), vec!["src/main.asm"]);
+        assert_eq!(candidates("ls .h", &ctx), vec![".hidden"]);
+        assert_eq!(candidates("cat /tmp/n", &ctx), vec!["/tmp/notes.txt"]);
+        assert_eq!(candidates("if true; then ec", &ctx), vec!["echo"]);
+        // the word runs back to the last space, the rest of the line stays put
+        let registry = CommandRegistry::default_commands();
+        let done = complete("cat no | wc", 6, &ctx, &registry);
+        assert_eq!(done, Completion { start: 4, candidates: vec!["notes.txt".to_string()] });
+    }
+
+    #[test]
+    fn test_paths_are_escaped() {
+        let mut ctx = TerminalContext::new();
+        ctx.vfs.create_dir("/tmp/my dir").unwrap();
+        ctx.vfs.create_file("/tmp/my dir/it's.txt", Vec::new()).unwrap();
+        ctx.vfs.create_file("/tmp/a&b", Vec::new()).unwrap();
+        ctx.vfs.create_file("/home/odd file", Vec::new()).unwrap();
+        ctx.cwd = "/tmp".to_string();
+        assert_eq!(candidates("cd my", &ctx), vec!["my\\ dir/"]);
+        assert_eq!(candidates("cat a", &ctx), vec!["a\\&b"]);
+        assert_eq!(candidates("ls ~/od", &ctx), vec!["~/odd\\ file"]);
+        // the escaped space from the last tab is part of the same word
+        let registry = CommandRegistry::default_commands();
+        let done = complete("cat my\\ dir/i", 13, &ctx, &registry);
+        assert_eq!(done, Completion { start: 4, candidates: vec!["my\\ dir/it\\'s.txt".to_string()] });
+
+        // and the shell reads it back as the one file
+        let line = format!("echo hi > {}; cat {}", done.candidates[0], done.candidates[0]);
+        let output = crate::shell::exec::run(&line, &mut ctx, &registry).unwrap();
+        assert_eq!(output, "hi\n");
+    }
+
+    #[test]
+    fn test_variables_options_and_hooks() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_var("project", "vfs");
+        assert_eq!(candidates("echo $pro", &ctx), vec!["$project"]);
+        assert_eq!(candidates("echo \"${pro", &ctx), vec!["\"${project}"]);
+        assert_eq!(candidates("rm -r", &ctx), vec!["-r"]);
+        assert_eq!(candidates("rm --r", &ctx), vec!["--recursive"]);
+        assert_eq!(candidates("cpu ", &ctx), vec!["docs", "help", "new", "run"]);
+        assert_eq!(candidates("storage s", &ctx), vec!["save", "stats"]);
+        assert_eq!(candidates("set -o null", &ctx), vec!["nullglob"]);
+    }
+}
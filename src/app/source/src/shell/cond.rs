@@ -0,0 +1,258 @@
+use crate::context::TerminalContext;
+use crate::vfs::{Permissions, VfsNode};
+use chrono::{DateTime, Local};
+
+// the checks shared by `test`, `[` and `[[ ]]`. files are looked up in the
+// vfs, and since there's only one user the owner bits decide -r/-w/-x
+
+const UNARY: &[&str] = &["-e", "-f", "-d", "-L", "-h", "-r", "-w", "-x", "-s", "-n", "-z"];
+const BINARY: &[&str] = &["=", "==", "!=", "<", ">", "-eq", "-ne", "-lt", "-le", "-gt", "-ge", "-nt", "-ot", "-ef"];
+
+pub fn is_unary(op: &str) -> bool {
+    UNARY.contains(&op)
+}
+
+pub fn is_binary(op: &str) -> bool {
+    BINARY.contains(&op)
+}
+
+pub fn is_integer_op(op: &str) -> bool {
+    matches!(op, "-eq" | "-ne" | "-lt" | "-le" | "-gt" | "-ge")
+}
+
+// what the file (or whatever a symlink points at) is, None if it doesn't exist
+fn lookup<'a>(path: &str, ctx: &'a TerminalContext) -> Option<&'a VfsNode> {
+    if path.is_empty() {
+        return None;
+    }
+    ctx.vfs.resolve_path_with_symlinks(&ctx.resolve_path(path), false)
+}
+
+fn meta(node: &VfsNode) -> (&Permissions, DateTime<Local>) {
+    match node {
+        VfsNode::File { permissions, mtime, .. }
+        | VfsNode::Directory { permissions, mtime, .. }
+        | VfsNode::Symlink { permissions, mtime, .. } => (permissions, *mtime),
+    }
+}
+
+/// `-f file`, `-z str` and friends
+pub fn unary(op: &str, arg: &str, ctx: &TerminalContext) -> bool {
+    match op {
+        "-n" => !arg.is_empty(),
+        "-z" => arg.is_empty(),
+        // the link itself, so no following
+        "-L" | "-h" => matches!(ctx.vfs.resolve_path(&ctx.resolve_path(arg)), Some(VfsNode::Symlink { .. })),
+        _ => {
+            let Some(node) = lookup(arg, ctx) else { return false };
+            let (perms, _) = meta(node);
+            match op {
+                "-e" => true,
+                "-f" => matches!(node, VfsNode::File { .. }),
+                "-d" => matches!(node, VfsNode::Directory { .. }),
+                "-r" => perms.user & 0b100 != 0,
+                "-w" => perms.user & 0b010 != 0,
+                "-x" => perms.user & 0b001 != 0,
+                "-s" => match node {
+                    VfsNode::File { content, .. } => !content.is_empty(),
+                    _ => true,
+                },
+                _ => false,
+            }
+        }
+    }
+}
+
+pub fn parse_int(s: &str) -> Result<i64, String> {
+    s.trim().parse().map_err(|_| format!("{}: integer expression expected", s))
+}
+
+pub fn compare_ints(op: &str, a: i64, b: i64) -> bool {
+    match op {
+        "-eq" => a == b,
+        "-ne" => a != b,
+        "-lt" => a < b,
+        "-le" => a <= b,
+        "-gt" => a > b,
+        _ => a >= b,
+    }
+}
+
+/// `a = b`, `a -lt b`, `a -nt b`... - only a non-number given to an
+/// integer comparison is an error
+pub fn binary(op: &str, left: &str, right: &str, ctx: &TerminalContext) -> Result<bool, String> {
+    Ok(match op {
+        "=" | "==" => left == right,
+        "!=" => left != right,
+        "<" => left < right,
+        ">" => left > right,
+        // a file that exists is newer than one that doesn't
+        "-nt" => match (lookup(left, ctx), lookup(right, ctx)) {
+            (Some(a), Some(b)) => meta(a).1 > meta(b).1,
+            (a, _) => a.is_some(),
+        },
+        "-ot" => match (lookup(left, ctx), lookup(right, ctx)) {
+            (Some(a), Some(b)) => meta(a).1 < meta(b).1,
+            (_, b) => b.is_some(),
+        },
+        // same node, however you got to it
+        "-ef" => match (lookup(left, ctx), lookup(right, ctx)) {
+            (Some(a), Some(b)) => std::ptr::eq(a, b),
+            _ => false,
+        },
+        op => compare_ints(op, parse_int(left)?, parse_int(right)?),
+    })
+}
+
+/// evaluate `test`'s arguments. Err means they didn't make sense, which
+/// is status 2 rather than plain false
+pub fn test(args: &[String], ctx: &TerminalContext) -> Result<bool, String> {
+    if args.is_empty() {
+        return Ok(false);
+    }
+    let mut p = TestArgs { args, pos: 0, ctx };
+    let result = p.or()?;
+    match args.get(p.pos) {
+        Some(arg) => Err(format!("{}: unexpected argument", arg)),
+        None => Ok(result),
+    }
+}
+
+// expr -o expr, expr -a expr, ! expr, ( expr ) and the primaries. -a
+// binds tighter than -o
+struct TestArgs<'a> {
+    args: &'a [String],
+    pos: usize,
+    ctx: &'a TerminalContext,
+}
+
+impl TestArgs<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.args.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Result<&str, String> {
+        let arg = self.args.get(self.pos).ok_or("argument expected")?;
+        self.pos += 1;
+        Ok(arg)
+    }
+
+    // `x OP y` coming up - that wins over reading x as an operator, so
+    // `[ ! = x ]` and `[ -n = -n ]` compare strings
+    fn binary_next(&self) -> bool {
+        self.args.get(self.pos + 1).is_some_and(|op| is_binary(op)) && self.args.len() > self.pos + 2
+    }
+
+    fn or(&mut self) -> Result<bool, String> {
+        let mut value = self.and()?;
+        while self.peek() == Some("-o") {
+            self.pos += 1;
+            value |= self.and()?;
+        }
+        Ok(value)
+    }
+
+    fn and(&mut self) -> Result<bool, String> {
+        let mut value = self.not()?;
+        while self.peek() == Some("-a") {
+            self.pos += 1;
+            value &= self.not()?;
+        }
+        Ok(value)
+    }
+
+    fn not(&mut self) -> Result<bool, String> {
+        if self.peek() == Some("!") && !self.binary_next() && self.args.len() > self.pos + 1 {
+            self.pos += 1;
+            return Ok(!self.not()?);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<bool, String> {
+        if self.binary_next() {
+            let left = self.next()?.to_string();
+            let op = self.next()?.to_string();
+            let right = self.next()?.to_string();
+            return binary(&op, &left, &right, self.ctx);
+        }
+        let arg = self.next()?.to_string();
+        if arg == "(" && self.pos < self.args.len() {
+            let value = self.or()?;
+            if self.next()? != ")" {
+                return Err("`)' expected".to_string());
+            }
+            return Ok(value);
+        }
+        if is_unary(&arg) && self.pos < self.args.len() {
+            let operand = self.next()?.to_string();
+            return Ok(unary(&arg, &operand, self.ctx));
+        }
+        // anything else on its own is true when it's not empty
+        Ok(!arg.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(args: &str, ctx: &TerminalContext) -> Result<bool, String> {
+        let args: Vec<String> = args.split_whitespace().map(String::from).collect();
+        test(&args, ctx)
+    }
+
+    #[test]
+    fn test_strings_and_numbers() {
+        let ctx = TerminalContext::new();
+        assert!(check("abc = abc", &ctx).unwrap());
+        assert!(check("abc != abd", &ctx).unwrap());
+        assert!(check("-n x", &ctx).unwrap());
+        assert!(!check("-z x", &ctx).unwrap());
+        assert!(check("10 -gt 9", &ctx).unwrap());
+        assert!(!check("3 -le 2", &ctx).unwrap());
+        assert_eq!(check("x -eq 1", &ctx).unwrap_err(), "x: integer expression expected");
+        // one word is just a non-empty check, none at all is false
+        assert!(check("-n", &ctx).unwrap());
+        assert!(!check("", &ctx).unwrap());
+        assert!(check("! = !", &ctx).unwrap());
+    }
+
+    #[test]
+    fn test_connectives() {
+        let ctx = TerminalContext::new();
+        assert!(check("! a = b", &ctx).unwrap());
+        assert!(check("a = b -o 1 -eq 1", &ctx).unwrap());
+        assert!(!check("a = a -a 1 -eq 2", &ctx).unwrap());
+        assert!(!check("! ( a = a -o b = c )", &ctx).unwrap());
+        assert!(check("( a = a", &ctx).is_err());
+        assert!(check("a b", &ctx).is_err());
+    }
+
+    #[test]
+    fn test_files() {
+        let mut ctx = TerminalContext::new();
+        ctx.vfs.create_dir("/tmp/d").unwrap();
+        ctx.vfs.create_file("/tmp/empty", Vec::new()).unwrap();
+        ctx.vfs.create_file("/tmp/full", b"hi".to_vec()).unwrap();
+        ctx.vfs.create_symlink("/tmp/link", "/tmp/full").unwrap();
+        ctx.cwd = "/tmp".to_string();
+        assert!(check("-d d -a -f full -a -e /tmp/empty", &ctx).unwrap());
+        assert!(!check("-f d", &ctx).unwrap());
+        assert!(!check("-e missing", &ctx).unwrap());
+        assert!(check("-s full", &ctx).unwrap());
+        assert!(!check("-s empty", &ctx).unwrap());
+        // -L looks at the link, everything else at what it points to
+        assert!(check("-L link -a -f link", &ctx).unwrap());
+        assert!(!check("-L full", &ctx).unwrap());
+        assert!(check("link -ef full", &ctx).unwrap());
+        assert!(check("-r full -a -w full", &ctx).unwrap());
+        assert!(!check("-x full", &ctx).unwrap());
+        assert!(check("-x d", &ctx).unwrap());
+        if let Some(VfsNode::File { mtime, .. }) = ctx.vfs.resolve_path_mut("/tmp/empty") {
+            *mtime -= chrono::Duration::seconds(60);
+        }
+        assert!(check("full -nt empty -a empty -ot full", &ctx).unwrap());
+        assert!(check("full -nt missing -a missing -ot full", &ctx).unwrap());
+    }
+}
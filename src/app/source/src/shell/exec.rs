@@ -0,0 +1,1434 @@
+use super::ast::{AndOr, Command, Compound, CondExpr, Connector, List, Pipeline, Redirect, SimpleCommand};
+use super::lexer::{RedirOp, Word};
+use super::parser::Parser;
+use super::{arith, cond, expand, lexer, pattern};
+use crate::command::{CommandRegistry, CommandResult};
+use crate::commands::cd::normalize_path;
+use crate::context::{Flow, ShellOptions, TerminalContext};
+use crate::process::{signal_name, SIGINT};
+use crate::vfs::VfsNode;
+use std::collections::HashMap;
+
+// a runaway `while true` would hang the browser tab for good, so loops
+// give up after this many rounds - unless $LOOP_LIMIT says otherwise
+const MAX_LOOP_ITERATIONS: usize = 100_000;
+
+// same idea for recursion, `f() { f; }` would otherwise blow the stack
+const MAX_FUNCTION_DEPTH: usize = 100;
+
+// interpreters that just mean this shell
+const SHELLS: &[&str] = &["sh", "bash"];
+
+/// which stream a piece of output was written to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stream {
+    Out, // stdout - the Ok side of a CommandResult
+    Err, // stderr - the Err side
+}
+
+/// everything the commands wrote, kept in order so stdout and stderr
+/// interleave on screen the same way they were produced
+#[derive(Debug, Default)]
+pub struct Output {
+    chunks: Vec<(Stream, String)>,
+}
+
+impl Output {
+    pub fn push(&mut self, stream: Stream, text: String) {
+        if !text.is_empty() {
+            self.chunks.push((stream, text));
+        }
+    }
+
+    // pull out just the stdout text (for a pipe), leaving stderr behind
+    fn take_stdout(&mut self) -> String {
+        let (out, rest): (Vec<_>, Vec<_>) = self.chunks.drain(..).partition(|(s, _)| *s == Stream::Out);
+        self.chunks = rest;
+        join_chunks(out.into_iter().map(|(_, text)| text))
+    }
+
+    fn append(&mut self, other: Output) {
+        self.chunks.extend(other.chunks);
+    }
+
+    /// everything in order, ready for the terminal
+    pub fn render(&self) -> String {
+        join_chunks(self.chunks.iter().map(|(_, text)| text.clone()))
+    }
+}
+
+// each chunk came from a different command, make sure they don't run together
+fn join_chunks(chunks: impl Iterator<Item = String>) -> String {
+    let mut out = String::new();
+    for chunk in chunks {
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&chunk);
+    }
+    out
+}
+
+/// lex, parse and run some input - a single line or a whole script.
+/// each top level command runs before the next one is parsed, so a
+/// syntax error further down doesn't stop the earlier lines
+pub fn run(input: &str, ctx: &mut TerminalContext, registry: &CommandRegistry) -> CommandResult {
+    let mut out = Output::default();
+    let mut status = run_script(input, None, ctx, registry, &mut out);
+    // an exit only unwinds as far as the outermost line, the terminal
+    // itself keeps going. a stray break/continue just gets dropped
+    if ctx.depth == 0 {
+        match ctx.flow.take() {
+            Some(Flow::Exit(code)) => {
+                status = code;
+                ctx.last_status = code;
+                run_exit_trap(ctx, registry, &mut out);
+            }
+            // ctrl-c ends the line, not the shell
+            Some(Flow::Interrupt) => {
+                status = 128 + SIGINT;
+                ctx.last_status = status;
+            }
+            _ => {}
+        }
+    }
+    if status == 0 {
+        Ok(out.render())
+    } else {
+        Err(out.render())
+    }
+}
+
+/// run a command line for at, watch or cron - in `cwd`, as a script of
+/// its own, so it can't change the user's variables, cwd or $?. what it
+/// wrote to stdout and stderr comes back together
+pub fn run_detached(input: &str, cwd: &str, ctx: &mut TerminalContext, registry: &CommandRegistry) -> String {
+    let status = ctx.last_status;
+    let parent = ChildScope::enter("sh", Vec::new(), ctx);
+    ctx.cwd = cwd.to_string();
+    let mut out = Output::default();
+    run_script(input, None, ctx, registry, &mut out);
+    ctx.flow = None;
+    run_exit_trap(ctx, registry, &mut out);
+    parent.restore(ctx);
+    ctx.last_status = status;
+    ctx.exit_code = None;
+    out.render()
+}
+
+/// run shell code in the current shell, into the caller's output - what
+/// source and eval do. exit, return and break are left in ctx.flow for
+/// whoever's above to deal with
+pub fn run_here(input: &str, stdin: Option<&str>, ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) -> i32 {
+    run_script(input, stdin, ctx, registry, out)
+}
+
+// the guts of run(), also used for function bodies so their output
+// lands in the caller's Output rather than coming back as a string
+fn run_script(input: &str, stdin: Option<&str>, ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) -> i32 {
+    let mut parser = match Parser::new(input) {
+        Ok(parser) => parser,
+        Err(e) => {
+            out.push(Stream::Err, e);
+            return 2;
+        }
+    };
+    let mut status = 0;
+    ctx.depth += 1;
+    loop {
+        parser.set_aliases(ctx.aliases.clone());
+        let list = match parser.next_list() {
+            Ok(Some(list)) => list,
+            Ok(None) => break,
+            Err(e) => {
+                out.push(Stream::Err, e);
+                status = 2; // what bash uses for syntax errors
+                ctx.last_status = status;
+                break;
+            }
+        };
+        status = exec_list(&list, stdin, ctx, registry, out);
+        if ctx.flow.is_some() {
+            break;
+        }
+    }
+    ctx.depth -= 1;
+    status
+}
+
+/// `$(script)` - run it and hand back its stdout minus the trailing
+/// newlines. the status ends up in ctx.exit_code for `x=$(cmd)` to pick up
+pub fn substitute(script: &str, ctx: &mut TerminalContext) -> Result<String, String> {
+    // an Arc clone rather than a take, so $(...) inside `source` still works
+    let registry = ctx.registry.clone().ok_or("command substitution: command registry not available")?;
+    let saved_loops = std::mem::replace(&mut ctx.loop_depth, 0);
+    let mut out = Output::default();
+    let mut status = capturing(true, ctx, |ctx| run_script(script, None, ctx, &registry, &mut out));
+    ctx.loop_depth = saved_loops;
+    // it's a subshell as far as exit/return go - they only end the
+    // substitution. ctrl-c stops whatever it's part of too
+    match ctx.flow.take() {
+        Some(Flow::Exit(code)) | Some(Flow::Return(code)) => status = code,
+        Some(Flow::Interrupt) => ctx.flow = Some(Flow::Interrupt),
+        _ => {}
+    }
+    ctx.exit_code = Some(status);
+    let stdout = out.take_stdout();
+    let stderr = out.render();
+    if !stderr.is_empty() {
+        if !ctx.pending_stderr.is_empty() {
+            ctx.pending_stderr.push('\n');
+        }
+        ctx.pending_stderr.push_str(&stderr);
+    }
+    Ok(stdout.trim_end_matches('\n').to_string())
+}
+
+// whatever a $(...) wrote to stderr goes to the terminal once the
+// command that used it gets going
+fn flush_pending_stderr(ctx: &mut TerminalContext, out: &mut Output) {
+    out.push(Stream::Err, std::mem::take(&mut ctx.pending_stderr));
+}
+
+/// run the traps for whatever signals the shell has had since the last
+/// look - between commands, like sh. $? is left as it was
+pub fn run_traps(ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) {
+    if ctx.trapping {
+        return;
+    }
+    for signal in ctx.processes.take_pending() {
+        // no trap (or `trap - SIG`) means the shell just ignores it -
+        // apart from ctrl-c, which stops whatever it's in the middle of
+        let Some(action) = ctx.traps.get(signal_name(signal)).cloned() else {
+            if signal == SIGINT && ctx.depth > 0 {
+                ctx.flow = Some(Flow::Interrupt);
+            }
+            continue;
+        };
+        let status = ctx.last_status;
+        ctx.trapping = true;
+        run_script(&action, None, ctx, registry, out);
+        ctx.trapping = false;
+        ctx.last_status = status;
+    }
+    // an exit from a trap that ran outside any script has nothing to end
+    if ctx.depth == 0 {
+        ctx.flow = None;
+    }
+}
+
+// trap ... EXIT - runs once, as the script (or the shell) finishes
+fn run_exit_trap(ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) {
+    if let Some(action) = ctx.traps.remove("EXIT") {
+        let saved = ctx.flow.take();
+        run_script(&action, None, ctx, registry, out);
+        ctx.flow = saved;
+    }
+}
+
+// `a; b; c` - the status of the whole thing is whatever ran last
+fn exec_list(list: &List, stdin: Option<&str>, ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) -> i32 {
+    let mut status = 0;
+    for item in &list.items {
+        status = match &item.background {
+            Some(text) => exec_background(item, text, stdin, ctx, registry, out),
+            None => exec_and_or(item, stdin, ctx, registry, out),
+        };
+        run_traps(ctx, registry, out);
+        if ctx.flow.is_some() {
+            break;
+        }
+    }
+    status
+}
+
+// `a && b || c` - skipped pipelines leave $? alone
+fn exec_and_or(and_or: &AndOr, stdin: Option<&str>, ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) -> i32 {
+    let mut status = exec_pipeline(&and_or.first, stdin, ctx, registry, out);
+    ctx.last_status = status;
+    // set -e ignores failures that a && or || is already dealing with,
+    // only the final pipeline of the chain counts
+    let mut guarded = !and_or.rest.is_empty();
+    for (i, (connector, pipeline)) in and_or.rest.iter().enumerate() {
+        if ctx.flow.is_some() {
+            return status;
+        }
+        let run_next = match connector {
+            Connector::And => status == 0,
+            Connector::Or => status != 0,
+        };
+        if run_next {
+            status = exec_pipeline(pipeline, stdin, ctx, registry, out);
+            ctx.last_status = status;
+            guarded = i + 1 < and_or.rest.len();
+        }
+    }
+    let checked = !guarded && ctx.condition_depth == 0;
+    if status != 0 && checked && ctx.options.errexit && ctx.flow.is_none() {
+        ctx.flow = Some(Flow::Exit(status));
+    }
+    status
+}
+
+// `cmd &`. nothing really runs side by side here, so the command runs
+// now - but as a job, and whatever it leaves going (a ping, a long cpu
+// run) carries on in the background under it. like a subshell, its
+// exit or failure under set -e doesn't touch the shell, which gets 0
+fn exec_background(and_or: &AndOr, text: &str, stdin: Option<&str>, ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) -> i32 {
+    let first = ctx.processes.next_pid();
+    let saved_errexit = std::mem::replace(&mut ctx.options.errexit, false);
+    let mut inner = Output::default();
+    let mut status = exec_and_or(and_or, stdin, ctx, registry, &mut inner);
+    ctx.options.errexit = saved_errexit;
+    if let Some(Flow::Exit(code)) = ctx.flow.take() {
+        status = code;
+    }
+    let job = ctx.processes.background(first, text, status);
+    // only an interactive shell says which job it started
+    if ctx.depth == 1 && ctx.script.is_none() {
+        out.push(Stream::Err, format!("[{}] {}", job.id, job.pid));
+    }
+    out.append(inner);
+    #[cfg(target_arch = "wasm32")]
+    crate::process::notify_when_done(job);
+    ctx.last_status = 0;
+    0
+}
+
+// run each stage in turn, feeding its stdout to the next one
+// stages that fail still let the pipeline carry on with empty input,
+// like a real shell - only the last stage decides the status
+fn exec_pipeline(pipeline: &Pipeline, stdin: Option<&str>, ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) -> i32 {
+    let mut input: Option<String> = stdin.map(|s| s.to_string());
+    let last = pipeline.commands.len() - 1;
+    let mut status = 0;
+
+    for (i, command) in pipeline.commands.iter().enumerate() {
+        // only the first stage shares the pipeline's own stdin
+        let fresh = i > 0;
+        if i == last {
+            status = with_input(fresh, ctx, |ctx| exec_command(command, input.as_deref(), ctx, registry, out));
+        } else {
+            let mut stage = Output::default();
+            capturing(true, ctx, |ctx| with_input(fresh, ctx, |ctx| exec_command(command, input.as_deref(), ctx, registry, &mut stage)));
+            input = Some(stage.take_stdout());
+            out.append(stage); // stderr still goes to the terminal
+        }
+        if ctx.flow.is_some() {
+            break;
+        }
+    }
+    status
+}
+
+fn exec_command(command: &Command, stdin: Option<&str>, ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) -> i32 {
+    match command {
+        Command::Simple(simple) => exec_simple(simple, stdin, ctx, registry, out),
+        Command::Compound(compound, redirects) => {
+            let mut redirs = match Redirections::setup(redirects, stdin, ctx, out) {
+                Some(redirs) => redirs,
+                None => return 1,
+            };
+            let mut inner = Output::default();
+            let input = redirs.input.take();
+            let status = capturing(redirs.captures(), ctx, |ctx| with_input(redirs.own_input, ctx, |ctx| exec_compound(compound, input.as_deref(), ctx, registry, &mut inner)));
+            flush_pending_stderr(ctx, out);
+            redirs.route(inner, out);
+            redirs.flush(ctx, out).unwrap_or(status)
+        }
+        Command::FunctionDef { name, body } => {
+            ctx.define_function(name, body);
+            0
+        }
+    }
+}
+
+// `read` only takes the line it needs and leaves the rest of stdin for
+// whatever runs next, with ctx.stdin_pos saying how far it's got. a pipe
+// or an input redirection brings its own stdin, so that starts over
+fn with_input<T>(fresh: bool, ctx: &mut TerminalContext, run: impl FnOnce(&mut TerminalContext) -> T) -> T {
+    if !fresh {
+        return run(ctx);
+    }
+    let saved = std::mem::replace(&mut ctx.stdin_pos, 0);
+    let result = run(ctx);
+    ctx.stdin_pos = saved;
+    result
+}
+
+// ctx.capturing is up while stdout isn't the terminal, so a command that
+// would carry on in the background (a long cpu run) finishes first instead
+fn capturing<T>(on: bool, ctx: &mut TerminalContext, run: impl FnOnce(&mut TerminalContext) -> T) -> T {
+    if !on {
+        return run(ctx);
+    }
+    ctx.capturing += 1;
+    let result = run(ctx);
+    ctx.capturing -= 1;
+    result
+}
+
+// run a function body with its own positionals and a fresh frame for
+// `local`. loops outside the function can't be broken out of from inside
+fn call_function(body: &str, args: Vec<String>, stdin: Option<&str>, ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) -> i32 {
+    if ctx.locals.len() >= MAX_FUNCTION_DEPTH {
+        out.push(Stream::Err, format!("maximum function nesting level exceeded ({})", MAX_FUNCTION_DEPTH));
+        return 1;
+    }
+    let saved_args = std::mem::replace(&mut ctx.args, args);
+    let saved_loops = std::mem::replace(&mut ctx.loop_depth, 0);
+    ctx.locals.push(Default::default());
+
+    let mut status = run_script(body, stdin, ctx, registry, out);
+    if let Some(Flow::Return(code)) = ctx.flow {
+        ctx.flow = None;
+        status = code;
+    }
+
+    // put back whatever the locals were hiding
+    for (name, old) in ctx.locals.pop().unwrap_or_default() {
+        match old {
+            Some(v) => ctx.vars.insert(name, v),
+            None => ctx.vars.remove(&name),
+        };
+    }
+    ctx.loop_depth = saved_loops;
+    ctx.args = saved_args;
+    status
+}
+
+// run a command as its own process - in the process table, with
+// whatever's running it as the parent, until it's done. one that got
+// killed on the way ends with 128 + the signal, like sh
+fn as_process(name: &str, args: &[String], ctx: &mut TerminalContext, run: impl FnOnce(&mut TerminalContext) -> i32) -> i32 {
+    let cmdline = std::iter::once(name).chain(args.iter().map(String::as_str)).collect::<Vec<_>>().join(" ");
+    let user = ctx.lookup_var("USER").cloned().unwrap_or_default();
+    let pid = ctx.processes.start(&cmdline, &user);
+    let status = run(ctx);
+    match ctx.processes.finish(pid) {
+        Some(signal) => 128 + signal,
+        None => status,
+    }
+}
+
+// a command from the registry. it only sees the part of stdin no `read`
+// has had yet
+fn run_builtin(cmd: &(dyn crate::command::Command + Send + Sync), args: &[String], stdin: Option<&str>, ctx: &mut TerminalContext, out: &mut Output) -> i32 {
+    let input = stdin.map(|s| s.get(ctx.stdin_pos..).unwrap_or_default());
+    if cmd.execute_into(args, input, ctx, out) {
+        return ctx.exit_code.take().unwrap_or(0);
+    }
+    match cmd.execute_with_stdin(args, input, ctx) {
+        Ok(text) => {
+            out.push(Stream::Out, text);
+            ctx.exit_code.take().unwrap_or(0)
+        }
+        Err(text) => {
+            out.push(Stream::Err, text);
+            ctx.exit_code.take().unwrap_or(1)
+        }
+    }
+}
+
+// a command that's neither a function nor a builtin: a path if the name
+// has a / in it, otherwise the first executable file of that name in $PATH
+fn find_executable(name: &str, ctx: &TerminalContext) -> Result<String, (String, i32)> {
+    let executable = |node: &VfsNode| matches!(node, VfsNode::File { permissions, .. } if permissions.user & 0b001 != 0);
+    if name.contains('/') {
+        let path = normalize_path(&ctx.resolve_path(name));
+        return match ctx.vfs.resolve_path_with_symlinks(&path, false) {
+            None => Err((format!("{}: No such file or directory", name), 127)),
+            Some(VfsNode::Directory { .. }) => Err((format!("{}: Is a directory", name), 126)),
+            Some(node) if !executable(node) => Err((format!("{}: Permission denied", name), 126)),
+            Some(_) => Ok(path),
+        };
+    }
+    let dirs = ctx.lookup_var("PATH").cloned().unwrap_or_default();
+    dirs.split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| normalize_path(&ctx.resolve_path(&format!("{}/{}", dir, name))))
+        .find(|path| ctx.vfs.resolve_path_with_symlinks(path, false).is_some_and(executable))
+        .ok_or_else(|| (format!("Command not found: {}", name), 127))
+}
+
+// run an executable file. `#!interpreter [arg]` hands it to that command
+// as `interpreter [arg] path args...`, anything else is a shell script
+fn run_executable(path: &str, args: &[String], stdin: Option<&str>, ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) -> i32 {
+    let script = match ctx.vfs.read_file(path) {
+        Ok(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        Err(e) => {
+            out.push(Stream::Err, format!("{}: {}", path, e));
+            return 126;
+        }
+    };
+    if let Some((interpreter, arg)) = shebang(&script) {
+        let name = interpreter.rsplit('/').next().unwrap_or_default();
+        if !SHELLS.contains(&name) {
+            let Some(cmd) = registry.get(name) else {
+                out.push(Stream::Err, format!("{}: {}: bad interpreter: No such file or directory", path, interpreter));
+                return 126;
+            };
+            let args: Vec<String> = arg.into_iter().chain([path.to_string()]).chain(args.iter().cloned()).collect();
+            return run_builtin(cmd.as_ref(), &args, stdin, ctx, out);
+        }
+    }
+    if ctx.depth >= MAX_FUNCTION_DEPTH {
+        out.push(Stream::Err, format!("{}: maximum nesting level exceeded ({})", path, MAX_FUNCTION_DEPTH));
+        return 1;
+    }
+
+    let parent = ChildScope::enter(path, args.to_vec(), ctx);
+    let mut status = run_script(&script, stdin, ctx, registry, out);
+    // exit (or a stray return) only ends the script. ctrl-c goes to
+    // whoever ran it as well
+    match ctx.flow.take() {
+        Some(Flow::Exit(code)) | Some(Flow::Return(code)) => status = code,
+        Some(Flow::Interrupt) => {
+            ctx.flow = Some(Flow::Interrupt);
+            status = 128 + SIGINT;
+        }
+        _ => {}
+    }
+    run_exit_trap(ctx, registry, out);
+    parent.restore(ctx);
+    status
+}
+
+// the interpreter and optional argument from a `#!` line. `#!/usr/bin/env
+// cpu` means cpu, there's only the one PATH to look in
+fn shebang(script: &str) -> Option<(String, Option<String>)> {
+    let line = script.strip_prefix("#!")?.lines().next().unwrap_or_default().trim();
+    let split = |text: &str| match text.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word.to_string(), Some(rest.trim().to_string())),
+        None => (text.to_string(), None),
+    };
+    match split(line) {
+        (interpreter, Some(rest)) if interpreter.rsplit('/').next() == Some("env") => Some(split(&rest)),
+        (interpreter, _) if interpreter.is_empty() => None,
+        found => Some(found),
+    }
+}
+
+// what a script sees of the shell that ran it, as if it were a new
+// process: the environment and cwd but none of the shell variables,
+// functions, aliases, options or traps. the files are shared of course
+struct ChildScope {
+    env: HashMap<String, String>,
+    vars: HashMap<String, String>,
+    cwd: String,
+    aliases: HashMap<String, String>,
+    functions: HashMap<String, String>,
+    options: ShellOptions,
+    args: Vec<String>,
+    locals: Vec<HashMap<String, Option<String>>>,
+    loop_depth: u32,
+    condition_depth: u32,
+    source_depth: u32,
+    script: Option<String>,
+    traps: HashMap<String, String>,
+}
+
+impl ChildScope {
+    fn enter(script: &str, args: Vec<String>, ctx: &mut TerminalContext) -> Self {
+        ChildScope {
+            env: ctx.env.clone(),
+            vars: std::mem::take(&mut ctx.vars),
+            cwd: ctx.cwd.clone(),
+            aliases: std::mem::take(&mut ctx.aliases),
+            functions: std::mem::take(&mut ctx.functions),
+            options: std::mem::take(&mut ctx.options),
+            args: std::mem::replace(&mut ctx.args, args),
+            locals: std::mem::take(&mut ctx.locals),
+            loop_depth: std::mem::replace(&mut ctx.loop_depth, 0),
+            condition_depth: std::mem::replace(&mut ctx.condition_depth, 0),
+            source_depth: std::mem::replace(&mut ctx.source_depth, 0),
+            script: ctx.script.replace(script.to_string()),
+            traps: std::mem::take(&mut ctx.traps),
+        }
+    }
+
+    fn restore(self, ctx: &mut TerminalContext) {
+        ctx.env = self.env;
+        ctx.vars = self.vars;
+        ctx.cwd = self.cwd;
+        ctx.aliases = self.aliases;
+        ctx.functions = self.functions;
+        ctx.options = self.options;
+        ctx.args = self.args;
+        ctx.locals = self.locals;
+        ctx.loop_depth = self.loop_depth;
+        ctx.condition_depth = self.condition_depth;
+        ctx.source_depth = self.source_depth;
+        ctx.script = self.script;
+        ctx.traps = self.traps;
+    }
+}
+
+fn exec_compound(compound: &Compound, stdin: Option<&str>, ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) -> i32 {
+    match compound {
+        Compound::If { branches, otherwise } => {
+            for (cond, body) in branches {
+                let status = exec_condition(cond, stdin, ctx, registry, out);
+                if ctx.flow.is_some() {
+                    return status;
+                }
+                if status == 0 {
+                    return exec_list(body, stdin, ctx, registry, out);
+                }
+            }
+            match otherwise {
+                Some(body) => exec_list(body, stdin, ctx, registry, out),
+                None => 0, // no branch taken counts as success
+            }
+        }
+        Compound::Loop { until, cond, body } => {
+            let mut status = 0;
+            let mut rounds = 0;
+            ctx.loop_depth += 1;
+            loop {
+                let test = exec_condition(cond, stdin, ctx, registry, out);
+                if ctx.flow.is_some() || (test == 0) == *until {
+                    break;
+                }
+                rounds += 1;
+                if rounds > loop_limit(ctx) {
+                    out.push(Stream::Err, format!("{}: loop limit reached ({} iterations)", if *until { "until" } else { "while" }, loop_limit(ctx)));
+                    status = 1;
+                    break;
+                }
+                status = exec_list(body, stdin, ctx, registry, out);
+                if !keep_looping(ctx) {
+                    break;
+                }
+            }
+            ctx.loop_depth -= 1;
+            status
+        }
+        Compound::For { var, words, body } => {
+            let items = match words {
+                Some(words) => {
+                    let mut items = Vec::new();
+                    for word in words {
+                        match expand::expand_word(word, ctx) {
+                            Ok(fields) => items.extend(fields),
+                            Err(e) => {
+                                out.push(Stream::Err, e);
+                                return 1;
+                            }
+                        }
+                    }
+                    items
+                }
+                None => ctx.args.clone(),
+            };
+            let mut status = 0;
+            ctx.loop_depth += 1;
+            for item in items {
+                ctx.assign_var(var, &item);
+                status = exec_list(body, stdin, ctx, registry, out);
+                if !keep_looping(ctx) {
+                    break;
+                }
+            }
+            ctx.loop_depth -= 1;
+            status
+        }
+        Compound::Case { word, arms } => {
+            let subject = match expand::expand_string(word, ctx) {
+                Ok(subject) => subject,
+                Err(e) => {
+                    out.push(Stream::Err, e);
+                    return 1;
+                }
+            };
+            for arm in arms {
+                for pat in &arm.patterns {
+                    let pat = match expand::expand_pattern(pat, ctx) {
+                        Ok(pat) => pat,
+                        Err(e) => {
+                            out.push(Stream::Err, e);
+                            return 1;
+                        }
+                    };
+                    if pattern::matches(&pat, &subject) {
+                        return exec_list(&arm.body, stdin, ctx, registry, out);
+                    }
+                }
+            }
+            0
+        }
+        Compound::Group(body) => exec_list(body, stdin, ctx, registry, out),
+        Compound::Arith(expr) => match arith_test(expr, ctx) {
+            Ok(true) => 0,
+            Ok(false) => 1,
+            Err(e) => {
+                out.push(Stream::Err, e);
+                1
+            }
+        },
+        Compound::Cond(expr) => match eval_cond(expr, ctx) {
+            Ok(true) => 0,
+            Ok(false) => 1,
+            Err(e) => {
+                out.push(Stream::Err, e);
+                2
+            }
+        },
+        Compound::ArithFor { init, cond, step, body } => {
+            if let Err(e) = expand::arithmetic(init, ctx) {
+                out.push(Stream::Err, e);
+                return 1;
+            }
+            let mut status = 0;
+            let mut rounds = 0;
+            ctx.loop_depth += 1;
+            loop {
+                // an empty condition loops until something breaks out
+                let go = if cond.parts.is_empty() { Ok(true) } else { arith_test(cond, ctx) };
+                match go {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(e) => {
+                        out.push(Stream::Err, e);
+                        status = 1;
+                        break;
+                    }
+                }
+                rounds += 1;
+                if rounds > loop_limit(ctx) {
+                    out.push(Stream::Err, format!("for: loop limit reached ({} iterations)", loop_limit(ctx)));
+                    status = 1;
+                    break;
+                }
+                status = exec_list(body, stdin, ctx, registry, out);
+                if !keep_looping(ctx) {
+                    break;
+                }
+                if let Err(e) = expand::arithmetic(step, ctx) {
+                    out.push(Stream::Err, e);
+                    status = 1;
+                    break;
+                }
+            }
+            ctx.loop_depth -= 1;
+            status
+        }
+    }
+}
+
+// (( expr )) and the test in for (( )) - true when non-zero
+fn arith_test(expr: &Word, ctx: &mut TerminalContext) -> Result<bool, String> {
+    Ok(expand::arithmetic(expr, ctx)? != "0")
+}
+
+// [[ ]] - operands are expanded without splitting or globbing, the right
+// of == and != is a pattern and the right of =~ a regex
+fn eval_cond(expr: &CondExpr, ctx: &mut TerminalContext) -> Result<bool, String> {
+    Ok(match expr {
+        CondExpr::Word(word) => !expand::expand_string(word, ctx)?.is_empty(),
+        CondExpr::Unary(op, word) => {
+            let arg = expand::expand_string(word, ctx)?;
+            cond::unary(op, &arg, ctx)
+        }
+        CondExpr::Not(expr) => !eval_cond(expr, ctx)?,
+        CondExpr::And(a, b) => eval_cond(a, ctx)? && eval_cond(b, ctx)?,
+        CondExpr::Or(a, b) => eval_cond(a, ctx)? || eval_cond(b, ctx)?,
+        CondExpr::Binary(op, left, right) => {
+            let left = expand::expand_string(left, ctx)?;
+            match op.as_str() {
+                "=" | "==" | "!=" => {
+                    let pat = expand::expand_pattern(right, ctx)?;
+                    pattern::matches(&pat, &left) == (op != "!=")
+                }
+                "=~" => {
+                    let re = expand::expand_regex(right, ctx)?;
+                    let re = regex::Regex::new(&re).map_err(|_| format!("{}: invalid regular expression", re))?;
+                    re.is_match(&left)
+                }
+                // both sides are arithmetic here, so [[ x+1 -eq 3 ]] works
+                op if cond::is_integer_op(op) => {
+                    let right = expand::expand_string(right, ctx)?;
+                    cond::compare_ints(op, arith::eval(&left, ctx)?, arith::eval(&right, ctx)?)
+                }
+                op => {
+                    let right = expand::expand_string(right, ctx)?;
+                    cond::binary(op, &left, &right, ctx)?
+                }
+            }
+        }
+    })
+}
+
+// the test part of an if/while - failures there are expected, so
+// set -e leaves them alone
+fn exec_condition(cond: &List, stdin: Option<&str>, ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) -> i32 {
+    ctx.condition_depth += 1;
+    let status = exec_list(cond, stdin, ctx, registry, out);
+    ctx.condition_depth -= 1;
+    status
+}
+
+// sort out a pending break/continue after one round of a loop body.
+// false means stop this loop (the flow may still be pending for an outer one)
+fn keep_looping(ctx: &mut TerminalContext) -> bool {
+    match ctx.flow {
+        Some(Flow::Break(n)) => {
+            ctx.flow = if n > 1 { Some(Flow::Break(n - 1)) } else { None };
+            false
+        }
+        Some(Flow::Continue(n)) if n > 1 => {
+            ctx.flow = Some(Flow::Continue(n - 1));
+            false
+        }
+        Some(Flow::Continue(_)) => {
+            ctx.flow = None;
+            true
+        }
+        Some(Flow::Exit(_)) | Some(Flow::Return(_)) | Some(Flow::Interrupt) => false,
+        None => true,
+    }
+}
+
+// how many rounds a while, until or for (( )) gets - $LOOP_LIMIT, where 0
+// means no limit, else MAX_LOOP_ITERATIONS
+fn loop_limit(ctx: &TerminalContext) -> usize {
+    match ctx.lookup_var("LOOP_LIMIT").and_then(|n| n.parse().ok()) {
+        Some(0) => usize::MAX,
+        Some(n) => n,
+        None => MAX_LOOP_ITERATIONS,
+    }
+}
+
+// set -x - show the command as it's about to run, after expansion,
+// prefixed with $PS4. goes straight to the terminal, redirects don't apply
+fn trace(assigns: &[(String, String)], args: &[String], ctx: &mut TerminalContext, out: &mut Output) {
+    let ps4 = ctx.lookup_var("PS4").cloned().unwrap_or_else(|| "+ ".to_string());
+    let prefix = lexer::lex_word(&ps4)
+        .and_then(|word| expand::expand_string(&word, ctx))
+        .unwrap_or(ps4);
+    let words: Vec<String> = assigns
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, trace_quote(value)))
+        .chain(args.iter().map(|arg| trace_quote(arg)))
+        .collect();
+    out.push(Stream::Err, format!("{}{}\n", prefix, words.join(" ")));
+}
+
+// quote an arg only if it wouldn't survive being typed back in as is
+fn trace_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%^".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+// where fd 1/2 currently point
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    Inherit(Stream),       // wherever our caller sends that stream
+    File(String),          // path, see `files` for what gets written
+    Closed,                // 2>&- and friends - just drop it
+}
+
+// the result of working through a command's redirections
+struct Redirections {
+    stdout: Target,
+    stderr: Target,
+    input: Option<String>,
+    own_input: bool, // input came from <, << or <<< rather than the caller
+    files: Vec<(String, String)>, // path -> data to write
+}
+
+impl Redirections {
+    // work out redirections in order, so `>f 2>&1` and `2>&1 >f` end up
+    // meaning different things just like in sh. errors are reported
+    // straight to `out` and give None
+    fn setup(redirects: &[Redirect], stdin: Option<&str>, ctx: &mut TerminalContext, out: &mut Output) -> Option<Self> {
+        let mut redirs = Redirections {
+            stdout: Target::Inherit(Stream::Out),
+            stderr: Target::Inherit(Stream::Err),
+            input: stdin.map(|s| s.to_string()),
+            own_input: false,
+            files: Vec::new(),
+        };
+
+        for redirect in redirects {
+            // here-doc bodies and here-strings are the input as they
+            // stand, no splitting or globbing
+            if matches!(redirect.op, RedirOp::HereDoc | RedirOp::HereString) {
+                redirs.own_input = true;
+                match expand::expand_string(&redirect.target, ctx) {
+                    Ok(text) if redirect.op == RedirOp::HereString => redirs.input = Some(text + "\n"),
+                    Ok(text) => redirs.input = Some(text),
+                    Err(e) => {
+                        out.push(Stream::Err, e);
+                        return None;
+                    }
+                }
+                continue;
+            }
+            let target = match expand::expand_word(&redirect.target, ctx) {
+                Ok(fields) if fields.len() == 1 => fields[0].clone(),
+                Ok(_) => {
+                    out.push(Stream::Err, format!("{}: ambiguous redirect", redirect.target.text()));
+                    return None;
+                }
+                Err(e) => {
+                    out.push(Stream::Err, e);
+                    return None;
+                }
+            };
+            let new_target = match redirect.op {
+                RedirOp::HereDoc | RedirOp::HereString => continue,
+                RedirOp::In => {
+                    let path = normalize_path(&ctx.resolve_path(&target));
+                    match ctx.vfs.read_file(&path) {
+                        Ok(bytes) => {
+                            redirs.input = Some(String::from_utf8_lossy(bytes).to_string());
+                            redirs.own_input = true;
+                        }
+                        Err(_) => {
+                            out.push(Stream::Err, format!("{}: No such file or directory", target));
+                            return None;
+                        }
+                    }
+                    continue;
+                }
+                RedirOp::Out | RedirOp::Append => {
+                    let path = normalize_path(&ctx.resolve_path(&target));
+                    let append = redirect.op == RedirOp::Append;
+                    // > truncates straight away even if nothing gets written
+                    let existing = if append {
+                        ctx.vfs.read_file(&path).map(|b| String::from_utf8_lossy(b).to_string()).unwrap_or_default()
+                    } else {
+                        String::new()
+                    };
+                    if !redirs.files.iter().any(|(p, _)| *p == path) {
+                        redirs.files.push((path.clone(), existing));
+                    }
+                    Target::File(path)
+                }
+                RedirOp::DupOut => match target.as_str() {
+                    "1" => redirs.stdout.clone(),
+                    "2" => redirs.stderr.clone(),
+                    "-" => Target::Closed,
+                    _ => {
+                        out.push(Stream::Err, format!("{}: ambiguous redirect", target));
+                        return None;
+                    }
+                },
+            };
+            match redirect.fd {
+                1 => redirs.stdout = new_target,
+                2 => redirs.stderr = new_target,
+                _ => {} // other fds don't go anywhere in here
+            }
+        }
+        Some(redirs)
+    }
+
+    // stdout goes to a file or nowhere, not the terminal
+    fn captures(&self) -> bool {
+        !matches!(self.stdout, Target::Inherit(Stream::Out))
+    }
+
+    // send what a command wrote wherever its fds point
+    fn route(&mut self, produced: Output, out: &mut Output) {
+        for (stream, text) in produced.chunks {
+            let target = if stream == Stream::Out { &self.stdout } else { &self.stderr };
+            match target {
+                Target::Inherit(s) => out.push(*s, text),
+                Target::File(path) => {
+                    if let Some((_, data)) = self.files.iter_mut().find(|(p, _)| p == path) {
+                        // files are line oriented - don't leave the last line hanging
+                        data.push_str(&text);
+                        if !data.is_empty() && !data.ends_with('\n') {
+                            data.push('\n');
+                        }
+                    }
+                }
+                Target::Closed => {}
+            }
+        }
+    }
+
+    // write redirected output into the vfs, persisted like any other
+    // write. Some(1) if that failed
+    fn flush(self, ctx: &mut TerminalContext, out: &mut Output) -> Option<i32> {
+        for (path, data) in self.files {
+            if let Err(e) = ctx.write_file_with_events(&path, data.as_bytes()) {
+                out.push(Stream::Err, format!("{}: {}", path, e));
+                return Some(1);
+            }
+        }
+        None
+    }
+}
+
+fn exec_simple(command: &SimpleCommand, stdin: Option<&str>, ctx: &mut TerminalContext, registry: &CommandRegistry, out: &mut Output) -> i32 {
+    // expansions can fail (${x:?}), which stops the command before it runs
+    ctx.exit_code = None;
+    let mut args = Vec::new();
+    for word in &command.words {
+        match expand::expand_word(word, ctx) {
+            Ok(fields) => args.extend(fields),
+            Err(e) => {
+                out.push(Stream::Err, e);
+                return 1;
+            }
+        }
+    }
+    let mut assigns = Vec::new();
+    for assign in &command.assigns {
+        match expand::expand_assignment(&assign.value, ctx) {
+            Ok(value) => assigns.push((assign.name.clone(), value)),
+            Err(e) => {
+                out.push(Stream::Err, e);
+                return 1;
+            }
+        }
+    }
+    flush_pending_stderr(ctx, out);
+    if ctx.options.xtrace {
+        trace(&assigns, &args, ctx, out);
+    }
+
+    let redirs = Redirections::setup(&command.redirects, stdin, ctx, out);
+    flush_pending_stderr(ctx, out);
+    let Some(mut redirs) = redirs else { return 1 };
+
+    // redirect-only line (`> file`) just creates/truncates the file,
+    // and bare `NAME=value` sets the variable for good. the status is
+    // that of the last $(...) in there, if any
+    let mut produced = Output::default();
+    let status = if args.is_empty() {
+        for (name, value) in &assigns {
+            ctx.assign_var(name, value);
+        }
+        ctx.exit_code.take().unwrap_or(0)
+    } else {
+        let name = args.remove(0);
+        // `NAME=value cmd` only puts NAME in the env while cmd runs
+        let saved: Vec<(String, Option<String>)> = assigns
+            .into_iter()
+            .map(|(k, v)| {
+                let old = ctx.env.insert(k.clone(), v);
+                (k, old)
+            })
+            .collect();
+        // find & run cmd or bail with err
+        // commands that care about their exact status leave it in
+        // ctx.exit_code, everyone else gets 0 for Ok and 1 for Err
+        ctx.exit_code = None;
+        // functions shadow commands of the same name
+        let input = redirs.input.as_deref();
+        let status = capturing(redirs.captures(), ctx, |ctx| with_input(redirs.own_input, ctx, |ctx| if let Some(body) = ctx.functions.get(&name).cloned() {
+            call_function(&body, args, input, ctx, registry, &mut produced)
+        } else if let Some(cmd) = registry.get(&name) {
+            as_process(&name, &args, ctx, |ctx| run_builtin(cmd.as_ref(), &args, input, ctx, &mut produced))
+        } else {
+            match find_executable(&name, ctx) {
+                Ok(path) => as_process(&name, &args, ctx, |ctx| run_executable(&path, &args, input, ctx, registry, &mut produced)),
+                Err((message, status)) => {
+                    produced.push(Stream::Err, message);
+                    status
+                }
+            }
+        }));
+        for (k, old) in saved.into_iter().rev() {
+            match old {
+                Some(v) => ctx.env.insert(k, v),
+                None => ctx.env.remove(&k),
+            };
+        }
+        status
+    };
+
+    redirs.route(produced, out);
+    redirs.flush(ctx, out).unwrap_or(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_line(input: &str, ctx: &mut TerminalContext) -> CommandResult {
+        let registry = CommandRegistry::default_commands();
+        run(input, ctx, &registry)
+    }
+
+    #[test]
+    fn test_pipeline_feeds_stdin() {
+        let mut ctx = TerminalContext::new();
+        let result = run_line("echo \"one two\" | sed s/one/1/ | grep 1", &mut ctx);
+        assert_eq!(result.unwrap(), "1 two");
+    }
+
+    #[test]
+    fn test_input_redirect() {
+        let mut ctx = TerminalContext::new();
+        ctx.vfs.create_file("/tmp/words", b"apple\nbanana\ncherry".to_vec()).unwrap();
+        ctx.cwd = "/tmp".to_string();
+        assert_eq!(run_line("grep an < words", &mut ctx).unwrap(), "banana");
+        assert!(run_line("cat < missing", &mut ctx).is_err());
+    }
+
+    #[test]
+    fn test_stderr_of_earlier_stage_is_kept() {
+        let mut ctx = TerminalContext::new();
+        let result = run_line("nosuchcmd | echo after", &mut ctx).unwrap();
+        assert_eq!(result, "Command not found: nosuchcmd\nafter\n");
+    }
+
+    #[test]
+    fn test_expansion_and_assignment() {
+        let mut ctx = TerminalContext::new();
+        run_line("greeting='hello there'", &mut ctx).unwrap();
+        assert_eq!(run_line("echo \"$greeting\" ${missing:-friend}", &mut ctx).unwrap(), "hello there friend\n");
+        // prefix assignments only last for the one command
+        assert!(run_line("TMPVAR=1 env", &mut ctx).unwrap().contains("TMPVAR=1"));
+        assert!(!ctx.env.contains_key("TMPVAR"));
+        assert!(run_line("echo ${nope:?}", &mut ctx).is_err());
+    }
+
+    #[test]
+    fn test_status_is_recorded() {
+        let mut ctx = TerminalContext::new();
+        let _ = run_line("nosuchcmd", &mut ctx);
+        assert_eq!(run_line("echo $?", &mut ctx).unwrap(), "127\n");
+        assert_eq!(run_line("echo $?", &mut ctx).unwrap(), "0\n");
+    }
+
+    #[test]
+    fn test_list_operators() {
+        let mut ctx = TerminalContext::new();
+        assert_eq!(run_line("echo a; echo b", &mut ctx).unwrap(), "a\nb\n");
+        assert_eq!(run_line("true && echo yes || echo no", &mut ctx).unwrap(), "yes\n");
+        assert_eq!(run_line("false && echo yes || echo no", &mut ctx).unwrap(), "no\n");
+        // the last thing that ran decides the overall status
+        assert!(run_line("echo a; false", &mut ctx).is_err());
+        assert!(run_line("false; echo a", &mut ctx).is_ok());
+        assert!(run_line("false || false", &mut ctx).is_err());
+        let _ = run_line("false && echo skipped", &mut ctx);
+        assert_eq!(run_line("echo $?", &mut ctx).unwrap(), "1\n");
+    }
+
+    #[test]
+    fn test_commands_can_pick_their_status() {
+        let mut ctx = TerminalContext::new();
+        let _ = run_line("false", &mut ctx);
+        assert_eq!(ctx.last_status, 1);
+        let _ = run_line("true", &mut ctx);
+        assert_eq!(ctx.last_status, 0);
+        // grep says 1 for no match but 2 for a missing file
+        let _ = run_line("echo abc | grep x", &mut ctx);
+        assert_eq!(ctx.last_status, 1);
+        let _ = run_line("grep x /no/such/file", &mut ctx);
+        assert_eq!(ctx.last_status, 2);
+    }
+
+    #[test]
+    fn test_errexit_stops_the_list() {
+        let mut ctx = TerminalContext::new();
+        ctx.options.errexit = true;
+        let result = run_line("echo one; false; echo two", &mut ctx).unwrap_err();
+        assert_eq!(result, "one\n");
+        // failures handled by && or || don't count
+        assert_eq!(run_line("false || echo handled; false && echo no; echo still", &mut ctx).unwrap(), "handled\nstill\n");
+        assert!(run_line("true && false; echo nope", &mut ctx).is_err());
+        // the next line runs as normal
+        assert!(ctx.flow.is_none());
+        assert_eq!(run_line("echo again", &mut ctx).unwrap(), "again\n");
+    }
+
+    #[test]
+    fn test_errexit_aborts_sourced_script() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_command_registry(std::sync::Arc::new(CommandRegistry::default_commands()));
+        ctx.vfs.create_file("/tmp/script.sh", b"echo start\nfalse\necho unreachable".to_vec()).unwrap();
+        let _ = run_line("set -e; source /tmp/script.sh; echo after", &mut ctx);
+        assert_eq!(ctx.last_status, 1);
+        ctx.vfs.write_file("/tmp/script.sh", b"false\nx=set".to_vec()).unwrap();
+        let _ = run_line("source /tmp/script.sh", &mut ctx);
+        assert!(ctx.get_var("x").is_none());
+        // without -e the script just carries on
+        let _ = run_line("set +e; source /tmp/script.sh", &mut ctx);
+        assert_eq!(ctx.get_var("x").unwrap(), "set");
+    }
+
+    #[test]
+    fn test_xtrace_prints_expanded_commands() {
+        let mut ctx = TerminalContext::new();
+        ctx.options.xtrace = true;
+        ctx.set_var("who", "the world");
+        assert_eq!(run_line("X=1 echo hi \"$who\"", &mut ctx).unwrap(), "+ X=1 echo hi 'the world'\nhi the world\n");
+        // PS4 gets expanded each time
+        ctx.set_var("PS4", "[$?] ");
+        let _ = run_line("false", &mut ctx);
+        assert_eq!(run_line("echo", &mut ctx).unwrap(), "[1] echo\n\n");
+    }
+
+    #[test]
+    fn test_if_branches() {
+        let mut ctx = TerminalContext::new();
+        ctx.vfs.create_file("/tmp/present", Vec::new()).unwrap();
+        let script = "if cat /tmp/present; then echo yes; else echo no; fi";
+        assert_eq!(run_line(script, &mut ctx).unwrap(), "yes\n");
+        let script = "if false; then echo a\nelif true; then echo b\nelse echo c\nfi";
+        assert_eq!(run_line(script, &mut ctx).unwrap(), "b\n");
+        assert!(run_line("if false; then echo a; fi", &mut ctx).is_ok());
+        // a failing condition doesn't trip set -e
+        ctx.options.errexit = true;
+        assert_eq!(run_line("if false; then echo a; fi; echo after", &mut ctx).unwrap(), "after\n");
+    }
+
+    #[test]
+    fn test_loops() {
+        let mut ctx = TerminalContext::new();
+        assert_eq!(run_line("for x in a 'b c'; do echo $x; done", &mut ctx).unwrap(), "a\nb c\n");
+        assert_eq!(ctx.get_var("x").unwrap(), "b c");
+        ctx.set_args(vec!["1".to_string(), "2".to_string()]);
+        assert_eq!(run_line("for n; do echo $n; done", &mut ctx).unwrap(), "1\n2\n");
+        // while/until with break and continue
+        let script = "n=; while true; do n=${n}x; case $n in xxx) break;; esac; done; echo $n";
+        assert_eq!(run_line(script, &mut ctx).unwrap(), "xxx\n");
+        let script = "for i in 1 2 3; do case $i in 2) continue;; esac; echo $i; done";
+        assert_eq!(run_line(script, &mut ctx).unwrap(), "1\n3\n");
+        let script = "for a in 1 2; do for b in x y; do echo $a$b; break 2; done; done";
+        assert_eq!(run_line(script, &mut ctx).unwrap(), "1x\n");
+        assert_eq!(run_line("until true; do echo never; done", &mut ctx).unwrap(), "");
+        assert!(run_line("while true; do :; done", &mut ctx).unwrap_err().contains("loop limit"));
+        // $LOOP_LIMIT moves the cap, and 0 takes it away
+        assert_eq!(run_line("LOOP_LIMIT=3; n=0; while true; do n=$((n + 1)); done", &mut ctx).unwrap_err(), "while: loop limit reached (3 iterations)");
+        assert_eq!(ctx.get_var("n").unwrap(), "3");
+        assert_eq!(run_line("LOOP_LIMIT=0; for ((i = 0; i < 100001; i++)); do :; done; echo $i", &mut ctx).unwrap(), "100001\n");
+    }
+
+    #[test]
+    fn test_case_patterns() {
+        let mut ctx = TerminalContext::new();
+        let script = "for f in notes.txt run.sh other; do case $f in\n *.txt) echo text;;\n *.sh|*.bash) echo script;;\n *) echo \"? $f\";;\nesac; done";
+        assert_eq!(run_line(script, &mut ctx).unwrap(), "text\nscript\n? other\n");
+        // quoted patterns are literal
+        assert_eq!(run_line("case '*' in '*') echo star;; *) echo any;; esac", &mut ctx).unwrap(), "star\n");
+        assert_eq!(run_line("case abc in '*') echo star;; *) echo any;; esac", &mut ctx).unwrap(), "any\n");
+    }
+
+    #[test]
+    fn test_compound_output_and_syntax_errors() {
+        let mut ctx = TerminalContext::new();
+        assert_eq!(run_line("for w in b a; do echo $w; done | grep a", &mut ctx).unwrap(), "a");
+        assert_eq!(run_line("if true; then nosuchcmd; fi 2>&-", &mut ctx).unwrap_err(), "");
+        // earlier lines still run before a syntax error further down
+        assert_eq!(run_line("echo first\nfi", &mut ctx).unwrap_err(), "first\nsyntax error near unexpected token `fi'");
+        assert_eq!(ctx.last_status, 2);
+    }
+
+    #[test]
+    fn test_output_redirects() {
+        let mut ctx = TerminalContext::new();
+        ctx.cwd = "/tmp".to_string();
+        let file = |path: &str, ctx: &TerminalContext| String::from_utf8_lossy(ctx.vfs.read_file(path).unwrap()).to_string();
+        assert_eq!(run_line("echo hi > ./out; echo again >> out", &mut ctx).unwrap(), "");
+        assert_eq!(file("/tmp/out", &ctx), "hi\nagain\n");
+        assert_eq!(run_line("cat < ./out", &mut ctx).unwrap(), "hi\nagain\n");
+        run_line("echo q > ../tmp/./q", &mut ctx).unwrap();
+        assert_eq!(file("/tmp/q", &ctx), "q\n");
+
+        // 2> only takes stderr, 2>&1 sends it wherever stdout is going
+        assert_eq!(run_line("nosuchcmd 2> err; echo ok", &mut ctx).unwrap(), "ok\n");
+        assert_eq!(file("/tmp/err", &ctx), "Command not found: nosuchcmd\n");
+        assert_eq!(run_line("{ echo out; nosuchcmd; } > both 2>&1", &mut ctx).unwrap_err(), "");
+        assert_eq!(file("/tmp/both", &ctx), "out\nCommand not found: nosuchcmd\n");
+    }
+
+    #[test]
+    fn test_source_and_eval_keep_stdout() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_command_registry(std::sync::Arc::new(CommandRegistry::default_commands()));
+        let file = |path: &str, ctx: &TerminalContext| String::from_utf8_lossy(ctx.vfs.read_file(path).unwrap()).to_string();
+        ctx.vfs.create_file("/tmp/s", b"echo hi; false".to_vec()).unwrap();
+        assert_eq!(run_line("source /tmp/s > /tmp/f", &mut ctx).unwrap_err(), "");
+        assert_eq!(file("/tmp/f", &ctx), "hi\n");
+        assert_eq!(run_line("eval 'echo hi; nosuchcmd' > /tmp/g", &mut ctx).unwrap_err(), "Command not found: nosuchcmd");
+        assert_eq!(file("/tmp/g", &ctx), "hi\n");
+        assert_eq!(run_line("x=$(source /tmp/s); echo $? $x", &mut ctx).unwrap(), "1 hi\n");
+
+        // exit's code is what $? says afterwards, return's straight away
+        ctx.vfs.create_file("/tmp/e", b"echo bye; exit 3; echo never".to_vec()).unwrap();
+        assert_eq!(run_line("source /tmp/e; echo after", &mut ctx).unwrap_err(), "bye\n");
+        assert_eq!(run_line("echo $?", &mut ctx).unwrap(), "3\n");
+        ctx.vfs.create_file("/tmp/r", b"return 4".to_vec()).unwrap();
+        assert_eq!(run_line("source /tmp/r; echo $?", &mut ctx).unwrap(), "4\n");
+        run_line("eval 'exit 5'", &mut ctx).unwrap_err();
+        assert_eq!(run_line("echo $?", &mut ctx).unwrap(), "5\n");
+    }
+
+    #[test]
+    fn test_closed_stderr_is_dropped() {
+        let mut ctx = TerminalContext::new();
+        assert_eq!(run_line("nosuchcmd 2>&-", &mut ctx).unwrap_err(), "");
+    }
+
+    #[test]
+    fn test_aliases() {
+        let mut ctx = TerminalContext::new();
+        run_line("alias say='echo said'", &mut ctx).unwrap();
+        assert_eq!(run_line("say hi | grep said", &mut ctx).unwrap(), "said hi");
+        // defined and used on the same line doesn't work, same as bash
+        assert!(run_line("alias yell='echo YELL'; yell", &mut ctx).is_err());
+        assert_eq!(run_line("yell", &mut ctx).unwrap(), "YELL\n");
+        run_line("alias echo='echo x'", &mut ctx).unwrap();
+        assert_eq!(run_line("echo y", &mut ctx).unwrap(), "x y\n");
+    }
+
+    #[test]
+    fn test_command_substitution() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_command_registry(std::sync::Arc::new(CommandRegistry::default_commands()));
+        assert_eq!(run_line("echo \"[$(echo a; echo b)]\"", &mut ctx).unwrap(), "[a\nb]\n");
+        // unquoted results get split, backticks work the same way
+        assert_eq!(run_line("for w in `echo 'x y'`; do echo -$w-; done", &mut ctx).unwrap(), "-x-\n-y-\n");
+        assert_eq!(run_line("echo $(echo $(echo nested))", &mut ctx).unwrap(), "nested\n");
+        assert_eq!(run_line("echo \"$(echo ')')\"", &mut ctx).unwrap(), ")\n");
+        // assignments take the status of the substitution
+        assert!(run_line("x=$(false)", &mut ctx).is_err());
+        assert_eq!(run_line("x=$(echo hi; true); echo $x", &mut ctx).unwrap(), "hi\n");
+        // stderr isn't captured
+        assert_eq!(run_line("x=$(nosuch)", &mut ctx).unwrap_err(), "Command not found: nosuch");
+        assert!(lexer::tokenize("echo $(oops").is_err());
+    }
+
+    #[test]
+    fn test_arithmetic_commands() {
+        let mut ctx = TerminalContext::new();
+        assert_eq!(run_line("for ((i = 0; i < 3; i++)); do echo $i; done", &mut ctx).unwrap(), "0\n1\n2\n");
+        let script = "n=0; while (( n < 5 )); do let n+=2 'm = n * 10'; done; echo $n $m $((n % 4))";
+        assert_eq!(run_line(script, &mut ctx).unwrap(), "6 60 2\n");
+        assert!(run_line("(( 0 ))", &mut ctx).is_err());
+        assert!(run_line("let 0", &mut ctx).is_err());
+        assert!(run_line("(( 2 > 1 )) && let 1", &mut ctx).is_ok());
+        assert!(run_line("for ((;;)); do break; done", &mut ctx).is_ok());
+    }
+
+    #[test]
+    fn test_conditionals() {
+        let mut ctx = TerminalContext::new();
+        ctx.vfs.create_file("/tmp/notes.txt", b"hi".to_vec()).unwrap();
+        ctx.cwd = "/tmp".to_string();
+        ctx.set_var("f", "notes.txt");
+        ctx.set_var("empty", "");
+        assert_eq!(run_line("if [ -f $f ] && test -d /tmp; then echo yes; fi", &mut ctx).unwrap(), "yes\n");
+        assert!(run_line("[ -z \"$empty\" ] && [ ! 2 -gt 3 ]", &mut ctx).is_ok());
+        assert_eq!(run_line("[ 1 = 1", &mut ctx).unwrap_err(), "[: missing `]'");
+        run_line("test 1 -eq x", &mut ctx).unwrap_err();
+        assert_eq!(ctx.last_status, 2);
+        // no splitting or globbing inside [[ ]], and an unquoted right side is a pattern
+        ctx.set_var("s", "a b");
+        assert!(run_line("[[ $s == a* && $empty == '' ]]", &mut ctx).is_ok());
+        assert!(run_line("[[ $s == 'a*' ]]", &mut ctx).is_err());
+        assert!(run_line("[[ ! -e missing || ( $f != *.txt ) ]]", &mut ctx).is_ok());
+        assert!(run_line("[[ abc < abd && 1+1 -eq 2 ]]", &mut ctx).is_ok());
+        assert!(run_line("[[ $f =~ ^no(tes|pe)\\.txt$ ]]", &mut ctx).is_ok());
+        assert!(run_line("[[ abc =~ a'.'c ]] || [[ abc =~ \"a.c\" ]]", &mut ctx).is_err());
+        run_line("[[ x =~ ( ]]", &mut ctx).unwrap_err();
+        assert_eq!(ctx.last_status, 2);
+    }
+
+    #[test]
+    fn test_scripting_builtins() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_command_registry(std::sync::Arc::new(CommandRegistry::default_commands()));
+        // each read takes the next line, the loop ends at end of input
+        let script = "while read -r name n; do printf '%s=%02d\\n' $name $n; done <<EOF\na 1\nb 2\nEOF";
+        assert_eq!(run_line(script, &mut ctx).unwrap(), "a=01\nb=02\n");
+        assert_eq!(run_line("{ read x; read y; cat; } <<EOF\n1\n2\n3\n4\nEOF\necho $x$y", &mut ctx).unwrap(), "3\n4\n12\n");
+        assert_eq!(run_line("echo hi | { read v; echo got $v; }", &mut ctx).unwrap(), "got hi\n");
+        run_line("set_args() { shift 2; echo $# $1; }", &mut ctx).unwrap();
+        assert_eq!(run_line("set_args a b c d", &mut ctx).unwrap(), "2 c\n");
+        assert!(run_line("shift", &mut ctx).is_err());
+        // exit ends the whole line, not just the function
+        run_line("leave() { exit 3; }", &mut ctx).unwrap();
+        assert_eq!(run_line("leave; echo unreachable", &mut ctx).unwrap_err(), "");
+        assert_eq!(ctx.last_status, 3);
+        assert_eq!(run_line("cmd='echo $((1 + 2))'; eval \"$cmd; v=set\"; echo $v", &mut ctx).unwrap(), "3\nset\n");
+        assert_eq!(run_line("for i in 1 2 3; do eval break; done; echo $i", &mut ctx).unwrap(), "1\n");
+        assert_eq!(run_line("exec echo replaced; echo not reached", &mut ctx).unwrap(), "replaced\n");
+    }
+
+    #[test]
+    fn test_heredocs() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_var("name", "vfs");
+        let script = "cat <<EOF\nhello $name\n  $((1 + 1)) \"q\"\nEOF\necho after";
+        assert_eq!(run_line(script, &mut ctx).unwrap(), "hello vfs\n  2 \"q\"\nafter\n");
+        assert_eq!(run_line("cat <<'EOF'\n$name\nEOF", &mut ctx).unwrap(), "$name\n");
+        assert_eq!(run_line("if true; then\n\tcat <<-X\n\t\tdeep\n\tX\nfi", &mut ctx).unwrap(), "deep\n");
+        assert_eq!(run_line("grep b <<< \"a $name b\"", &mut ctx).unwrap(), "a vfs b");
+    }
+
+    #[test]
+    fn test_functions() {
+        let mut ctx = TerminalContext::new();
+        run_line("greet() {\n  echo hello $1\n}", &mut ctx).unwrap();
+        assert_eq!(ctx.get_function("greet").unwrap(), "echo hello $1");
+        ctx.set_args(vec!["outer".to_string()]);
+        assert_eq!(run_line("greet world; echo $1", &mut ctx).unwrap(), "hello world\nouter\n");
+        // a function beats a command of the same name, and pipes like one
+        run_line("echo() { :; }", &mut ctx).unwrap();
+        assert_eq!(run_line("echo hi", &mut ctx).unwrap(), "");
+        ctx.functions.remove("echo");
+        assert_eq!(run_line("up() { grep b; }; echo 'a\nb' | up", &mut ctx).unwrap(), "b");
+    }
+
+    #[test]
+    fn test_local_and_return() {
+        let mut ctx = TerminalContext::new();
+        let script = "x=global; f() { local x=inner y; x=changed; echo $x; }; f; echo $x $y";
+        assert_eq!(run_line(script, &mut ctx).unwrap(), "changed\nglobal\n");
+        assert!(ctx.get_var("y").is_none());
+        // return leaves loops inside the function too
+        let script = "f() { for i in 1 2 3; do return 3; done; echo unreached; }; f";
+        assert_eq!(run_line(script, &mut ctx).unwrap_err(), "");
+        assert_eq!(ctx.last_status, 3);
+        assert_eq!(run_line("g() { false; return; }; g || echo failed", &mut ctx).unwrap(), "failed\n");
+        assert!(run_line("return", &mut ctx).unwrap_err().contains("can only `return'"));
+        assert!(run_line("local x", &mut ctx).unwrap_err().contains("only be used in a function"));
+        assert!(run_line("r() { r; }; r", &mut ctx).unwrap_err().contains("nesting level"));
+    }
+
+    #[test]
+    fn test_executable_scripts() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_command_registry(std::sync::Arc::new(CommandRegistry::default_commands()));
+        let script = b"#!/bin/sh\necho \"$0 got $# args: $*\"\ncd /tmp; x=set; exit 3\necho unreached".to_vec();
+        ctx.vfs.create_file("/bin/greet", script).unwrap();
+        assert_eq!(run_line("greet a b", &mut ctx).unwrap_err(), "Command not found: greet");
+        run_line("chmod +x /bin/greet", &mut ctx).unwrap();
+        assert_eq!(run_line("x=mine; greet a b", &mut ctx).unwrap_err(), "/bin/greet got 2 args: a b\n");
+        assert_eq!(ctx.last_status, 3);
+        // a child shell - none of its changes come back
+        assert_eq!((ctx.cwd.as_str(), ctx.get_var("x").map(String::as_str)), ("/", Some("mine")));
+        assert!(run_line("cd /bin && ./greet", &mut ctx).unwrap_err().starts_with("/bin/greet got 0"));
+        assert!(run_line("nosuch/greet", &mut ctx).unwrap_err().contains("No such file"));
+        run_line("chmod a-x greet", &mut ctx).unwrap();
+        assert!(run_line("./greet", &mut ctx).unwrap_err().contains("Permission denied"));
+        assert_eq!(ctx.last_status, 126);
+    }
+
+    #[test]
+    fn test_shebang_interpreters() {
+        let mut ctx = TerminalContext::new();
+        ctx.vfs.create_file("/tmp/add.asm", b"#!/bin/cpu\npush 2\npush 3\nadd\nprint\nhalt\n".to_vec()).unwrap();
+        ctx.vfs.create_file("/tmp/up", b"#!/usr/bin/env sed s/c/C/\nabc\n".to_vec()).unwrap();
+        ctx.vfs.create_file("/tmp/odd", b"#!/bin/nope\n".to_vec()).unwrap();
+        run_line("chmod 755 /tmp/add.asm /tmp/up /tmp/odd", &mut ctx).unwrap();
+        ctx.set_var("PATH", "/bin:/tmp");
+        assert!(run_line("add.asm", &mut ctx).unwrap().contains('5'));
+        assert!(run_line("up", &mut ctx).unwrap().ends_with("\nabC\n"));
+        assert!(run_line("odd", &mut ctx).unwrap_err().contains("bad interpreter"));
+    }
+}
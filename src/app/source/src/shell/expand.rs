@@ -0,0 +1,481 @@
+use super::lexer::{ParamExpr, ParamOp, Word, WordPart};
+use super::{arith, brace, glob, pattern};
+use crate::context::{TerminalContext, SHELL_PID};
+
+/// one finished argument, still remembering which bits were quoted so
+/// pattern matching (and later globbing) can tell `*` from `'*'`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Field {
+    pub segments: Vec<(String, bool)>, // (text, quoted)
+}
+
+impl Field {
+    fn push(&mut self, text: &str, quoted: bool) {
+        match self.segments.last_mut() {
+            Some((last, q)) if *q == quoted => last.push_str(text),
+            _ => self.segments.push((text.to_string(), quoted)),
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.segments.iter().map(|(text, _)| text.as_str()).collect()
+    }
+
+    // the field as a glob pattern - quoted bits are escaped so they only
+    // ever match themselves
+    fn pattern(&self) -> String {
+        self.segments
+            .iter()
+            .map(|(text, quoted)| if *quoted { pattern::escape(text) } else { text.clone() })
+            .collect()
+    }
+}
+
+// collects fields as parts get expanded, handling the splitting
+#[derive(Default)]
+struct Builder {
+    fields: Vec<Field>,
+    current: Field,
+    started: bool, // "" has to give an empty field rather than nothing
+}
+
+impl Builder {
+    fn push(&mut self, text: &str, quoted: bool) {
+        self.current.push(text, quoted);
+        self.started = true;
+    }
+
+    fn finish_field(&mut self) {
+        if self.started {
+            self.fields.push(std::mem::take(&mut self.current));
+            self.started = false;
+        }
+    }
+
+    // unquoted expansion result - split it on IFS
+    fn push_split(&mut self, text: &str, ifs: &str) {
+        for c in text.chars() {
+            if !ifs.contains(c) {
+                self.current.push(&c.to_string(), false);
+                self.started = true;
+            } else if c.is_whitespace() {
+                self.finish_field();
+            } else {
+                // non whitespace separators always delimit, even empty fields
+                self.started = true;
+                self.finish_field();
+            }
+        }
+    }
+}
+
+fn ifs(ctx: &TerminalContext) -> String {
+    ctx.lookup_var("IFS").cloned().unwrap_or_else(|| " \t\n".to_string())
+}
+
+/// expand a word into zero or more fields - parameters are substituted
+/// and unquoted results are split on IFS
+pub fn expand_fields(word: &Word, ctx: &mut TerminalContext) -> Result<Vec<Field>, String> {
+    let ifs = ifs(ctx);
+    let mut b = Builder::default();
+    for part in &word.parts {
+        match part {
+            WordPart::Literal(s) => b.push(s, false),
+            WordPart::Quoted(s) => b.push(s, true),
+            WordPart::DoubleQuoted(inner) => {
+                let was_started = b.started;
+                b.started = true;
+                for part in inner {
+                    match part {
+                        // "$@" keeps each arg as its own field
+                        WordPart::Param(p) if p.name == "@" && p.op == ParamOp::Plain => {
+                            for (i, arg) in ctx.args.iter().enumerate() {
+                                if i > 0 {
+                                    b.finish_field();
+                                }
+                                b.push(arg, true);
+                            }
+                            // a lone "$@" with no args is no field at all
+                            if ctx.args.is_empty() && inner.len() == 1 {
+                                b.started = was_started;
+                            }
+                        }
+                        WordPart::Param(p) => {
+                            let value = expand_param(p, ctx, true)?;
+                            b.push(&value, true);
+                        }
+                        other => b.push(&word_text(other, ctx)?, true),
+                    }
+                }
+            }
+            WordPart::Param(p) => {
+                let value = expand_param(p, ctx, false)?;
+                b.push_split(&value, &ifs);
+            }
+            WordPart::Command(script) => {
+                let value = super::exec::substitute(script, ctx)?;
+                b.push_split(&value, &ifs);
+            }
+            WordPart::Arith(expr) => {
+                let value = arithmetic(expr, ctx)?;
+                b.push_split(&value, &ifs);
+            }
+        }
+    }
+    b.finish_field();
+    Ok(b.fields)
+}
+
+/// expand a word into plain argument strings, globbing any field with
+/// unquoted wildcards in it against the vfs
+pub fn expand_word(word: &Word, ctx: &mut TerminalContext) -> Result<Vec<String>, String> {
+    let mut out = Vec::new();
+    let mut fields = Vec::new();
+    for word in brace::expand(word) {
+        let word = expand_tilde(&word, ctx, false);
+        fields.extend(expand_fields(&word, ctx)?);
+    }
+    for field in fields {
+        let pat = field.pattern();
+        if pattern::literal(&pat).is_some() {
+            out.push(field.text());
+            continue;
+        }
+        let paths = glob::expand(&pat, ctx);
+        if !paths.is_empty() {
+            out.extend(paths);
+        } else if ctx.options.failglob {
+            return Err(format!("no match: {}", field.text()));
+        } else if !ctx.options.nullglob {
+            // no match leaves the pattern as it was, like sh
+            out.push(field.text());
+        }
+    }
+    Ok(out)
+}
+
+/// expand without any splitting, for assignments and the like
+pub fn expand_string(word: &Word, ctx: &mut TerminalContext) -> Result<String, String> {
+    let mut out = String::new();
+    for part in &word.parts {
+        out.push_str(&word_text(part, ctx)?);
+    }
+    Ok(out)
+}
+
+/// the value of a `NAME=value` - no splitting, and a ~ works after any
+/// `:` as well as at the start, so PATH=~/bin:~/tools does what you'd think
+pub fn expand_assignment(word: &Word, ctx: &mut TerminalContext) -> Result<String, String> {
+    let word = expand_tilde(word, ctx, true);
+    expand_string(&word, ctx)
+}
+
+// swap an unquoted ~ at the start of a word (or after a : in an assignment)
+// for the directory it means. the bit up to the next / has to be
+// unquoted, so `~"x"` and `~$USER` are left alone like in bash
+fn expand_tilde(word: &Word, ctx: &TerminalContext, assignment: bool) -> Word {
+    let mut parts = Vec::new();
+    for (idx, part) in word.parts.iter().enumerate() {
+        let WordPart::Literal(text) = part else {
+            parts.push(part.clone());
+            continue;
+        };
+        let chars: Vec<char> = text.chars().collect();
+        let last = idx + 1 == word.parts.len();
+        let mut literal = String::new();
+        let mut at_start = idx == 0;
+        let mut i = 0;
+        while i < chars.len() {
+            if at_start && chars[i] == '~' {
+                let end = (i + 1..chars.len())
+                    .find(|&j| chars[j] == '/' || (assignment && chars[j] == ':'))
+                    .unwrap_or(chars.len());
+                let user: String = chars[i + 1..end].iter().collect();
+                let whole = end < chars.len() || last;
+                if let Some(dir) = whole.then(|| home_dir(&user, ctx)).flatten() {
+                    if !literal.is_empty() {
+                        parts.push(WordPart::Literal(std::mem::take(&mut literal)));
+                    }
+                    // quoted so a home with spaces in it isn't split or globbed
+                    parts.push(WordPart::Quoted(dir));
+                    i = end;
+                    at_start = false;
+                    continue;
+                }
+            }
+            literal.push(chars[i]);
+            at_start = assignment && chars[i] == ':';
+            i += 1;
+        }
+        if !literal.is_empty() {
+            parts.push(WordPart::Literal(literal));
+        }
+    }
+    Word { parts }
+}
+
+// ~ is $HOME, ~+ and ~- are $PWD and $OLDPWD, ~name is that user's home
+fn home_dir(user: &str, ctx: &TerminalContext) -> Option<String> {
+    match user {
+        "" => ctx.lookup_var("HOME").cloned(),
+        "+" => Some(ctx.cwd.clone()),
+        "-" => ctx.lookup_var("OLDPWD").cloned(),
+        name if ctx.lookup_var("USER").is_some_and(|u| u == name) => ctx.lookup_var("HOME").cloned(),
+        name => {
+            // everyone else lives under /home, if they exist at all
+            let dir = format!("/home/{}", name);
+            ctx.vfs.resolve_path(&dir).is_some().then_some(dir)
+        }
+    }
+}
+
+/// a word used as a pattern (${x#pat}, case arms) - quoted bits
+/// can't be wildcards
+pub fn expand_pattern(word: &Word, ctx: &mut TerminalContext) -> Result<String, String> {
+    let mut out = String::new();
+    for part in &word.parts {
+        match part {
+            WordPart::Literal(s) => out.push_str(s),
+            other => out.push_str(&pattern::escape(&word_text(other, ctx)?)),
+        }
+    }
+    Ok(out)
+}
+
+/// the right of `[[ x =~ re ]]` - like a pattern, quoting makes things
+/// match literally
+pub fn expand_regex(word: &Word, ctx: &mut TerminalContext) -> Result<String, String> {
+    let mut out = String::new();
+    for part in &word.parts {
+        match part {
+            WordPart::Literal(s) => out.push_str(s),
+            WordPart::Param(_) | WordPart::Command(_) | WordPart::Arith(_) => out.push_str(&word_text(part, ctx)?),
+            other => out.push_str(&regex::escape(&word_text(other, ctx)?)),
+        }
+    }
+    Ok(out)
+}
+
+fn word_text(part: &WordPart, ctx: &mut TerminalContext) -> Result<String, String> {
+    Ok(match part {
+        WordPart::Literal(s) | WordPart::Quoted(s) => s.clone(),
+        WordPart::DoubleQuoted(inner) => {
+            let mut out = String::new();
+            for part in inner {
+                out.push_str(&word_text(part, ctx)?);
+            }
+            out
+        }
+        WordPart::Param(p) => expand_param(p, ctx, true)?,
+        WordPart::Command(script) => super::exec::substitute(script, ctx)?,
+        WordPart::Arith(expr) => arithmetic(expr, ctx)?,
+    })
+}
+
+/// $(( expr )) - the expression gets the usual $x / $(...) expansion
+/// first, then it's evaluated
+pub fn arithmetic(expr: &Word, ctx: &mut TerminalContext) -> Result<String, String> {
+    let text = expand_string(expr, ctx)?;
+    Ok(arith::eval(&text, ctx)?.to_string())
+}
+
+// current value of a parameter, None when unset
+fn lookup(name: &str, ctx: &TerminalContext, quoted: bool) -> Option<String> {
+    match name {
+        "?" => Some(ctx.last_status.to_string()),
+        "#" => Some(ctx.args.len().to_string()),
+        "$" => Some(SHELL_PID.to_string()),
+        // unset until there's been a job
+        "!" => ctx.processes.last_background().map(|pid| pid.to_string()),
+        "@" | "*" => {
+            // "$*" joins with the first char of IFS, everything else with a space
+            let sep = if quoted && name == "*" { ifs(ctx).chars().next().map(String::from).unwrap_or_default() } else { " ".to_string() };
+            Some(ctx.args.join(&sep))
+        }
+        "-" => {
+            let mut flags = String::new();
+            if ctx.options.errexit { flags.push('e'); }
+            if ctx.options.xtrace { flags.push('x'); }
+            Some(flags)
+        }
+        n if n.chars().all(|c| c.is_ascii_digit()) => {
+            let idx: usize = n.parse().ok()?;
+            match idx.checked_sub(1) {
+                Some(i) => ctx.get_arg(i).cloned(),
+                // ${0}, ${00} and friends all mean $0
+                None => Some(ctx.script.clone().unwrap_or_else(|| "sh".to_string())),
+            }
+        }
+        n => ctx.lookup_var(n).cloned(),
+    }
+}
+
+fn expand_param(p: &ParamExpr, ctx: &mut TerminalContext, quoted: bool) -> Result<String, String> {
+    let value = lookup(&p.name, ctx, quoted);
+    // the colon forms treat empty the same as unset
+    let missing = |colon: bool| match &value {
+        None => true,
+        Some(v) => colon && v.is_empty(),
+    };
+    match &p.op {
+        ParamOp::Plain => Ok(value.unwrap_or_default()),
+        ParamOp::Length => Ok(match p.name.as_str() {
+            "@" | "*" => ctx.args.len().to_string(),
+            _ => value.unwrap_or_default().chars().count().to_string(),
+        }),
+        ParamOp::Default { colon, word } => {
+            if missing(*colon) { expand_string(word, ctx) } else { Ok(value.unwrap_or_default()) }
+        }
+        ParamOp::Assign { colon, word } => {
+            if !missing(*colon) {
+                return Ok(value.unwrap_or_default());
+            }
+            if !p.name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                return Err(format!("${}: cannot assign in this way", p.name));
+            }
+            let new_value = expand_string(word, ctx)?;
+            ctx.assign_var(&p.name, &new_value);
+            Ok(new_value)
+        }
+        ParamOp::Alternate { colon, word } => {
+            if missing(*colon) { Ok(String::new()) } else { expand_string(word, ctx) }
+        }
+        ParamOp::Error { colon, word } => {
+            if !missing(*colon) {
+                return Ok(value.unwrap_or_default());
+            }
+            let message = expand_string(word, ctx)?;
+            if message.is_empty() {
+                Err(format!("{}: parameter null or not set", p.name))
+            } else {
+                Err(format!("{}: {}", p.name, message))
+            }
+        }
+        ParamOp::TrimPrefix { longest, pattern } => {
+            let pat = expand_pattern(pattern, ctx)?;
+            Ok(pattern::trim_prefix(&value.unwrap_or_default(), &pat, *longest))
+        }
+        ParamOp::TrimSuffix { longest, pattern } => {
+            let pat = expand_pattern(pattern, ctx)?;
+            Ok(pattern::trim_suffix(&value.unwrap_or_default(), &pat, *longest))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::lexer::{tokenize, Token};
+
+    fn expand(input: &str, ctx: &mut TerminalContext) -> Result<Vec<String>, String> {
+        let mut out = Vec::new();
+        for token in tokenize(input)?.0 {
+            if let Token::Word(word) = token {
+                out.extend(expand_word(&word, ctx)?);
+            }
+        }
+        Ok(out)
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_var("name", "world");
+        assert_eq!(expand("hello $name ${name}s", &mut ctx).unwrap(), vec!["hello", "world", "worlds"]);
+        assert_eq!(expand("$HOME '$HOME'", &mut ctx).unwrap(), vec!["/home", "$HOME"]);
+        // unset and unquoted disappears, quoted stays as an empty arg
+        assert_eq!(expand("a $nothing \"$nothing\"", &mut ctx).unwrap(), vec!["a", ""]);
+    }
+
+    #[test]
+    fn test_field_splitting() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_var("list", "  one two   three ");
+        assert_eq!(expand("$list", &mut ctx).unwrap(), vec!["one", "two", "three"]);
+        assert_eq!(expand("\"$list\"", &mut ctx).unwrap(), vec!["  one two   three "]);
+        ctx.set_var("IFS", ":");
+        ctx.set_var("path", "a::b");
+        assert_eq!(expand("$path", &mut ctx).unwrap(), vec!["a", "", "b"]);
+    }
+
+    #[test]
+    fn test_positional_and_special() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_args(vec!["a b".to_string(), "c".to_string()]);
+        ctx.last_status = 3;
+        assert_eq!(expand("$# $1 $2 $3 $?", &mut ctx).unwrap(), vec!["2", "a", "b", "c", "3"]);
+        assert_eq!(expand("\"$@\"", &mut ctx).unwrap(), vec!["a b", "c"]);
+        assert_eq!(expand("\"$*\"", &mut ctx).unwrap(), vec!["a b c"]);
+        assert_eq!(expand("x\"$@\"y", &mut ctx).unwrap(), vec!["xa b", "cy"]);
+        ctx.set_args(Vec::new());
+        assert!(expand("\"$@\"", &mut ctx).unwrap().is_empty());
+        // leading zeros still mean $0, never an argument before $1
+        assert_eq!(expand("$0 ${0} ${00} ${01}", &mut ctx).unwrap(), vec!["sh", "sh", "sh"]);
+    }
+
+    #[test]
+    fn test_brace_and_tilde() {
+        let mut ctx = TerminalContext::new();
+        ctx.vfs.create_dir("/home/alice").unwrap();
+        ctx.set_var("x", "1");
+        assert_eq!(expand("mkdir -p p/{src,tests}", &mut ctx).unwrap(), vec!["mkdir", "-p", "p/src", "p/tests"]);
+        assert_eq!(expand("f{1..2}.$x \"{a,b}\"", &mut ctx).unwrap(), vec!["f1.1", "f2.1", "{a,b}"]);
+        assert_eq!(expand("~ ~/bin ~alice ~bob ~+ '~' a~", &mut ctx).unwrap(), vec!["/home", "/home/bin", "/home/alice", "~bob", "/", "~", "a~"]);
+        assert_eq!(expand("~/{a,b}", &mut ctx).unwrap(), vec!["/home/a", "/home/b"]);
+        // only assignments look after colons
+        let word = crate::shell::lexer::lex_word("~/bin:~/tools").unwrap();
+        assert_eq!(expand_assignment(&word, &mut ctx).unwrap(), "/home/bin:/home/tools");
+        assert_eq!(expand("~/bin:~/tools", &mut ctx).unwrap(), vec!["/home/bin:~/tools"]);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_var("n", "4");
+        assert_eq!(expand("$((n * 2)) $(($n + 1))x \"$(( (n > 3) ? 10 : 20 ))\"", &mut ctx).unwrap(), vec!["8", "5x", "10"]);
+        assert_eq!(expand("$((n += 1)) $n", &mut ctx).unwrap(), vec!["5", "5"]);
+        assert!(expand("$((1 / 0))", &mut ctx).unwrap_err().contains("division by 0"));
+    }
+
+    #[test]
+    fn test_globbing() {
+        let mut ctx = TerminalContext::new();
+        ctx.vfs.create_file("/tmp/a.log", Vec::new()).unwrap();
+        ctx.vfs.create_file("/tmp/b.log", Vec::new()).unwrap();
+        ctx.cwd = "/tmp".to_string();
+        assert_eq!(expand("rm *.log", &mut ctx).unwrap(), vec!["rm", "a.log", "b.log"]);
+        assert_eq!(expand("'*.log' \\*.log", &mut ctx).unwrap(), vec!["*.log", "*.log"]);
+        ctx.set_var("pat", "?.log");
+        assert_eq!(expand("$pat \"$pat\"", &mut ctx).unwrap(), vec!["a.log", "b.log", "?.log"]);
+        // what happens to a pattern that matches nothing
+        assert_eq!(expand("*.txt", &mut ctx).unwrap(), vec!["*.txt"]);
+        ctx.options.nullglob = true;
+        assert!(expand("*.txt", &mut ctx).unwrap().is_empty());
+        ctx.options.failglob = true;
+        assert_eq!(expand("*.txt", &mut ctx).unwrap_err(), "no match: *.txt");
+    }
+
+    #[test]
+    fn test_default_alternate_and_error() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_var("empty", "");
+        assert_eq!(expand("${unset:-dflt} ${empty:-dflt} ${empty-dflt}x", &mut ctx).unwrap(), vec!["dflt", "dflt", "x"]);
+        assert_eq!(expand("${HOME:+set} ${unset:+set}x", &mut ctx).unwrap(), vec!["set", "x"]);
+        assert_eq!(expand("${new:=value} $new", &mut ctx).unwrap(), vec!["value", "value"]);
+        assert_eq!(ctx.get_var("new").unwrap(), "value");
+        assert_eq!(expand("${unset:?is required}", &mut ctx).unwrap_err(), "unset: is required");
+        assert!(expand("${1:=x}", &mut ctx).is_err());
+    }
+
+    #[test]
+    fn test_length_and_trimming() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_var("file", "/home/user/notes.tar.gz");
+        assert_eq!(expand("${#file}", &mut ctx).unwrap(), vec!["23"]);
+        assert_eq!(expand("${file##*/} ${file%%.*} ${file%.*}", &mut ctx).unwrap(),
+            vec!["notes.tar.gz", "/home/user/notes", "/home/user/notes.tar"]);
+        // quoted wildcards in the pattern are literal
+        ctx.set_var("star", "*x*");
+        assert_eq!(expand("${star#'*'} ${star#*}", &mut ctx).unwrap(), vec!["x*", "*x*"]);
+    }
+}
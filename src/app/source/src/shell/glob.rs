@@ -0,0 +1,165 @@
+// pathname expansion - turn `*.txt` or `src/**/*.asm` into the matching
+// paths in the vfs. patterns come from expand::Field::pattern, so quoted
+// wildcards are already backslash escaped
+
+use super::pattern;
+use crate::context::TerminalContext;
+use crate::vfs::VfsNode;
+
+/// every path matching `pat`, sorted. relative patterns give relative
+/// results, absolute ones absolute. empty when nothing matches
+pub fn expand(pat: &str, ctx: &TerminalContext) -> Vec<String> {
+    let absolute = pat.starts_with('/');
+    // `*/` only matches directories, and keeps the slash
+    let dirs_only = pat.ends_with('/');
+    let components: Vec<&str> = pat.split('/').filter(|c| !c.is_empty()).collect();
+    let start = Walk {
+        shown: if absolute { "/".to_string() } else { String::new() },
+        real: if absolute { "/".to_string() } else { ctx.cwd.clone() },
+    };
+    let mut found = Vec::new();
+    start.walk(&components, dirs_only, ctx, &mut found);
+    found.sort();
+    found.dedup();
+    found
+}
+
+// where the walk has got to - the path as the user will see it, and the
+// absolute vfs path it points at
+struct Walk {
+    shown: String,
+    real: String,
+}
+
+impl Walk {
+    fn child(&self, name: &str) -> Walk {
+        let shown = if self.shown.is_empty() || self.shown.ends_with('/') {
+            format!("{}{}", self.shown, name)
+        } else {
+            format!("{}/{}", self.shown, name)
+        };
+        let real = match name {
+            "." => self.real.clone(),
+            ".." => match self.real.trim_end_matches('/').rsplit_once('/') {
+                Some((parent, _)) if !parent.is_empty() => parent.to_string(),
+                _ => "/".to_string(),
+            },
+            _ if self.real.ends_with('/') => format!("{}{}", self.real, name),
+            _ => format!("{}/{}", self.real, name),
+        };
+        Walk { shown, real }
+    }
+
+    // follows symlinks, so a link to a directory can be walked into
+    fn node<'a>(&self, ctx: &'a TerminalContext) -> Option<&'a VfsNode> {
+        ctx.vfs.resolve_path_with_symlinks(&self.real, false)
+    }
+
+    fn is_dir(&self, ctx: &TerminalContext) -> bool {
+        matches!(self.node(ctx), Some(VfsNode::Directory { .. }))
+    }
+
+    // sorted names in this directory, hidden ones left out unless asked for
+    fn entries(&self, ctx: &TerminalContext, hidden: bool) -> Vec<String> {
+        let mut names: Vec<String> = match self.node(ctx) {
+            Some(VfsNode::Directory { children, .. }) => children.keys().filter(|n| hidden || !n.starts_with('.')).cloned().collect(),
+            _ => Vec::new(),
+        };
+        names.sort();
+        names
+    }
+
+    fn walk(&self, components: &[&str], dirs_only: bool, ctx: &TerminalContext, found: &mut Vec<String>) {
+        let Some((first, rest)) = components.split_first() else {
+            if dirs_only {
+                if self.is_dir(ctx) {
+                    found.push(format!("{}/", self.shown.trim_end_matches('/')));
+                }
+            } else if !self.shown.is_empty() {
+                found.push(self.shown.clone());
+            }
+            return;
+        };
+
+        if *first == "**" {
+            // any number of directories, including none. hidden ones are skipped
+            self.walk(rest, dirs_only, ctx, found);
+            for name in self.entries(ctx, false) {
+                let next = self.child(&name);
+                if next.is_dir(ctx) {
+                    next.walk(components, dirs_only, ctx, found);
+                } else if rest.is_empty() && !dirs_only {
+                    found.push(next.shown);
+                }
+            }
+            return;
+        }
+
+        // nothing to match, just check the name is there
+        if let Some(name) = pattern::literal(first) {
+            let next = self.child(&name);
+            if (rest.is_empty() && next.node(ctx).is_some()) || next.is_dir(ctx) {
+                next.walk(rest, dirs_only, ctx, found);
+            }
+            return;
+        }
+
+        // dotfiles only match when the pattern itself starts with a dot
+        let hidden = first.starts_with('.') || first.starts_with("\\.");
+        for name in self.entries(ctx, hidden) {
+            if !pattern::matches(first, &name) {
+                continue;
+            }
+            let next = self.child(&name);
+            if rest.is_empty() || next.is_dir(ctx) {
+                next.walk(rest, dirs_only, ctx, found);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> TerminalContext {
+        let mut ctx = TerminalContext::new();
+        for dir in ["/proj", "/proj/src", "/proj/src/cpu", "/proj/.git"] {
+            ctx.vfs.create_dir(dir).unwrap();
+        }
+        for file in ["/proj/a.txt", "/proj/b.txt", "/proj/c.md", "/proj/.hidden.txt", "/proj/src/main.asm", "/proj/src/cpu/alu.asm", "/proj/.git/x.asm"] {
+            ctx.vfs.create_file(file, Vec::new()).unwrap();
+        }
+        ctx.cwd = "/proj".to_string();
+        ctx
+    }
+
+    #[test]
+    fn test_basic_wildcards() {
+        let ctx = setup();
+        assert_eq!(expand("*.txt", &ctx), vec!["a.txt", "b.txt"]);
+        assert_eq!(expand("?.md", &ctx), vec!["c.md"]);
+        assert_eq!(expand("[ac].*", &ctx), vec!["a.txt", "c.md"]);
+        assert_eq!(expand("/proj/*.md", &ctx), vec!["/proj/c.md"]);
+        assert_eq!(expand("src/*", &ctx), vec!["src/cpu", "src/main.asm"]);
+        assert_eq!(expand("*/", &ctx), vec!["src/"]);
+        assert!(expand("*.nope", &ctx).is_empty());
+        // quoted wildcards come in escaped
+        assert!(expand("\\*.txt", &ctx).is_empty());
+    }
+
+    #[test]
+    fn test_hidden_files() {
+        let ctx = setup();
+        assert!(!expand("*", &ctx).contains(&".hidden.txt".to_string()));
+        assert_eq!(expand(".*.txt", &ctx), vec![".hidden.txt"]);
+    }
+
+    #[test]
+    fn test_globstar() {
+        let ctx = setup();
+        assert_eq!(expand("**/*.asm", &ctx), vec!["src/cpu/alu.asm", "src/main.asm"]);
+        assert_eq!(expand("src/**/*.asm", &ctx), vec!["src/cpu/alu.asm", "src/main.asm"]);
+        assert_eq!(expand("../proj/src/*.asm", &ctx), vec!["../proj/src/main.asm"]);
+    }
+}
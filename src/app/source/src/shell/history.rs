@@ -0,0 +1,141 @@
+// `!!`, `!n`, `!prefix` and `^old^new` - bash's history expansion, done on
+// the raw line typed at the prompt before anything else sees it
+
+/// expand history references in a line typed at the prompt. None means
+/// there weren't any, so the caller knows whether to echo the new line
+pub fn expand(line: &str, history: &[String]) -> Result<Option<String>, String> {
+    if let Some(rest) = line.strip_prefix('^') {
+        return quick_substitution(rest, history).map(Some);
+    }
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::new();
+    let mut changed = false;
+    let (mut single, mut double) = (false, false);
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            // the backslash stays, the lexer takes it off again later
+            '\\' if !single => {
+                out.push(c);
+                if let Some(&next) = chars.get(i + 1) {
+                    out.push(next);
+                    i += 1;
+                }
+            }
+            '\'' if !double => {
+                single = !single;
+                out.push(c);
+            }
+            '"' if !single => {
+                double = !double;
+                out.push(c);
+            }
+            // $! and ${!x} aren't history
+            '!' if !single && !after_dollar(&chars[..i]) => match event(&chars[i + 1..], history)? {
+                Some((text, used)) => {
+                    out.push_str(&text);
+                    i += used;
+                    changed = true;
+                }
+                None => out.push(c),
+            },
+            c => out.push(c),
+        }
+        i += 1;
+    }
+    Ok(changed.then_some(out))
+}
+
+fn after_dollar(before: &[char]) -> bool {
+    matches!(before, [.., '$'] | [.., '$', '{'])
+}
+
+// whatever follows a ! - the text it stands for and how many chars that
+// took up. None when the ! is just a ! (`!=`, `[[ ! -e x ]]`...)
+fn event(rest: &[char], history: &[String]) -> Result<Option<(String, usize)>, String> {
+    let not_found = |spec: &[char]| format!("!{}: event not found", spec.iter().collect::<String>());
+    match rest.first() {
+        None | Some(' ' | '\t' | '\n' | '=' | '(' | '"') => Ok(None),
+        Some('!') => history.last().map(|h| Some((h.clone(), 1))).ok_or_else(|| not_found(&rest[..1])),
+        // !$ - the last word of the last line
+        Some('$') => history
+            .last()
+            .and_then(|h| h.split_whitespace().last())
+            .map(|word| Some((word.to_string(), 1)))
+            .ok_or_else(|| not_found(&rest[..1])),
+        // !n counts from the start, !-n back from the end
+        Some(c) if c.is_ascii_digit() || *c == '-' => {
+            let len = 1 + rest[1..].iter().take_while(|c| c.is_ascii_digit()).count();
+            let n: i64 = rest[..len].iter().collect::<String>().parse().map_err(|_| not_found(&rest[..len]))?;
+            let idx = if n > 0 { n - 1 } else { history.len() as i64 + n };
+            usize::try_from(idx)
+                .ok()
+                .and_then(|idx| history.get(idx))
+                .map(|h| Some((h.clone(), len)))
+                .ok_or_else(|| not_found(&rest[..len]))
+        }
+        // !prefix - the latest line starting with it
+        _ => {
+            let len = rest.iter().take_while(|c| !c.is_whitespace() && !matches!(c, ';' | '&' | '|' | '"' | '\'' | ')')).count();
+            let prefix: String = rest[..len].iter().collect();
+            history
+                .iter()
+                .rev()
+                .find(|h| h.starts_with(&prefix))
+                .map(|h| Some((h.clone(), len)))
+                .ok_or_else(|| not_found(&rest[..len]))
+        }
+    }
+}
+
+// ^old^new^ - the last line with the first `old` swapped for `new`
+fn quick_substitution(rest: &str, history: &[String]) -> Result<String, String> {
+    let mut parts = rest.splitn(3, '^');
+    let old = parts.next().unwrap_or_default();
+    let new = parts.next().unwrap_or_default();
+    let last = history.last().ok_or("^: event not found")?;
+    if old.is_empty() || !last.contains(old) {
+        return Err(format!("^{}^{}: substitution failed", old, new));
+    }
+    Ok(last.replacen(old, new, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> Vec<String> {
+        ["ls -l /tmp", "echo hello world", "cat notes.txt"].iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_events() {
+        let h = history();
+        assert_eq!(expand("sudo !!", &h).unwrap().unwrap(), "sudo cat notes.txt");
+        assert_eq!(expand("!1 && !-2", &h).unwrap().unwrap(), "ls -l /tmp && echo hello world");
+        assert_eq!(expand("!ec | wc", &h).unwrap().unwrap(), "echo hello world | wc");
+        assert_eq!(expand("vi !$", &h).unwrap().unwrap(), "vi notes.txt");
+        assert_eq!(expand("!nope", &h).unwrap_err(), "!nope: event not found");
+        assert_eq!(expand("!9", &h).unwrap_err(), "!9: event not found");
+    }
+
+    #[test]
+    fn test_plain_bangs_are_left_alone() {
+        let h = history();
+        for line in ["[ a != b ]", "[[ ! -e x ]]", "echo '!!' \\!!", "echo $! ${!name}", "echo hi!"] {
+            assert_eq!(expand(line, &h).unwrap(), None, "{}", line);
+        }
+        // double quotes don't stop it, same as bash
+        assert_eq!(expand("echo \"!!\"", &h).unwrap().unwrap(), "echo \"cat notes.txt\"");
+    }
+
+    #[test]
+    fn test_quick_substitution() {
+        let h = history();
+        assert_eq!(expand("^notes^todo", &h).unwrap().unwrap(), "cat todo.txt");
+        assert_eq!(expand("^txt^md^", &h).unwrap().unwrap(), "cat notes.md");
+        assert!(expand("^zzz^y", &h).is_err());
+        assert!(expand("^a^b", &[]).is_err());
+    }
+}
@@ -0,0 +1,838 @@
+/// one piece of a shell word, remembering how it was quoted
+/// later stages (expansion, globbing) care about the difference
+#[derive(Debug, Clone, PartialEq)]
+pub enum WordPart {
+    Literal(String),             // unquoted text
+    Quoted(String),              // single quoted or backslash escaped, never expanded
+    DoubleQuoted(Vec<WordPart>), // "..." - expanded but never split
+    Param(ParamExpr),            // $name, ${name:-word} and friends
+    Command(String),             // $(cmd) or `cmd` - the script inside, run at expansion time
+    Arith(Word),                 // $(( expr )) - expanded, then evaluated as arithmetic
+}
+
+/// what to do with a parameter once it's been looked up
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamOp {
+    Plain,                                       // $x ${x}
+    Length,                                      // ${#x}
+    Default { colon: bool, word: Word },         // ${x:-w} ${x-w}
+    Assign { colon: bool, word: Word },          // ${x:=w} ${x=w}
+    Alternate { colon: bool, word: Word },       // ${x:+w} ${x+w}
+    Error { colon: bool, word: Word },           // ${x:?w} ${x?w}
+    TrimPrefix { longest: bool, pattern: Word }, // ${x#p} ${x##p}
+    TrimSuffix { longest: bool, pattern: Word }, // ${x%p} ${x%%p}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamExpr {
+    pub name: String, // variable name, digits for positionals or one of @*#?$!-
+    pub op: ParamOp,
+    pub raw: String,  // exactly as written, for text() and error messages
+}
+
+/// a single shell word, e.g. `foo"bar baz"` is one word with two parts
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Word {
+    pub parts: Vec<WordPart>,
+}
+
+impl Word {
+    // glue all the parts back together, quotes removed and expansions
+    // left as written
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            part_text(part, &mut out);
+        }
+        out
+    }
+
+    fn push_literal(&mut self, c: char) {
+        if let Some(WordPart::Literal(s)) = self.parts.last_mut() {
+            s.push(c);
+        } else {
+            self.parts.push(WordPart::Literal(c.to_string()));
+        }
+    }
+
+    fn push_quoted(&mut self, c: char) {
+        push_quoted(&mut self.parts, c);
+    }
+}
+
+fn part_text(part: &WordPart, out: &mut String) {
+    match part {
+        WordPart::Literal(s) | WordPart::Quoted(s) => out.push_str(s),
+        WordPart::DoubleQuoted(inner) => inner.iter().for_each(|p| part_text(p, out)),
+        WordPart::Param(p) => out.push_str(&p.raw),
+        WordPart::Command(script) => {
+            out.push_str("$(");
+            out.push_str(script);
+            out.push(')');
+        }
+        WordPart::Arith(expr) => {
+            out.push_str("$((");
+            out.push_str(&expr.text());
+            out.push_str("))");
+        }
+    }
+}
+
+fn push_quoted(parts: &mut Vec<WordPart>, c: char) {
+    if let Some(WordPart::Quoted(s)) = parts.last_mut() {
+        s.push(c);
+    } else {
+        parts.push(WordPart::Quoted(c.to_string()));
+    }
+}
+
+/// the redirection operators we understand
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RedirOp {
+    In,     // <
+    Out,    // >
+    Append, // >>
+    DupOut, // >&  (as in 2>&1)
+    HereDoc,    // << and <<- - the target word is the body, not a file
+    HereString, // <<< word
+}
+
+/// what the lexer hands to the parser
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Word(Word),
+    Pipe,    // |
+    Semi,    // ;
+    DSemi,   // ;; - ends a case arm
+    AndIf,   // &&
+    OrIf,    // ||
+    Amp,     // & - runs what's before it as a background job
+    Newline, // ends a command just like ; does
+    LParen,  // (
+    RParen,  // )
+    Arith(String), // (( expr )) - just the expression, as written
+    // optional fd number glued to the front, e.g. the 2 in `2>`
+    Redirect { fd: Option<u32>, op: RedirOp },
+}
+
+/// start and end char offsets of a token in the input
+pub type Span = (usize, usize);
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    tokens: Vec<Token>,
+    spans: Vec<Span>, // char range of each token in the input
+    word_start: usize,
+    heredoc_next: Option<bool>,       // just saw <<, the next word is its delimiter (true for <<-)
+    heredocs: Vec<(usize, bool)>,     // delimiter tokens whose bodies start after the next newline
+}
+
+impl Lexer {
+    fn new(input: &str) -> Self {
+        Self {
+            chars: input.chars().collect(),
+            pos: 0,
+            tokens: Vec::new(),
+            spans: Vec::new(),
+            word_start: 0,
+            heredoc_next: None,
+            heredocs: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn tokenize(&mut self) -> Result<Vec<Token>, String> {
+        let mut current: Option<Word> = None;
+
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' => {
+                    // whitespace ends the current word
+                    self.finish_word(&mut current);
+                    self.pos += 1;
+                }
+                '\\' if current.is_none() && self.peek_at(1) == Some('\n') => {
+                    // continuation between words, not part of either
+                    self.pos += 2;
+                }
+                '(' if self.peek_at(1) == Some('(') => {
+                    self.finish_word(&mut current);
+                    let start = self.pos;
+                    self.pos += 2;
+                    let expr = self.arith_body()?;
+                    self.emit(Token::Arith(expr), start);
+                }
+                '\n' | '|' | ';' | '(' | ')' => {
+                    // operators end the current word too
+                    self.finish_word(&mut current);
+                    let start = self.pos;
+                    let token = match (c, self.peek_at(1)) {
+                        ('|', Some('|')) => { self.pos += 1; Token::OrIf }
+                        ('|', _) => Token::Pipe,
+                        (';', Some(';')) => { self.pos += 1; Token::DSemi }
+                        (';', _) => Token::Semi,
+                        ('(', _) => Token::LParen,
+                        (')', _) => Token::RParen,
+                        _ => Token::Newline,
+                    };
+                    self.pos += 1;
+                    self.emit(token, start);
+                    if c == '\n' {
+                        self.read_heredocs()?;
+                    }
+                }
+                '&' if self.peek_at(1) == Some('&') => {
+                    self.finish_word(&mut current);
+                    self.pos += 2;
+                    self.emit(Token::AndIf, self.pos - 2);
+                }
+                '&' => {
+                    self.finish_word(&mut current);
+                    self.pos += 1;
+                    self.emit(Token::Amp, self.pos - 1);
+                }
+                '<' | '>' => {
+                    // a word made only of unquoted digits right before the
+                    // operator is the fd it applies to (2>err.log)
+                    let fd = match &current {
+                        Some(Word { parts }) => match parts.as_slice() {
+                            [WordPart::Literal(digits)] if digits.chars().all(|d| d.is_ascii_digit()) => digits.parse().ok(),
+                            _ => None,
+                        },
+                        None => None,
+                    };
+                    let start = if fd.is_some() {
+                        current = None;
+                        self.word_start
+                    } else {
+                        self.finish_word(&mut current);
+                        self.pos
+                    };
+                    let mut heredoc = None;
+                    let op = match (c, self.peek_at(1), self.peek_at(2)) {
+                        ('>', Some('>'), _) => { self.pos += 1; RedirOp::Append }
+                        ('>', Some('&'), _) => { self.pos += 1; RedirOp::DupOut }
+                        ('>', _, _) => RedirOp::Out,
+                        ('<', Some('<'), Some('<')) => { self.pos += 2; RedirOp::HereString }
+                        ('<', Some('<'), Some('-')) => { self.pos += 2; heredoc = Some(true); RedirOp::HereDoc }
+                        ('<', Some('<'), _) => { self.pos += 1; heredoc = Some(false); RedirOp::HereDoc }
+                        _ => RedirOp::In,
+                    };
+                    self.pos += 1;
+                    self.emit(Token::Redirect { fd, op }, start);
+                    self.heredoc_next = heredoc;
+                }
+                '#' if current.is_none() => {
+                    // comment runs to end of line
+                    while self.peek().is_some_and(|ch| ch != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                _ => {
+                    if current.is_none() {
+                        self.word_start = self.pos;
+                    }
+                    let word = current.get_or_insert_with(Word::default);
+                    self.word_char(word)?;
+                }
+            }
+        }
+
+        self.finish_word(&mut current);
+        if let Some(&(idx, _)) = self.heredocs.first() {
+            return Err(heredoc_eof(&self.tokens[idx]));
+        }
+        Ok(std::mem::take(&mut self.tokens))
+    }
+
+    fn emit(&mut self, token: Token, start: usize) {
+        // the word straight after << is the delimiter, remember where it is
+        if let Some(strip) = self.heredoc_next.take() {
+            if matches!(token, Token::Word(_)) {
+                self.heredocs.push((self.tokens.len(), strip));
+            }
+        }
+        self.tokens.push(token);
+        self.spans.push((start, self.pos));
+    }
+
+    // just after a newline - any here-docs started on that line have their
+    // bodies next, one after the other. each delimiter token is swapped
+    // for its body so the parser sees `<<` followed by the text
+    fn read_heredocs(&mut self) -> Result<(), String> {
+        for (idx, strip) in std::mem::take(&mut self.heredocs) {
+            let Token::Word(delimiter) = &self.tokens[idx] else { continue };
+            // quoting any of the delimiter turns off expansion in the body
+            let quoted = delimiter.parts.iter().any(|p| !matches!(p, WordPart::Literal(_)));
+            let end = delimiter.text();
+            let mut body = String::new();
+            loop {
+                if self.peek().is_none() {
+                    return Err(heredoc_eof(&self.tokens[idx]));
+                }
+                let mut line = String::new();
+                while let Some(c) = self.peek() {
+                    self.pos += 1;
+                    if c == '\n' {
+                        break;
+                    }
+                    line.push(c);
+                }
+                let line = if strip { line.trim_start_matches('\t') } else { line.as_str() };
+                if line == end {
+                    break;
+                }
+                body.push_str(line);
+                body.push('\n');
+            }
+            let word = if quoted {
+                Word { parts: vec![WordPart::Quoted(body)] }
+            } else {
+                Word { parts: vec![WordPart::DoubleQuoted(heredoc_parts(&body)?)] }
+            };
+            self.tokens[idx] = Token::Word(word);
+        }
+        Ok(())
+    }
+
+    fn finish_word(&mut self, current: &mut Option<Word>) {
+        if let Some(word) = current.take() {
+            self.emit(Token::Word(word), self.word_start);
+        }
+    }
+
+    // eat one piece of a word - a plain char, an escape, a quoted string
+    // or an expansion - and add it on
+    fn word_char(&mut self, word: &mut Word) -> Result<(), String> {
+        let c = self.peek().unwrap_or_default();
+        match c {
+            '\\' => {
+                match self.peek_at(1) {
+                    // line continuation - just drop both chars
+                    Some('\n') => {}
+                    Some(next) => word.push_quoted(next),
+                    // trailing backslash is kept as is
+                    None => word.push_literal('\\'),
+                }
+                self.pos += 2;
+            }
+            '\'' => {
+                // make sure '' still produces an (empty) argument
+                word.parts.push(WordPart::Quoted(String::new()));
+                self.pos += 1;
+                loop {
+                    match self.peek() {
+                        Some('\'') => break,
+                        Some(ch) => word.push_quoted(ch),
+                        None => return Err("unexpected EOF while looking for matching `''".to_string()),
+                    }
+                    self.pos += 1;
+                }
+                self.pos += 1;
+            }
+            '"' => {
+                self.pos += 1;
+                let inner = self.double_quoted()?;
+                word.parts.push(WordPart::DoubleQuoted(inner));
+            }
+            '$' => match self.dollar()? {
+                Some(part) => word.parts.push(part),
+                None => word.push_literal('$'),
+            },
+            '`' => {
+                let script = self.backquoted()?;
+                word.parts.push(WordPart::Command(script));
+            }
+            _ => {
+                word.push_literal(c);
+                self.pos += 1;
+            }
+        }
+        Ok(())
+    }
+
+    // body of "...", opening quote already eaten
+    fn double_quoted(&mut self) -> Result<Vec<WordPart>, String> {
+        let mut parts = Vec::new();
+        loop {
+            match self.peek() {
+                Some('"') => break,
+                Some('\\') => {
+                    // inside double quotes backslash only escapes these
+                    match self.peek_at(1) {
+                        Some('\n') => self.pos += 1,
+                        Some(next) if matches!(next, '"' | '\\' | '$' | '`') => {
+                            push_quoted(&mut parts, next);
+                            self.pos += 1;
+                        }
+                        _ => push_quoted(&mut parts, '\\'),
+                    }
+                    self.pos += 1;
+                }
+                Some('$') => match self.dollar()? {
+                    Some(part) => parts.push(part),
+                    None => push_quoted(&mut parts, '$'),
+                },
+                Some('`') => {
+                    let script = self.backquoted()?;
+                    parts.push(WordPart::Command(script));
+                }
+                Some(ch) => {
+                    push_quoted(&mut parts, ch);
+                    self.pos += 1;
+                }
+                None => return Err("unexpected EOF while looking for matching `\"'".to_string()),
+            }
+        }
+        self.pos += 1;
+        Ok(parts)
+    }
+
+    // sitting on a `$` - work out what kind of expansion follows
+    // None means it was just a dollar sign (only the `$` is eaten)
+    fn dollar(&mut self) -> Result<Option<WordPart>, String> {
+        let start = self.pos;
+        self.pos += 1;
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                let body = self.braced()?;
+                let raw: String = self.chars[start..self.pos].iter().collect();
+                Ok(Some(WordPart::Param(parse_braced(&body, raw)?)))
+            }
+            Some('(') if self.peek_at(1) == Some('(') => {
+                self.pos += 2;
+                let expr = self.arith_body()?;
+                Ok(Some(WordPart::Arith(lex_word(&expr)?)))
+            }
+            Some('(') => {
+                self.pos += 1;
+                Ok(Some(WordPart::Command(self.parenthesized()?)))
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(ch) = self.peek().filter(|ch| ch.is_ascii_alphanumeric() || *ch == '_') {
+                    name.push(ch);
+                    self.pos += 1;
+                }
+                Ok(Some(plain_param(name)))
+            }
+            // single char specials - $10 is $1 then a 0, same as sh
+            Some(c) if c.is_ascii_digit() || "@*#?$!-".contains(c) => {
+                self.pos += 1;
+                Ok(Some(plain_param(c.to_string())))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // everything up to the matching `}`, skipping over nested braces
+    // and quotes so ${x:-"}"} does the right thing
+    fn braced(&mut self) -> Result<String, String> {
+        let mut depth = 0;
+        let mut body = String::new();
+        loop {
+            let c = self.peek().ok_or("unexpected EOF while looking for matching `}'")?;
+            self.pos += 1;
+            match c {
+                '}' if depth == 0 => return Ok(body),
+                '}' => depth -= 1,
+                '{' => depth += 1,
+                '\\' => {
+                    if let Some(next) = self.peek() {
+                        body.push(c);
+                        self.pos += 1;
+                        body.push(next);
+                        continue;
+                    }
+                }
+                '\'' | '"' => {
+                    body.push(c);
+                    while let Some(ch) = self.peek() {
+                        self.pos += 1;
+                        body.push(ch);
+                        if ch == c {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            body.push(c);
+        }
+    }
+
+    // the script inside $( ... ), opening paren already eaten. nested
+    // parens and quotes are skipped over so $(echo ")") works
+    fn parenthesized(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        let mut depth = 0;
+        loop {
+            let c = self.peek().ok_or("unexpected EOF while looking for matching `)'")?;
+            self.pos += 1;
+            match c {
+                ')' if depth == 0 => break,
+                ')' => depth -= 1,
+                '(' => depth += 1,
+                '\\' => self.pos += 1,
+                '\'' => {
+                    while self.peek().is_some_and(|ch| ch != '\'') {
+                        self.pos += 1;
+                    }
+                    self.pos += 1;
+                }
+                '"' => {
+                    while let Some(ch) = self.peek() {
+                        self.pos += if ch == '\\' { 2 } else { 1 };
+                        if ch == '"' {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            if self.pos > self.chars.len() {
+                return Err("unexpected EOF while looking for matching `)'".to_string());
+            }
+        }
+        Ok(self.chars[start..self.pos - 1].iter().collect())
+    }
+
+    // the expression inside (( ... )), both opening parens already eaten
+    fn arith_body(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                Some('(') => depth += 1,
+                Some(')') if depth > 0 => depth -= 1,
+                Some(')') if self.peek_at(1) == Some(')') => break,
+                Some(')') | None => return Err("unexpected EOF while looking for matching `))'".to_string()),
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        self.pos += 2;
+        Ok(self.chars[start..self.pos - 2].iter().collect())
+    }
+
+    // old style `cmd`, sitting on the opening backquote. inside, a
+    // backslash only escapes $ ` and \ - everything else is kept
+    fn backquoted(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut script = String::new();
+        loop {
+            match self.peek() {
+                Some('`') => break,
+                Some('\\') if matches!(self.peek_at(1), Some('$' | '`' | '\\')) => {
+                    script.push(self.peek_at(1).unwrap_or_default());
+                    self.pos += 1;
+                }
+                Some(c) => script.push(c),
+                None => return Err("unexpected EOF while looking for matching ``'".to_string()),
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        Ok(script)
+    }
+}
+
+fn heredoc_eof(delimiter: &Token) -> String {
+    let end = match delimiter {
+        Token::Word(word) => word.text(),
+        _ => String::new(),
+    };
+    format!("unexpected EOF while looking for here-document delimiter `{}'", end)
+}
+
+// an unquoted here-doc body expands like it's in double quotes, except
+// that a " is just a ". backslash only escapes $ ` \ and newline
+fn heredoc_parts(body: &str) -> Result<Vec<WordPart>, String> {
+    let mut lexer = Lexer::new(body);
+    let mut parts = Vec::new();
+    while let Some(c) = lexer.peek() {
+        match c {
+            '\\' => match lexer.peek_at(1) {
+                Some('\n') => lexer.pos += 2,
+                Some(next @ ('$' | '`' | '\\')) => {
+                    push_quoted(&mut parts, next);
+                    lexer.pos += 2;
+                }
+                _ => {
+                    push_quoted(&mut parts, c);
+                    lexer.pos += 1;
+                }
+            },
+            '$' => match lexer.dollar()? {
+                Some(part) => parts.push(part),
+                None => push_quoted(&mut parts, '$'),
+            },
+            '`' => {
+                let script = lexer.backquoted()?;
+                parts.push(WordPart::Command(script));
+            }
+            _ => {
+                push_quoted(&mut parts, c);
+                lexer.pos += 1;
+            }
+        }
+    }
+    Ok(parts)
+}
+
+fn plain_param(name: String) -> WordPart {
+    let raw = format!("${}", name);
+    WordPart::Param(ParamExpr { name, op: ParamOp::Plain, raw })
+}
+
+// split the inside of ${...} into name, operator and operand
+fn parse_braced(body: &str, raw: String) -> Result<ParamExpr, String> {
+    let bad = || format!("{}: bad substitution", raw);
+
+    // ${#name} is the length, but ${#} alone is the arg count
+    if let Some(rest) = body.strip_prefix('#').filter(|r| !r.is_empty()) {
+        if !is_param_name(rest) {
+            return Err(bad());
+        }
+        return Ok(ParamExpr { name: rest.to_string(), op: ParamOp::Length, raw });
+    }
+
+    // names are word chars, all digits, or a single special char
+    let name_len = match body.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => body
+            .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+            .unwrap_or(body.len()),
+        Some(c) if c.is_ascii_digit() => body.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(body.len()),
+        Some(c) if "@*#?$!-".contains(c) => 1,
+        _ => return Err(bad()),
+    };
+    let name = body[..name_len].to_string();
+    let rest = &body[name_len..];
+
+    let (colon, rest) = match rest.strip_prefix(':') {
+        Some(r) => (true, r),
+        None => (false, rest),
+    };
+    let op = match rest.chars().next() {
+        None if !colon => ParamOp::Plain,
+        Some('-') => ParamOp::Default { colon, word: lex_word(&rest[1..])? },
+        Some('=') => ParamOp::Assign { colon, word: lex_word(&rest[1..])? },
+        Some('+') => ParamOp::Alternate { colon, word: lex_word(&rest[1..])? },
+        Some('?') => ParamOp::Error { colon, word: lex_word(&rest[1..])? },
+        Some('#') if !colon => match rest.strip_prefix("##") {
+            Some(p) => ParamOp::TrimPrefix { longest: true, pattern: lex_word(p)? },
+            None => ParamOp::TrimPrefix { longest: false, pattern: lex_word(&rest[1..])? },
+        },
+        Some('%') if !colon => match rest.strip_prefix("%%") {
+            Some(p) => ParamOp::TrimSuffix { longest: true, pattern: lex_word(p)? },
+            None => ParamOp::TrimSuffix { longest: false, pattern: lex_word(&rest[1..])? },
+        },
+        _ => return Err(bad()),
+    };
+    Ok(ParamExpr { name, op, raw })
+}
+
+fn is_param_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_'),
+        Some(c) if c.is_ascii_digit() => chars.all(|ch| ch.is_ascii_digit()),
+        Some(c) => "@*#?$!-".contains(c) && s.len() == 1,
+        None => false,
+    }
+}
+
+/// lex text as a single word - quotes and expansions work as usual but
+/// spaces are just text. used for ${x:-word} operands and for prompt
+/// strings like PS4
+pub fn lex_word(text: &str) -> Result<Word, String> {
+    let mut lexer = Lexer::new(text);
+    let mut word = Word::default();
+    while lexer.peek().is_some() {
+        lexer.word_char(&mut word)?;
+    }
+    Ok(word)
+}
+
+/// split a line into tokens the way sh does:
+/// 'single quotes' are fully literal, "double quotes" keep spaces but
+/// honour a few backslash escapes, a bare backslash escapes the next char
+/// and backslash-newline is a line continuation.
+/// also gives back where each token sits in the input (as char offsets)
+/// so the parser can pull out the source of a function body
+pub fn tokenize(input: &str) -> Result<(Vec<Token>, Vec<Span>), String> {
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.tokenize()?;
+    Ok((tokens, lexer.spans))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(input: &str) -> Vec<String> {
+        tokenize(input).unwrap().0.iter().map(|t| match t {
+            Token::Word(w) => w.text(),
+            Token::Pipe => "|".to_string(),
+            Token::Semi => ";".to_string(),
+            Token::DSemi => ";;".to_string(),
+            Token::AndIf => "&&".to_string(),
+            Token::OrIf => "||".to_string(),
+            Token::Amp => "&".to_string(),
+            Token::Newline => "\\n".to_string(),
+            Token::LParen => "(".to_string(),
+            Token::RParen => ")".to_string(),
+            Token::Arith(expr) => format!("(({}))", expr),
+            Token::Redirect { fd, op } => format!("{}{:?}", fd.map(|n| n.to_string()).unwrap_or_default(), op),
+        }).collect()
+    }
+
+    fn first_word(input: &str) -> Word {
+        match tokenize(input).unwrap().0.into_iter().next() {
+            Some(Token::Word(word)) => word,
+            other => panic!("expected a word, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_plain_words() {
+        assert_eq!(args("ls -l  /home"), vec!["ls", "-l", "/home"]);
+        assert!(args("   ").is_empty());
+    }
+
+    #[test]
+    fn test_quotes_keep_spaces() {
+        assert_eq!(args("echo \"hello   world\""), vec!["echo", "hello   world"]);
+        assert_eq!(args("grep 'a b' file"), vec!["grep", "a b", "file"]);
+        assert_eq!(args("alias ll='ls -l'"), vec!["alias", "ll=ls -l"]);
+        assert_eq!(args("echo '' \"\""), vec!["echo", "", ""]);
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(args("echo a\\ b"), vec!["echo", "a b"]);
+        assert_eq!(args("echo \"say \\\"hi\\\"\""), vec!["echo", "say \"hi\""]);
+        assert_eq!(args("echo 'no \\escape'"), vec!["echo", "no \\escape"]);
+        assert_eq!(args("echo \"keep \\n\""), vec!["echo", "keep \\n"]);
+    }
+
+    #[test]
+    fn test_line_continuation_and_comments() {
+        assert_eq!(args("echo one \\\ntwo"), vec!["echo", "one", "two"]);
+        assert_eq!(args("echo one \\\n two"), vec!["echo", "one", "two"]);
+        assert_eq!(args("echo a # note\necho b"), vec!["echo", "a", "\\n", "echo", "b"]);
+        assert_eq!(args("echo hi # a comment"), vec!["echo", "hi"]);
+        assert_eq!(args("echo a#b"), vec!["echo", "a#b"]);
+    }
+
+    #[test]
+    fn test_unterminated_quote() {
+        assert!(tokenize("echo 'oops").is_err());
+        assert!(tokenize("echo \"oops").is_err());
+        assert!(tokenize("echo ${oops").is_err());
+    }
+
+    #[test]
+    fn test_pipe_operator() {
+        assert_eq!(args("ls|grep x | wc"), vec!["ls", "|", "grep", "x", "|", "wc"]);
+        assert_eq!(args("echo 'a|b' c\\|d"), vec!["echo", "a|b", "c|d"]);
+    }
+
+    #[test]
+    fn test_list_operators() {
+        assert_eq!(args("a;b && c||d"), vec!["a", ";", "b", "&&", "c", "||", "d"]);
+        assert_eq!(args("a)b;;c\nd"), vec!["a", ")", "b", ";;", "c", "\\n", "d"]);
+        assert_eq!(args("echo 'a;b' a\\&\\&b"), vec!["echo", "a;b", "a&&b"]);
+        assert_eq!(args("a & b& 2>&1"), vec!["a", "&", "b", "&", "2DupOut", "1"]);
+    }
+
+    #[test]
+    fn test_substitutions_and_arithmetic() {
+        assert_eq!(args("((x = (1 + 2) * 3)); echo $((x)) $(echo) `pwd`"), vec!["((x = (1 + 2) * 3))", ";", "echo", "$((x))", "$(echo)", "$(pwd)"]);
+        assert!(matches!(first_word("$((1))").parts[0], WordPart::Arith(_)));
+        assert!(matches!(first_word("$( (1))").parts[0], WordPart::Command(_)));
+        assert!(tokenize("echo $((1 + 2)").is_err());
+    }
+
+    #[test]
+    fn test_heredocs() {
+        assert_eq!(args("cat <<EOF | wc\nhi $x\n  EOF\nEOF\necho done"), vec!["cat", "HereDoc", "hi $x\n  EOF\n", "|", "wc", "\\n", "echo", "done"]);
+        // <<- strips leading tabs, including from the delimiter line
+        assert_eq!(args("cat <<-END\n\tindented\n\tEND"), vec!["cat", "HereDoc", "indented\n", "\\n"]);
+        assert_eq!(args("cat <<<'a b'"), vec!["cat", "HereString", "a b"]);
+        // two on one line read their bodies in order
+        assert_eq!(args("a <<X; b <<Y\n1\nX\n2\nY"), vec!["a", "HereDoc", "1\n", ";", "b", "HereDoc", "2\n", "\\n"]);
+        let Token::Word(body) = &tokenize("cat <<'EOF'\n$HOME\nEOF").unwrap().0[2] else { panic!("expected body") };
+        assert_eq!(body.parts, vec![WordPart::Quoted("$HOME\n".to_string())]);
+        let Token::Word(body) = &tokenize("cat <<EOF\n\"$HOME\" \\$x\nEOF").unwrap().0[2] else { panic!("expected body") };
+        assert!(matches!(body.parts.as_slice(), [WordPart::DoubleQuoted(parts)] if matches!(parts[1], WordPart::Param(_))));
+        assert!(tokenize("cat <<EOF\nno end").unwrap_err().contains("here-document"));
+    }
+
+    #[test]
+    fn test_redirections() {
+        assert_eq!(args("echo hi >out.txt"), vec!["echo", "hi", "Out", "out.txt"]);
+        assert_eq!(args("cmd 2>&1 >> log < in"), vec!["cmd", "2DupOut", "1", "Append", "log", "In", "in"]);
+        // quoted digits and digits inside words are not fd numbers
+        assert_eq!(args("echo '2'>x a2>y"), vec!["echo", "2", "Out", "x", "a2", "Out", "y"]);
+        assert_eq!(args("echo '>' a\\>b"), vec!["echo", ">", "a>b"]);
+    }
+
+    #[test]
+    fn test_spans_cover_tokens() {
+        let (tokens, spans) = tokenize("f() { echo 'a b'; } 2>x").unwrap();
+        assert_eq!(tokens.len(), spans.len());
+        assert_eq!(spans, vec![(0, 1), (1, 2), (2, 3), (4, 5), (6, 10), (11, 16), (16, 17), (18, 19), (20, 22), (22, 23)]);
+    }
+
+    #[test]
+    fn test_quoting_is_recorded() {
+        assert_eq!(first_word("a'b'").parts, vec![
+            WordPart::Literal("a".to_string()),
+            WordPart::Quoted("b".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_dollar_forms() {
+        let word = first_word("$HOME/x");
+        assert!(matches!(&word.parts[0], WordPart::Param(p) if p.name == "HOME" && p.op == ParamOp::Plain));
+        assert_eq!(word.parts[1], WordPart::Literal("/x".to_string()));
+
+        let word = first_word("\"$1-$#\"");
+        let WordPart::DoubleQuoted(inner) = &word.parts[0] else { panic!("expected double quotes") };
+        assert_eq!(inner.len(), 3);
+
+        // a lone dollar is just a dollar, and single quotes stop expansion
+        assert_eq!(args("echo $ a$"), vec!["echo", "$", "a$"]);
+        assert_eq!(first_word("'$HOME'").parts, vec![WordPart::Quoted("$HOME".to_string())]);
+    }
+
+    #[test]
+    fn test_braced_params() {
+        let param = |input: &str| match first_word(input).parts.into_iter().next() {
+            Some(WordPart::Param(p)) => p,
+            other => panic!("expected a param, got {:?}", other),
+        };
+        assert_eq!(param("${#name}").op, ParamOp::Length);
+        assert_eq!(param("${#}").name, "#");
+        assert_eq!(param("${10}").name, "10");
+        assert!(matches!(param("${x:-a b}").op, ParamOp::Default { colon: true, ref word } if word.text() == "a b"));
+        assert!(matches!(param("${x-}").op, ParamOp::Default { colon: false, .. }));
+        assert!(matches!(param("${f##*/}").op, ParamOp::TrimPrefix { longest: true, ref pattern } if pattern.text() == "*/"));
+        assert!(matches!(param("${f%.txt}").op, ParamOp::TrimSuffix { longest: false, .. }));
+        assert!(matches!(param("${x:-${y:-z}}").op, ParamOp::Default { .. }));
+        assert!(tokenize("${x:}").is_err());
+        assert!(tokenize("${!x}").is_err());
+    }
+}
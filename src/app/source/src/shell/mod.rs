@@ -0,0 +1,16 @@
+// the shell language itself - turning a raw input line into something
+// run_command can dispatch
+pub mod arith;
+pub mod ast;
+pub mod brace;
+pub mod complete;
+pub mod cond;
+pub mod exec;
+pub mod expand;
+pub mod glob;
+pub mod history;
+pub mod lexer;
+pub mod parser;
+pub mod pattern;
+pub mod prompt;
+pub mod startup;
@@ -0,0 +1,867 @@
+use super::ast::{AndOr, Assignment, CaseArm, Command, Compound, CondExpr, Connector, List, Pipeline, Redirect, SimpleCommand};
+use super::lexer::{self, RedirOp, Span, Token, Word, WordPart};
+use std::collections::{HashMap, HashSet};
+
+// words that mean something when they start a command (or end a
+// compound one). anywhere else they're just text, `echo fi` is fine
+const RESERVED: &[&str] = &["if", "then", "elif", "else", "fi", "while", "until", "do", "done", "for", "case", "esac", "{", "}", "[["];
+
+/// recursive descent over the token list, nothing clever. hands out one
+/// top level command at a time so a script can run line by line
+pub struct Parser {
+    source: Vec<char>,
+    tokens: Vec<Token>,
+    spans: Vec<Span>,
+    pos: usize,
+    aliases: HashMap<String, String>,
+}
+
+impl Parser {
+    pub fn new(input: &str) -> Result<Self, String> {
+        let (tokens, spans) = lexer::tokenize(input)?;
+        Ok(Self { source: input.chars().collect(), tokens, spans, pos: 0, aliases: HashMap::new() })
+    }
+
+    /// aliases to expand from here on. the caller refreshes these between
+    /// lists, so `alias` takes effect on the next line like in bash
+    pub fn set_aliases(&mut self, aliases: HashMap<String, String>) {
+        self.aliases = aliases;
+    }
+
+    // the original text between two char offsets
+    fn source_text(&self, start: usize, end: usize) -> String {
+        self.source[start..end].iter().collect::<String>().trim().to_string()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    // the current token if it's an unquoted reserved word
+    fn peek_keyword(&self) -> Option<&str> {
+        match self.peek() {
+            Some(Token::Word(Word { parts })) => match parts.as_slice() {
+                [WordPart::Literal(s)] if RESERVED.contains(&s.as_str()) => Some(s.as_str()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.peek_keyword() == Some(keyword) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(unexpected(self.peek()))
+        }
+    }
+
+    fn skip_newlines(&mut self) {
+        while let Some(Token::Newline) = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    /// the next complete command (everything up to the end of the line,
+    /// plus whatever lines an if/while/... spans); `None` at end of input
+    pub fn next_list(&mut self) -> Result<Option<List>, String> {
+        self.skip_newlines();
+        if self.peek().is_none() {
+            return Ok(None);
+        }
+        let mut items = vec![self.parse_and_or()?];
+        loop {
+            match self.peek() {
+                Some(Token::Semi) => self.pos += 1,
+                // `a & b` - the & already ended a
+                _ if items.last().is_some_and(|item| item.background.is_some()) => {}
+                _ => break,
+            }
+            // a trailing `;` is fine, `;;` or `; &&` is not
+            if matches!(self.peek(), None | Some(Token::Newline)) {
+                break;
+            }
+            items.push(self.parse_and_or()?);
+        }
+        match self.peek() {
+            None => {}
+            Some(Token::Newline) => self.pos += 1,
+            other => return Err(unexpected(other)),
+        }
+        Ok(Some(List { items }))
+    }
+
+    // the body of an if/while/for/case - commands separated by ; or
+    // newlines, running until one of the closing keywords shows up
+    fn compound_list(&mut self) -> Result<List, String> {
+        self.skip_newlines();
+        let mut items = Vec::new();
+        while !self.at_list_end() {
+            let item = self.parse_and_or()?;
+            let ended = item.background.is_some();
+            items.push(item);
+            match self.peek() {
+                Some(Token::Semi) | Some(Token::Newline) => {
+                    self.pos += 1;
+                    self.skip_newlines();
+                }
+                _ if ended => self.skip_newlines(),
+                _ => break,
+            }
+        }
+        if items.is_empty() {
+            // `if then` and friends
+            return Err(unexpected(self.peek()));
+        }
+        Ok(List { items })
+    }
+
+    fn at_list_end(&self) -> bool {
+        match self.peek() {
+            None | Some(Token::DSemi) | Some(Token::RParen) => true,
+            _ => matches!(self.peek_keyword(), Some("then" | "elif" | "else" | "fi" | "do" | "done" | "esac" | "}")),
+        }
+    }
+
+    fn parse_and_or(&mut self) -> Result<AndOr, String> {
+        let start = self.pos;
+        let first = self.parse_pipeline()?;
+        let mut rest = Vec::new();
+        loop {
+            let connector = match self.peek() {
+                Some(Token::AndIf) => Connector::And,
+                Some(Token::OrIf) => Connector::Or,
+                _ => break,
+            };
+            self.pos += 1;
+            self.skip_newlines(); // `a &&` can carry on onto the next line
+            rest.push((connector, self.parse_pipeline()?));
+        }
+        // a trailing & makes the whole chain a job, and ends it just like ;
+        let background = match self.peek() {
+            Some(Token::Amp) => {
+                let text = self.source_text(self.spans[start].0, self.spans[self.pos - 1].1);
+                self.pos += 1;
+                Some(text)
+            }
+            _ => None,
+        };
+        Ok(AndOr { first, rest, background })
+    }
+
+    fn parse_pipeline(&mut self) -> Result<Pipeline, String> {
+        let mut commands = vec![self.parse_command()?];
+        while let Some(Token::Pipe) = self.peek() {
+            self.pos += 1;
+            self.skip_newlines();
+            commands.push(self.parse_command()?);
+        }
+        Ok(Pipeline { commands })
+    }
+
+    fn parse_command(&mut self) -> Result<Command, String> {
+        if let Some(name) = self.function_name() {
+            return self.parse_function(name);
+        }
+        self.expand_aliases();
+        let compound = match self.peek_keyword() {
+            Some("if") => self.parse_if()?,
+            Some("while") | Some("until") => self.parse_loop()?,
+            Some("for") => self.parse_for()?,
+            Some("case") => self.parse_case()?,
+            Some("{") => self.parse_group()?,
+            Some("[[") => self.parse_cond()?,
+            // then/fi/done/... can't start a command
+            Some(_) => return Err(unexpected(self.peek())),
+            None => match self.peek() {
+                Some(Token::Arith(expr)) => {
+                    let expr = lexer::lex_word(expr)?;
+                    self.pos += 1;
+                    Compound::Arith(expr)
+                }
+                _ => return Ok(Command::Simple(self.parse_simple_command()?)),
+            },
+        };
+        let mut redirects = Vec::new();
+        while let Some(&Token::Redirect { fd, op }) = self.peek() {
+            self.pos += 1;
+            redirects.push(self.parse_redirect(fd, op)?);
+        }
+        Ok(Command::Compound(compound, redirects))
+    }
+
+    // swap an alias at the start of a command for the tokens of its value.
+    // the result can be another alias, but not one we've already expanded
+    // (so `alias ls='ls -F'` works), and a value ending in a blank means
+    // the word after it gets checked too (`alias sudo='sudo '`)
+    fn expand_aliases(&mut self) {
+        let mut at = self.pos;
+        let mut seen = HashSet::new();
+        let mut next = None;
+        loop {
+            let Some((name, value)) = self.alias_at(at, &seen) else {
+                // done with this word, maybe move on to the one after
+                match next.take() {
+                    Some(end) => {
+                        at = end;
+                        seen.clear();
+                        continue;
+                    }
+                    None => return,
+                }
+            };
+            // a broken alias (unclosed quote...) is left as a plain word
+            let Ok((tokens, _)) = lexer::tokenize(&value) else { return };
+            let count = tokens.len();
+            let span = self.spans[at];
+            self.tokens.splice(at..at + 1, tokens);
+            self.spans.splice(at..at + 1, vec![span; count]);
+            seen.insert(name);
+            next = value.ends_with([' ', '\t']).then_some(at + count);
+        }
+    }
+
+    fn alias_at(&self, at: usize, seen: &HashSet<String>) -> Option<(String, String)> {
+        let Some(Token::Word(Word { parts })) = self.tokens.get(at) else { return None };
+        let [WordPart::Literal(name)] = parts.as_slice() else { return None };
+        if seen.contains(name) {
+            return None;
+        }
+        self.aliases.get(name).map(|value| (name.clone(), value.clone()))
+    }
+
+    // `name ( )` coming up? then it's a function definition
+    fn function_name(&self) -> Option<String> {
+        let Some(Token::Word(Word { parts })) = self.peek() else { return None };
+        let [WordPart::Literal(name)] = parts.as_slice() else { return None };
+        let parens = matches!(
+            (self.tokens.get(self.pos + 1), self.tokens.get(self.pos + 2)),
+            (Some(Token::LParen), Some(Token::RParen))
+        );
+        (parens && is_name(name) && !RESERVED.contains(&name.as_str())).then(|| name.clone())
+    }
+
+    fn parse_function(&mut self, name: String) -> Result<Command, String> {
+        self.pos += 3; // name ( )
+        self.skip_newlines();
+        let first = self.pos;
+        let body = match self.peek_keyword() {
+            Some("{") => self.parse_group()?,
+            Some("if") => self.parse_if()?,
+            Some("while") | Some("until") => self.parse_loop()?,
+            Some("for") => self.parse_for()?,
+            Some("case") => self.parse_case()?,
+            Some("[[") => self.parse_cond()?,
+            _ => return Err(unexpected(self.peek())),
+        };
+        // keep the text inside the braces, or the whole compound command
+        let text = match body {
+            Compound::Group(_) => self.source_text(self.spans[first].1, self.spans[self.pos - 1].0),
+            _ => self.source_text(self.spans[first].0, self.spans[self.pos - 1].1),
+        };
+        Ok(Command::FunctionDef { name, body: text })
+    }
+
+    fn parse_group(&mut self) -> Result<Compound, String> {
+        self.pos += 1; // {
+        let body = self.compound_list()?;
+        self.expect_keyword("}")?;
+        Ok(Compound::Group(body))
+    }
+
+    fn parse_if(&mut self) -> Result<Compound, String> {
+        self.pos += 1; // if
+        let mut branches = Vec::new();
+        let mut otherwise = None;
+        loop {
+            let cond = self.compound_list()?;
+            self.expect_keyword("then")?;
+            let body = self.compound_list()?;
+            branches.push((cond, body));
+            match self.peek_keyword() {
+                Some("elif") => self.pos += 1,
+                Some("else") => {
+                    self.pos += 1;
+                    otherwise = Some(self.compound_list()?);
+                    self.expect_keyword("fi")?;
+                    break;
+                }
+                _ => {
+                    self.expect_keyword("fi")?;
+                    break;
+                }
+            }
+        }
+        Ok(Compound::If { branches, otherwise })
+    }
+
+    fn parse_loop(&mut self) -> Result<Compound, String> {
+        let until = self.peek_keyword() == Some("until");
+        self.pos += 1;
+        let cond = self.compound_list()?;
+        let body = self.parse_do_group()?;
+        Ok(Compound::Loop { until, cond, body })
+    }
+
+    // do ... done
+    fn parse_do_group(&mut self) -> Result<List, String> {
+        self.expect_keyword("do")?;
+        let body = self.compound_list()?;
+        self.expect_keyword("done")?;
+        Ok(body)
+    }
+
+    fn parse_for(&mut self) -> Result<Compound, String> {
+        self.pos += 1; // for
+        if let Some(Token::Arith(header)) = self.peek() {
+            return self.parse_arith_for(header.clone());
+        }
+        let var = match self.peek() {
+            Some(Token::Word(word)) => word.text(),
+            other => return Err(unexpected(other)),
+        };
+        if !is_name(&var) {
+            return Err(format!("`{}': not a valid identifier", var));
+        }
+        self.pos += 1;
+        self.skip_newlines();
+
+        let words = if matches!(self.peek(), Some(Token::Word(w)) if w.parts == [WordPart::Literal("in".to_string())]) {
+            self.pos += 1;
+            let mut words = Vec::new();
+            while let Some(Token::Word(word)) = self.peek() {
+                words.push(word.clone());
+                self.pos += 1;
+            }
+            match self.peek() {
+                Some(Token::Semi) | Some(Token::Newline) => self.pos += 1,
+                other => return Err(unexpected(other)),
+            }
+            Some(words)
+        } else {
+            if let Some(Token::Semi) = self.peek() {
+                self.pos += 1;
+            }
+            None
+        };
+        self.skip_newlines();
+        let body = self.parse_do_group()?;
+        Ok(Compound::For { var, words, body })
+    }
+
+    // for (( init; cond; step )) do ...; done
+    fn parse_arith_for(&mut self, header: String) -> Result<Compound, String> {
+        self.pos += 1;
+        let exprs: Vec<&str> = header.split(';').collect();
+        let [init, cond, step] = exprs.as_slice() else {
+            return Err("syntax error: arithmetic expression required, `for ((init; cond; step))'".to_string());
+        };
+        let (init, cond, step) = (lexer::lex_word(init)?, lexer::lex_word(cond)?, lexer::lex_word(step)?);
+        if let Some(Token::Semi) = self.peek() {
+            self.pos += 1;
+        }
+        self.skip_newlines();
+        let body = self.parse_do_group()?;
+        Ok(Compound::ArithFor { init, cond, step, body })
+    }
+
+    fn parse_case(&mut self) -> Result<Compound, String> {
+        self.pos += 1; // case
+        let word = match self.peek() {
+            Some(Token::Word(word)) => word.clone(),
+            other => return Err(unexpected(other)),
+        };
+        self.pos += 1;
+        self.skip_newlines();
+        match self.peek() {
+            Some(Token::Word(w)) if w.parts == [WordPart::Literal("in".to_string())] => self.pos += 1,
+            other => return Err(unexpected(other)),
+        }
+        self.skip_newlines();
+
+        let mut arms = Vec::new();
+        while self.peek_keyword() != Some("esac") {
+            // the opening ( is optional
+            if let Some(Token::LParen) = self.peek() {
+                self.pos += 1;
+            }
+            let mut patterns = Vec::new();
+            loop {
+                match self.peek() {
+                    Some(Token::Word(word)) => patterns.push(word.clone()),
+                    other => return Err(unexpected(other)),
+                }
+                self.pos += 1;
+                match self.peek() {
+                    Some(Token::Pipe) => self.pos += 1,
+                    Some(Token::RParen) => {
+                        self.pos += 1;
+                        break;
+                    }
+                    other => return Err(unexpected(other)),
+                }
+            }
+            // an arm is allowed to do nothing: `*) ;;`
+            self.skip_newlines();
+            let body = if self.at_list_end() { List::default() } else { self.compound_list()? };
+            arms.push(CaseArm { patterns, body });
+            // the last arm doesn't need its ;;
+            match self.peek() {
+                Some(Token::DSemi) => {
+                    self.pos += 1;
+                    self.skip_newlines();
+                }
+                _ if self.peek_keyword() == Some("esac") => {}
+                other => return Err(unexpected(other)),
+            }
+        }
+        self.pos += 1; // esac
+        Ok(Compound::Case { word, arms })
+    }
+
+    // [[ ... ]] - the tokens in between get read as an expression rather
+    // than a command, so && || ( ) < > mean what they do in test
+    fn parse_cond(&mut self) -> Result<Compound, String> {
+        self.pos += 1; // [[
+        let expr = self.cond_or()?;
+        if !self.at_word("]]") {
+            return Err(unexpected(self.peek()));
+        }
+        self.pos += 1;
+        Ok(Compound::Cond(expr))
+    }
+
+    fn at_word(&self, text: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(Word { parts })) if matches!(parts.as_slice(), [WordPart::Literal(s)] if s == text))
+    }
+
+    fn cond_or(&mut self) -> Result<CondExpr, String> {
+        let mut expr = self.cond_and()?;
+        while let Some(Token::OrIf) = self.peek() {
+            self.pos += 1;
+            self.skip_newlines();
+            expr = CondExpr::Or(Box::new(expr), Box::new(self.cond_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn cond_and(&mut self) -> Result<CondExpr, String> {
+        let mut expr = self.cond_not()?;
+        while let Some(Token::AndIf) = self.peek() {
+            self.pos += 1;
+            self.skip_newlines();
+            expr = CondExpr::And(Box::new(expr), Box::new(self.cond_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn cond_not(&mut self) -> Result<CondExpr, String> {
+        if self.at_word("!") {
+            self.pos += 1;
+            return Ok(CondExpr::Not(Box::new(self.cond_not()?)));
+        }
+        self.cond_primary()
+    }
+
+    fn cond_primary(&mut self) -> Result<CondExpr, String> {
+        let word = match self.peek() {
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let expr = self.cond_or()?;
+                match self.peek() {
+                    Some(Token::RParen) => self.pos += 1,
+                    other => return Err(unexpected(other)),
+                }
+                return Ok(expr);
+            }
+            Some(Token::Word(word)) if !self.at_word("]]") => word.clone(),
+            other => return Err(unexpected(other)),
+        };
+        self.pos += 1;
+        // -f file, unless it's really `-f == x`
+        let text = literal(&word);
+        if text.as_deref().is_some_and(super::cond::is_unary) && self.cond_operator().is_none() {
+            if let Some(Token::Word(operand)) = self.peek().filter(|_| !self.at_word("]]")) {
+                let operand = operand.clone();
+                self.pos += 1;
+                return Ok(CondExpr::Unary(text.unwrap_or_default(), operand));
+            }
+        }
+        let Some(op) = self.cond_operator() else { return Ok(CondExpr::Word(word)) };
+        self.pos += 1;
+        let right = if op == "=~" {
+            self.cond_regex()?
+        } else {
+            match self.peek() {
+                Some(Token::Word(right)) if !self.at_word("]]") => {
+                    let right = right.clone();
+                    self.pos += 1;
+                    right
+                }
+                other => return Err(unexpected(other)),
+            }
+        };
+        Ok(CondExpr::Binary(op, word, right))
+    }
+
+    // a binary operator at the current token? < and > come through as
+    // redirections
+    fn cond_operator(&self) -> Option<String> {
+        match self.peek() {
+            Some(Token::Word(word)) => literal(word).filter(|op| op == "=~" || super::cond::is_binary(op)),
+            Some(Token::Redirect { fd: None, op: RedirOp::In }) => Some("<".to_string()),
+            Some(Token::Redirect { fd: None, op: RedirOp::Out }) => Some(">".to_string()),
+            _ => None,
+        }
+    }
+
+    // the right side of =~ can have ( ) and | in it unquoted, which the
+    // lexer has already chopped up - so take the raw text up to ]] (or
+    // && / || outside any parens) and lex that as one word
+    fn cond_regex(&mut self) -> Result<Word, String> {
+        let first = self.pos;
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                None | Some(Token::Newline) => return Err(unexpected(self.peek())),
+                Some(Token::Word(_)) if depth == 0 && self.at_word("]]") => break,
+                Some(Token::AndIf) | Some(Token::OrIf) | Some(Token::RParen) if depth == 0 => break,
+                Some(Token::LParen) => depth += 1,
+                Some(Token::RParen) => depth -= 1,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        if self.pos == first {
+            return Err(unexpected(self.peek()));
+        }
+        lexer::lex_word(&self.source_text(self.spans[first].0, self.spans[self.pos - 1].1))
+    }
+
+    fn parse_simple_command(&mut self) -> Result<SimpleCommand, String> {
+        let mut assigns = Vec::new();
+        let mut words = Vec::new();
+        let mut redirects = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Word(word)) => {
+                    // assignments only count before the command name
+                    match split_assignment(word).filter(|_| words.is_empty()) {
+                        Some(assign) => assigns.push(assign),
+                        None => words.push(word.clone()),
+                    }
+                    self.pos += 1;
+                }
+                Some(&Token::Redirect { fd, op }) => {
+                    self.pos += 1;
+                    redirects.push(self.parse_redirect(fd, op)?);
+                }
+                _ => break,
+            }
+        }
+        if assigns.is_empty() && words.is_empty() && redirects.is_empty() {
+            // `| foo` or `foo |` - nothing to pipe from/to
+            return Err(unexpected(self.peek()));
+        }
+        Ok(SimpleCommand { assigns, words, redirects })
+    }
+
+    // the operator's already been eaten, every redirection needs a
+    // target word after it
+    fn parse_redirect(&mut self, fd: Option<u32>, op: RedirOp) -> Result<Redirect, String> {
+        let target = match self.peek() {
+            Some(Token::Word(word)) => word.clone(),
+            other => return Err(unexpected(other)),
+        };
+        self.pos += 1;
+        let reads = matches!(op, RedirOp::In | RedirOp::HereDoc | RedirOp::HereString);
+        let fd = fd.unwrap_or(if reads { 0 } else { 1 });
+        Ok(Redirect { fd, op, target })
+    }
+}
+
+// the text of a word with no quoting or expansions in it
+fn literal(word: &Word) -> Option<String> {
+    match word.parts.as_slice() {
+        [WordPart::Literal(s)] => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// a valid variable name - letters, digits and _, not starting with a digit
+pub(crate) fn is_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// `NAME=...` where NAME is unquoted and a valid identifier
+fn split_assignment(word: &Word) -> Option<Assignment> {
+    let Some(WordPart::Literal(first)) = word.parts.first() else { return None };
+    let (name, rest) = first.split_once('=')?;
+    if !is_name(name) {
+        return None;
+    }
+    let mut value = Word::default();
+    if !rest.is_empty() {
+        value.parts.push(WordPart::Literal(rest.to_string()));
+    }
+    value.parts.extend(word.parts[1..].iter().cloned());
+    Some(Assignment { name: name.to_string(), value })
+}
+
+/// bash's wording for "input ran out before the command was finished" -
+/// callers that can ask for more input look for this
+pub const UNEXPECTED_EOF: &str = "syntax error: unexpected end of input";
+
+/// whether input stops partway through a command - an open quote, an
+/// `if` with no `fi`, a trailing `|` or `\` - so the caller should ask
+/// for another line rather than report a syntax error
+pub fn incomplete(input: &str) -> bool {
+    let trailing = input.chars().rev().take_while(|&c| c == '\\').count();
+    if trailing % 2 == 1 {
+        return true;
+    }
+    let ran_out = |e: String| e == UNEXPECTED_EOF || e.starts_with("unexpected EOF");
+    let mut parser = match Parser::new(input) {
+        Ok(parser) => parser,
+        Err(e) => return ran_out(e),
+    };
+    loop {
+        match parser.next_list() {
+            Ok(Some(_)) => {}
+            Ok(None) => return false,
+            Err(e) => return ran_out(e),
+        }
+    }
+}
+
+fn unexpected(token: Option<&Token>) -> String {
+    let text = match token {
+        Some(Token::Pipe) => "|".to_string(),
+        Some(Token::Semi) => ";".to_string(),
+        Some(Token::DSemi) => ";;".to_string(),
+        Some(Token::AndIf) => "&&".to_string(),
+        Some(Token::OrIf) => "||".to_string(),
+        Some(Token::Amp) => "&".to_string(),
+        Some(Token::Newline) => "newline".to_string(),
+        Some(Token::LParen) => "(".to_string(),
+        Some(Token::RParen) => ")".to_string(),
+        Some(Token::Arith(_)) => "((".to_string(),
+        Some(Token::Word(w)) => w.text(),
+        Some(Token::Redirect { op, .. }) => match op {
+            RedirOp::In => "<",
+            RedirOp::Out => ">",
+            RedirOp::Append => ">>",
+            RedirOp::DupOut => ">&",
+            RedirOp::HereDoc => "<<",
+            RedirOp::HereString => "<<<",
+        }.to_string(),
+        None => return UNEXPECTED_EOF.to_string(),
+    };
+    format!("syntax error near unexpected token `{}'", text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the whole input in one go; `None` means there was nothing to run
+    fn parse_str(input: &str) -> Result<Option<List>, String> {
+        let mut parser = Parser::new(input)?;
+        let mut all: Option<List> = None;
+        while let Some(list) = parser.next_list()? {
+            all.get_or_insert_with(List::default).items.extend(list.items);
+        }
+        Ok(all)
+    }
+
+    fn first_command(input: &str) -> Command {
+        parse_str(input).unwrap().unwrap().items.remove(0).first.commands.remove(0)
+    }
+
+    fn first_simple(input: &str) -> SimpleCommand {
+        match first_command(input) {
+            Command::Simple(cmd) => cmd,
+            other => panic!("expected a simple command, got {:?}", other),
+        }
+    }
+
+    fn stages(input: &str) -> Vec<Vec<String>> {
+        parse_str(input).unwrap().unwrap().items[0].first.commands.iter()
+            .map(|c| match c {
+                Command::Simple(cmd) => cmd.words.iter().map(|w| w.text()).collect(),
+                _ => vec!["<compound>".to_string()],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_single_command() {
+        assert_eq!(stages("echo hi"), vec![vec!["echo", "hi"]]);
+        assert!(parse_str("  ").unwrap().is_none());
+        assert!(parse_str("\n\n").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_pipeline_stages() {
+        assert_eq!(
+            stages("cat f | grep a | sed s/a/b/"),
+            vec![vec!["cat", "f"], vec!["grep", "a"], vec!["sed", "s/a/b/"]]
+        );
+    }
+
+    #[test]
+    fn test_redirects_are_split_from_words() {
+        let cmd = first_simple("echo hi > out 2>&1");
+        assert_eq!(cmd.words.len(), 2);
+        assert_eq!(cmd.redirects.len(), 2);
+        assert_eq!((cmd.redirects[0].fd, cmd.redirects[0].op), (1, RedirOp::Out));
+        assert_eq!((cmd.redirects[1].fd, cmd.redirects[1].op), (2, RedirOp::DupOut));
+        assert_eq!(cmd.redirects[1].target.text(), "1");
+        assert!(parse_str("echo >").is_err());
+    }
+
+    #[test]
+    fn test_leading_assignments() {
+        let cmd = first_simple("A=1 B='x y' env C=2");
+        assert_eq!(cmd.assigns.iter().map(|a| (a.name.as_str(), a.value.text())).collect::<Vec<_>>(),
+            vec![("A", "1".to_string()), ("B", "x y".to_string())]);
+        assert_eq!(cmd.words.iter().map(|w| w.text()).collect::<Vec<_>>(), vec!["env", "C=2"]);
+        // quoted names and bad identifiers are just words
+        assert_eq!(stages("'A'=1 1x=2"), vec![vec!["A=1", "1x=2"]]);
+    }
+
+    #[test]
+    fn test_dangling_pipe_is_an_error() {
+        assert!(parse_str("| grep a").is_err());
+        assert!(parse_str("ls |").is_err());
+        assert!(parse_str("ls | | wc").is_err());
+    }
+
+    #[test]
+    fn test_lists_and_connectors() {
+        let list = parse_str("a && b || c; d;").unwrap().unwrap();
+        assert_eq!(list.items.len(), 2);
+        let connectors: Vec<Connector> = list.items[0].rest.iter().map(|(c, _)| *c).collect();
+        assert_eq!(connectors, vec![Connector::And, Connector::Or]);
+        assert!(list.items[1].rest.is_empty());
+        assert!(parse_str("a ;; b").is_err());
+        assert!(parse_str("&& b").is_err());
+        assert!(parse_str("a ||").is_err());
+        // newlines separate commands, but not after && or |
+        assert_eq!(parse_str("a\nb &&\nc |\nd").unwrap().unwrap().items.len(), 2);
+
+        // & ends a command as well, making it a job
+        let list = parse_str("sleep 5 && echo  done & b; c &").unwrap().unwrap();
+        let jobs: Vec<_> = list.items.iter().map(|item| item.background.as_deref()).collect();
+        assert_eq!(jobs, vec![Some("sleep 5 && echo  done"), None, Some("c")]);
+        assert!(parse_str("& b").is_err());
+        assert_eq!(parse_str("if a; then b & fi").unwrap().unwrap().items.len(), 1);
+    }
+
+    #[test]
+    fn test_if_elif_else() {
+        let cmd = first_command("if a; then b; elif c\nthen d\nelse e; fi > out");
+        let Command::Compound(Compound::If { branches, otherwise }, redirects) = cmd else { panic!("expected if") };
+        assert_eq!(branches.len(), 2);
+        assert!(otherwise.is_some());
+        assert_eq!(redirects.len(), 1);
+        assert!(parse_str("if a; then fi").is_err());
+        assert_eq!(parse_str("if a; then b").unwrap_err(), UNEXPECTED_EOF);
+        assert!(parse_str("then a").is_err());
+        // keywords are only special in command position
+        assert_eq!(stages("echo if then fi"), vec![vec!["echo", "if", "then", "fi"]]);
+    }
+
+    #[test]
+    fn test_loops() {
+        let Command::Compound(Compound::Loop { until, .. }, _) = first_command("until a\ndo\n b\ndone") else { panic!("expected loop") };
+        assert!(until);
+        let Command::Compound(Compound::For { var, words, .. }, _) = first_command("for f in a b c; do echo $f; done") else { panic!("expected for") };
+        assert_eq!(var, "f");
+        assert_eq!(words.unwrap().len(), 3);
+        let Command::Compound(Compound::For { words, .. }, _) = first_command("for arg\ndo echo; done") else { panic!("expected for") };
+        assert!(words.is_none());
+        assert!(parse_str("for 1x in a; do b; done").is_err());
+        assert!(parse_str("while a; do b; done; done").is_err());
+    }
+
+    #[test]
+    fn test_function_definitions() {
+        let Command::FunctionDef { name, body } = first_command("greet() {\n  echo hi $1\n  echo bye; }") else { panic!("expected function") };
+        assert_eq!(name, "greet");
+        assert_eq!(body, "echo hi $1\n  echo bye;");
+        let Command::FunctionDef { body, .. } = first_command("f()\nif a; then b; fi") else { panic!("expected function") };
+        assert_eq!(body, "if a; then b; fi");
+        assert!(parse_str("f() echo hi").is_err());
+        assert!(parse_str("if() { a; }").is_err());
+        // braces on their own group commands
+        assert!(matches!(first_command("{ a; b; } > out"), Command::Compound(Compound::Group(_), _)));
+        assert!(parse_str("{ a; b }").is_err());
+    }
+
+    #[test]
+    fn test_alias_expansion() {
+        let parse_with = |input: &str, aliases: &[(&str, &str)]| {
+            let mut parser = Parser::new(input).unwrap();
+            parser.set_aliases(aliases.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect());
+            let list = parser.next_list().unwrap().unwrap();
+            let Command::Simple(cmd) = &list.items[0].first.commands[0] else { panic!("expected simple command") };
+            cmd.words.iter().map(|w| w.text()).collect::<Vec<_>>()
+        };
+        let aliases = [("ll", "ls -l"), ("ls", "ls -F"), ("a", "b"), ("b", "a x"), ("sudo", "sudo "), ("please", "sudo ")];
+        assert_eq!(parse_with("ll /tmp", &aliases), vec!["ls", "-F", "-l", "/tmp"]);
+        // a -> b -> a stops instead of looping forever
+        assert_eq!(parse_with("a", &aliases), vec!["a", "x"]);
+        // only the command word, unless the value ends in a space
+        assert_eq!(parse_with("echo ll", &aliases), vec!["echo", "ll"]);
+        assert_eq!(parse_with("please ll", &aliases), vec!["sudo", "ls", "-F", "-l"]);
+        assert_eq!(parse_with("'ll'", &aliases), vec!["ll"]);
+    }
+
+    #[test]
+    fn test_arithmetic_commands() {
+        assert!(matches!(first_command("(( x = 1 + 2 ))"), Command::Compound(Compound::Arith(_), _)));
+        let Command::Compound(Compound::ArithFor { init, cond, step, .. }, _) = first_command("for ((i=0; i<3; i++)); do echo $i; done") else {
+            panic!("expected arithmetic for")
+        };
+        assert_eq!((init.text(), cond.text(), step.text()), ("i=0".to_string(), " i<3".to_string(), " i++".to_string()));
+        assert!(parse_str("for ((i=0)) do :; done").is_err());
+    }
+
+    #[test]
+    fn test_conditional_expressions() {
+        let Command::Compound(Compound::Cond(expr), _) = first_command("[[ -f x && ! ( a < b || $c == d* ) ]]") else { panic!("expected [[") };
+        let CondExpr::And(left, right) = expr else { panic!("expected &&") };
+        assert!(matches!(*left, CondExpr::Unary(ref op, _) if op == "-f"));
+        assert!(matches!(*right, CondExpr::Not(_)));
+        // the regex is kept whole, parens and all
+        let Command::Compound(Compound::Cond(CondExpr::Binary(op, _, re)), _) = first_command("[[ $x =~ ^(a|b)+$ ]]") else { panic!("expected =~") };
+        assert_eq!((op.as_str(), re.text()), ("=~", "^(a|b)+$".to_string()));
+        assert!(matches!(first_command("[[ -n ]]"), Command::Compound(Compound::Cond(CondExpr::Word(_)), _)));
+        assert!(parse_str("[[ a == ]]").is_err());
+        assert!(parse_str("[[ a").is_err());
+        assert_eq!(stages("echo [[ ]]"), vec![vec!["echo", "[[", "]]"]]);
+    }
+
+    #[test]
+    fn test_case() {
+        let input = "case $x in\n  a|b) echo ab;;\n  (c) ;;\n  *) echo other\nesac";
+        let Command::Compound(Compound::Case { arms, .. }, _) = first_command(input) else { panic!("expected case") };
+        assert_eq!(arms.len(), 3);
+        assert_eq!(arms[0].patterns.len(), 2);
+        assert!(arms[1].body.items.is_empty());
+        assert!(parse_str("case x in a) b").is_err());
+    }
+
+    #[test]
+    fn test_incomplete_input() {
+        for input in ["if true; then", "echo 'abc", "ls |", "a &&", "f() {", "cat <<EOF\nhi", "echo \"$(ls", "echo a \\"] {
+            assert!(incomplete(input), "{}", input);
+        }
+        for input in ["echo hi", "if a; then b; fi", "echo a \\\\", "fi", "echo )"] {
+            assert!(!incomplete(input), "{}", input);
+        }
+    }
+}
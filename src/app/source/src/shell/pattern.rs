@@ -0,0 +1,211 @@
+// sh style pattern matching - `*`, `?`, `[abc]`, `[!a-z]` and backslash
+// escapes. used by ${x#pat} and friends, and by anything else that
+// needs to match a glob against a string
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Char(char),
+    Any,  // ?
+    Star, // *
+    Class { negate: bool, ranges: Vec<(char, char)> },
+}
+
+fn compile(pattern: &str) -> Vec<Piece> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut pieces = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                pieces.push(Piece::Char(chars[i + 1]));
+                i += 2;
+                continue;
+            }
+            '*' => {
+                // runs of stars mean the same as one
+                if pieces.last() != Some(&Piece::Star) {
+                    pieces.push(Piece::Star);
+                }
+            }
+            '?' => pieces.push(Piece::Any),
+            '[' => match compile_class(&chars, i) {
+                Some((piece, end)) => {
+                    pieces.push(piece);
+                    i = end;
+                }
+                // no closing bracket, so it's just a [
+                None => pieces.push(Piece::Char('[')),
+            },
+            c => pieces.push(Piece::Char(c)),
+        }
+        i += 1;
+    }
+    pieces
+}
+
+// parse a [...] starting at `start`, returns the piece and the index of `]`
+fn compile_class(chars: &[char], start: usize) -> Option<(Piece, usize)> {
+    let mut i = start + 1;
+    let negate = matches!(chars.get(i), Some('!') | Some('^'));
+    if negate {
+        i += 1;
+    }
+    let mut ranges = Vec::new();
+    let mut first = true;
+    while i < chars.len() {
+        let mut c = chars[i];
+        // a ] straight after the opening bracket is a literal
+        if c == ']' && !first {
+            return Some((Piece::Class { negate, ranges }, i));
+        }
+        if c == '\\' && i + 1 < chars.len() {
+            i += 1;
+            c = chars[i];
+        }
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|&e| e != ']') {
+            ranges.push((c, chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((c, c));
+            i += 1;
+        }
+        first = false;
+    }
+    None
+}
+
+fn matches_pieces(pieces: &[Piece], text: &[char]) -> bool {
+    // classic two pointer wildcard match, backtracking to the last star
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        let ok = match pieces.get(p) {
+            Some(Piece::Char(c)) => *c == text[t],
+            Some(Piece::Any) => true,
+            Some(Piece::Class { negate, ranges }) => {
+                ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&text[t])) != *negate
+            }
+            Some(Piece::Star) => {
+                star = Some((p, t));
+                p += 1;
+                continue;
+            }
+            None => false,
+        };
+        if ok {
+            p += 1;
+            t += 1;
+        } else if let Some((sp, st)) = star {
+            p = sp + 1;
+            t = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    pieces[p..].iter().all(|piece| *piece == Piece::Star)
+}
+
+/// does the whole of `text` match `pattern`
+pub fn matches(pattern: &str, text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    matches_pieces(&compile(pattern), &chars)
+}
+
+/// the plain text a pattern stands for, or None if it has wildcards
+/// in it and needs matching against something
+pub fn literal(pattern: &str) -> Option<String> {
+    compile(pattern)
+        .into_iter()
+        .map(|piece| match piece {
+            Piece::Char(c) => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// backslash any characters that would otherwise act as wildcards
+pub fn escape(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// ${x#p} / ${x##p} - drop the shortest (or longest) matching prefix
+pub fn trim_prefix(text: &str, pattern: &str, longest: bool) -> String {
+    let pieces = compile(pattern);
+    let chars: Vec<char> = text.chars().collect();
+    let mut cuts: Box<dyn Iterator<Item = usize>> = if longest {
+        Box::new((0..=chars.len()).rev())
+    } else {
+        Box::new(0..=chars.len())
+    };
+    match cuts.find(|&n| matches_pieces(&pieces, &chars[..n])) {
+        Some(n) => chars[n..].iter().collect(),
+        None => text.to_string(),
+    }
+}
+
+/// ${x%p} / ${x%%p} - drop the shortest (or longest) matching suffix
+pub fn trim_suffix(text: &str, pattern: &str, longest: bool) -> String {
+    let pieces = compile(pattern);
+    let chars: Vec<char> = text.chars().collect();
+    let mut cuts: Box<dyn Iterator<Item = usize>> = if longest {
+        Box::new(0..=chars.len())
+    } else {
+        Box::new((0..=chars.len()).rev())
+    };
+    match cuts.find(|&n| matches_pieces(&pieces, &chars[n..])) {
+        Some(n) => chars[..n].iter().collect(),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcards() {
+        assert!(matches("*.txt", "notes.txt"));
+        assert!(!matches("*.txt", "notes.txt.bak"));
+        assert!(matches("a?c", "abc"));
+        assert!(matches("*", ""));
+        assert!(matches("[abc]x", "bx"));
+        assert!(matches("[!abc]x", "dx"));
+        assert!(matches("[a-c]*", "cat"));
+        assert!(!matches("[a-c]*", "dog"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("a[", "a["));
+    }
+
+    #[test]
+    fn test_escaped_wildcards_are_literal() {
+        assert!(matches("\\*", "*"));
+        assert!(!matches("\\*", "x"));
+        assert!(matches(&escape("a*b"), "a*b"));
+    }
+
+    #[test]
+    fn test_literal() {
+        assert_eq!(literal("plain.txt").as_deref(), Some("plain.txt"));
+        assert_eq!(literal("\\*x").as_deref(), Some("*x"));
+        assert_eq!(literal("a["), Some("a[".to_string()));
+        assert_eq!(literal("*.txt"), None);
+        assert_eq!(literal("[ab]"), None);
+    }
+
+    #[test]
+    fn test_trimming() {
+        assert_eq!(trim_prefix("/usr/local/bin", "*/", false), "usr/local/bin");
+        assert_eq!(trim_prefix("/usr/local/bin", "*/", true), "bin");
+        assert_eq!(trim_suffix("archive.tar.gz", ".*", false), "archive.tar");
+        assert_eq!(trim_suffix("archive.tar.gz", ".*", true), "archive");
+        assert_eq!(trim_suffix("file", ".*", true), "file");
+    }
+}
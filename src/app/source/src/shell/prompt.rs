@@ -0,0 +1,182 @@
+// PS1/PS2 - the prompt is built from a template the way bash does it:
+// backslash escapes like \u and \w first, then $var, $(cmd) and $((...))
+// on what's left, so `PS1='$(branch) \w\$ '` can call a shell function
+
+use super::{exec, expand, lexer};
+use crate::context::TerminalContext;
+use chrono::Local;
+
+// what \h and \H show when HOSTNAME isn't set
+const DEFAULT_HOST: &str = "virt";
+
+/// the prompt to show before the next line of input. `continuing` means
+/// the lines so far leave a command unfinished, which gets $PS2 instead
+pub fn prompt(ctx: &mut TerminalContext, continuing: bool) -> String {
+    let name = if continuing { "PS2" } else { "PS1" };
+    let template = ctx.lookup_var(name).cloned().unwrap_or_default();
+    // $PROMPT_COMMAND runs before each PS1, for anything that wants to
+    // set variables for it to show - a branch name, say
+    let mut shown = String::new();
+    if !continuing {
+        if let Some(hook) = ctx.lookup_var("PROMPT_COMMAND").cloned() {
+            shown = run_hook(&hook, ctx);
+        }
+    }
+    shown.push_str(&render(&template, ctx));
+    shown
+}
+
+// anything the hook prints goes above the prompt. $? afterwards is still
+// the status of the last command the user ran
+fn run_hook(hook: &str, ctx: &mut TerminalContext) -> String {
+    let Some(registry) = ctx.registry.clone() else { return String::new() };
+    let status = ctx.last_status;
+    let output = match exec::run(hook, ctx, &registry) {
+        Ok(output) | Err(output) => output,
+    };
+    ctx.last_status = status;
+    ctx.exit_code = None;
+    if output.is_empty() {
+        output
+    } else {
+        format!("{}\n", output.trim_end_matches('\n'))
+    }
+}
+
+fn render(template: &str, ctx: &mut TerminalContext) -> String {
+    let text = decode(template, ctx);
+    // a $(...) in the prompt mustn't leave its status or errors behind
+    // for the next command to pick up
+    let status = ctx.last_status;
+    let stderr = std::mem::take(&mut ctx.pending_stderr);
+    let shown = lexer::lex_word(&text)
+        .and_then(|word| expand::expand_string(&word, ctx))
+        .unwrap_or(text);
+    ctx.last_status = status;
+    ctx.exit_code = None;
+    ctx.pending_stderr = stderr;
+    shown
+}
+
+// swap the backslash escapes for what they stand for. the results are
+// escaped again so a $ or ` in a directory name is shown, not expanded
+fn decode(template: &str, ctx: &TerminalContext) -> String {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let Some(code) = chars.next() else {
+            out.push('\\');
+            break;
+        };
+        let text = match code {
+            'u' => ctx.lookup_var("USER").cloned().unwrap_or_default(),
+            'h' => host(ctx).split('.').next().unwrap_or_default().to_string(),
+            'H' => host(ctx),
+            'w' => tilde_cwd(ctx),
+            'W' => match tilde_cwd(ctx) {
+                dir if dir == "~" || dir == "/" => dir,
+                dir => dir.rsplit('/').next().unwrap_or_default().to_string(),
+            },
+            '$' => if is_root(ctx) { "#" } else { "$" }.to_string(),
+            't' => Local::now().format("%H:%M:%S").to_string(),
+            'T' => Local::now().format("%I:%M:%S").to_string(),
+            '@' => Local::now().format("%I:%M %p").to_string(),
+            'A' => Local::now().format("%H:%M").to_string(),
+            'd' => Local::now().format("%a %b %d").to_string(),
+            '!' => (ctx.history.len() + 1).to_string(),
+            'n' => "\n".to_string(),
+            'r' => "\r".to_string(),
+            'a' => "\x07".to_string(),
+            'e' => "\x1b".to_string(),
+            '\\' => "\\".to_string(),
+            // readline's markers round non-printing bits, nothing to show
+            '[' | ']' => String::new(),
+            // \nnn is a char in octal
+            '0'..='7' => {
+                let mut digits = code.to_string();
+                while digits.len() < 3 && chars.peek().is_some_and(|c| ('0'..='7').contains(c)) {
+                    digits.extend(chars.next());
+                }
+                let n = u32::from_str_radix(&digits, 8).unwrap_or_default();
+                char::from_u32(n).map(String::from).unwrap_or_default()
+            }
+            // anything else is shown as typed, backslash and all
+            other => format!("\\{}", other),
+        };
+        for c in text.chars() {
+            if matches!(c, '$' | '`' | '\\' | '"' | '\'') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn host(ctx: &TerminalContext) -> String {
+    ctx.lookup_var("HOSTNAME").cloned().unwrap_or_else(|| DEFAULT_HOST.to_string())
+}
+
+fn is_root(ctx: &TerminalContext) -> bool {
+    ctx.lookup_var("USER").is_some_and(|user| user == "root")
+}
+
+// the cwd with $HOME shortened to ~
+fn tilde_cwd(ctx: &TerminalContext) -> String {
+    let home = ctx.lookup_var("HOME").map(|h| h.trim_end_matches('/')).unwrap_or_default();
+    if home.is_empty() {
+        return ctx.cwd.clone();
+    }
+    match ctx.cwd.strip_prefix(home) {
+        Some("") => "~".to_string(),
+        Some(rest) if rest.starts_with('/') => format!("~{}", rest),
+        _ => ctx.cwd.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::CommandRegistry;
+    use std::sync::Arc;
+
+    fn ps1(template: &str, ctx: &mut TerminalContext) -> String {
+        ctx.set_var("PS1", template);
+        prompt(ctx, false)
+    }
+
+    #[test]
+    fn test_escapes() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_var("HOSTNAME", "box.local");
+        ctx.cwd = "/home/src/a$b".to_string();
+        assert_eq!(ps1("\\u@\\h:\\w\\$ ", &mut ctx), "user@box:~/src/a$b$ ");
+        assert_eq!(ps1("[\\W] \\H", &mut ctx), "[a$b] box.local");
+        ctx.cwd = "/home".to_string();
+        ctx.set_var("USER", "root");
+        assert_eq!(ps1("\\[\\e[1m\\]\\W\\$\\101\\\\", &mut ctx), "\x1b[1m~#A\\");
+        ctx.cwd = "/".to_string();
+        assert_eq!(ps1("\\W \\q", &mut ctx), "/ \\q");
+        assert_eq!(ps1("\\t", &mut ctx).len(), 8);
+    }
+
+    #[test]
+    fn test_expansions_and_hooks() {
+        let mut ctx = TerminalContext::new();
+        ctx.set_command_registry(Arc::new(CommandRegistry::default_commands()));
+        exec::run("branch() { echo main; }; false", &mut ctx, &CommandRegistry::default_commands()).ok();
+        assert_eq!(ps1("[$?] ($(branch)) > ", &mut ctx), "[1] (main) > ");
+        assert_eq!(ctx.last_status, 1);
+
+        ctx.set_var("PROMPT_COMMAND", "dirty=*; echo hook");
+        assert_eq!(ps1("${dirty}\\$ ", &mut ctx), "hook\n*$ ");
+        assert_eq!(ctx.last_status, 1);
+
+        ctx.set_var("PS2", "... ");
+        assert_eq!(prompt(&mut ctx, true), "... ");
+    }
+}
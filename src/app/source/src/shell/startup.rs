@@ -0,0 +1,85 @@
+// startup files - /etc/profile for everyone, then ~/.shellrc for the
+// user's own aliases, functions and prompt. both go through `source`, so
+// they're just shell scripts
+
+use crate::command::{self, CommandRegistry};
+use crate::context::TerminalContext;
+
+pub const PROFILE: &str = "/etc/profile";
+
+const DEFAULT_PROFILE: &str = "\
+# /etc/profile - run at startup for every user, before ~/.shellrc
+export PATH=/bin:/usr/bin
+PS1='[virt::core] ➤ '
+PS2='> '
+";
+
+const DEFAULT_SHELLRC: &str = "\
+# ~/.shellrc - run at startup after /etc/profile. your own aliases,
+# functions and PS1 go here
+alias ll='ls -l'
+alias la='ls -a'
+";
+
+/// the startup files in the order they run, each with what to write
+/// there when it doesn't exist yet
+pub fn files(ctx: &TerminalContext) -> Vec<(String, &'static str)> {
+    let home = ctx.lookup_var("HOME").map(|h| h.trim_end_matches('/')).unwrap_or_default();
+    vec![(PROFILE.to_string(), DEFAULT_PROFILE), (format!("{}/.shellrc", home), DEFAULT_SHELLRC)]
+}
+
+/// source each startup file that exists and hand back whatever they
+/// printed. a failing line doesn't stop the rest of the file, same as
+/// bash, and a broken profile doesn't stop ~/.shellrc
+pub fn run(ctx: &mut TerminalContext, registry: &CommandRegistry) -> String {
+    let mut shown = Vec::new();
+    for (path, _) in files(ctx) {
+        if ctx.vfs.resolve_path(&path).is_none() {
+            continue;
+        }
+        let output = match command::run_command(&format!("source '{}'", path.replace('\'', "'\\''")), ctx, registry) {
+            Ok(output) | Err(output) => output,
+        };
+        if !output.is_empty() {
+            shown.push(output);
+        }
+    }
+    // the saved history comes in whatever the rc files say, once they've
+    // had a chance to point $HISTFILE somewhere else
+    ctx.load_history();
+    // the status of the last startup line isn't the user's $?
+    ctx.last_status = 0;
+    shown.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_profile_then_shellrc() {
+        let mut ctx = TerminalContext::new();
+        let registry = CommandRegistry::default_commands();
+        ctx.set_command_registry(Arc::new(CommandRegistry::default_commands()));
+        for (path, default) in files(&ctx) {
+            ctx.vfs.create_file(&path, default.as_bytes().to_vec()).unwrap();
+        }
+        assert_eq!(run(&mut ctx, &registry), "");
+        assert_eq!(ctx.aliases.get("ll").unwrap(), "ls -l");
+        assert_eq!(ctx.lookup_var("PS2").unwrap(), "> ");
+        assert_eq!(ctx.last_status, 0);
+
+        // the saved history comes back without the rc files asking for it
+        ctx.vfs.create_file("/home/.history", b"ls\npwd\n".to_vec()).unwrap();
+        run(&mut ctx, &registry);
+        assert_eq!(ctx.history, vec!["ls", "pwd"]);
+
+        // later files see what earlier ones set up
+        ctx.vfs.write_file(PROFILE, b"greet() { echo hi $1; }\nPS1='\\w$ '".to_vec()).unwrap();
+        ctx.vfs.write_file("/home/.shellrc", b"greet there\nnope".to_vec()).unwrap();
+        let output = run(&mut ctx, &registry);
+        assert!(output.starts_with("hi there\n"), "{}", output);
+        assert_eq!(ctx.lookup_var("PS1").unwrap(), "\\w$ ");
+    }
+}
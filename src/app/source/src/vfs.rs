@@ -103,6 +103,8 @@ impl VirtualFileSystem {
                             VfsNode::Symlink { target, .. } if !physical => {
                                 // swap in symlink target for current component
                                 let mut target_comps: Vec<&str> = target.trim_matches('/').split('/').filter(|c| !c.is_empty()).collect();
+                                // absolute targets start over from the root
+                                if target.starts_with('/') { node = &self.root; }
                                 components = [target_comps, components[1..].to_vec()].concat();
                                 seen += 1;
                                 if seen > 16 { return None; } // bail if too many redirects
@@ -3,6 +3,7 @@
 export interface CommandResponse {
   success: boolean;
   output: string;
+  special_action?: 'clear_screen' | 'continue_input';
 }
 
 export interface FileInfo {
@@ -79,6 +80,11 @@ export interface NanoEditorState {
 export class Terminal {
   constructor();
   execute_command(input: string): CommandResponse;
+  search_history(prefix: string): string[];
+  render_prompt(): string;
+  interrupt(): string;
+  tick(): void;
+  complete(line: string, cursor: number): { start: number; candidates: string[] };
   get_current_directory(): string;
   list_files(path?: string): ListFilesResponse;
   read_file(path: string): ReadFileResponse;